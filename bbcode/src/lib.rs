@@ -14,6 +14,35 @@ pub struct Document<'a> {
     pub children: Vec<Node<'a>>,
 }
 
+impl Document<'_> {
+    /// The text a reader sees, without tags. Image and video tags are dropped since
+    /// their content is a URL or id, and blocks start on a new line.
+    pub fn plain_text(&self) -> String {
+        let mut text = String::new();
+        push_text(&self.children, &mut text);
+        text
+    }
+}
+
+fn push_text(nodes: &[Node<'_>], text: &mut String) {
+    for node in nodes {
+        match node {
+            Node::Text(s) => text.push_str(s),
+            Node::Element(e) if e.is("img") || e.is("youtube") => {}
+            Node::Element(e) => {
+                let block = e.tag == "*"
+                    || ["li", "list", "ul", "ol", "quote", "code", "indent"]
+                        .iter()
+                        .any(|tag| e.is(tag));
+                if block && !text.is_empty() && !text.ends_with('\n') {
+                    text.push('\n');
+                }
+                push_text(&e.children, text);
+            }
+        }
+    }
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum Node<'a> {
     Text(&'a str),
@@ -51,3 +80,14 @@ pub fn unquote(s: &str) -> &str {
         s
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn plain_text_drops_tags() {
+        let doc = parse("[b]Writ[/b] helper[list][*]one[*]two[/list][img]https://x.y/a.png[/img]");
+        assert_eq!(doc.plain_text(), "Writ helper\none\ntwo");
+    }
+}
@@ -0,0 +1,75 @@
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::AddonRef;
+
+use crate::output::{Outcome, OutputFormat, print_changes, print_json};
+use crate::{Result, Wait, confirm};
+
+#[derive(Parser)]
+pub struct AutoremoveCommand {
+    #[clap(long)]
+    remove: Option<bool>,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
+}
+
+impl AutoremoveCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let candidates = service.get_autoremove_candidates().wait().await?;
+
+        if candidates.is_empty() && !format.is_json() {
+            println!("No unused libraries");
+            return Ok(());
+        }
+        if self.dry_run {
+            self.show_addons_to_remove(format, &candidates);
+            print_changes(format, &service.plan_autoremove().wait().await?);
+            return Ok(());
+        }
+        let remove = match self.remove {
+            Some(remove) => remove,
+            // json without --remove lists the libraries, like --remove false
+            None if format.is_json() => false,
+            None => {
+                self.show_addons_to_remove(format, &candidates);
+                candidates.is_empty()
+                    || confirm("Do you want to remove these libraries?", format).is_ok()
+            }
+        };
+        if !remove {
+            if format.is_json() {
+                print_json(&candidates);
+            } else if self.remove.is_some() {
+                self.show_addons_to_remove(format, &candidates);
+            }
+            return Ok(());
+        }
+
+        let removed = service.autoremove().wait().await?;
+        if !format.is_json() {
+            for addon in removed.iter() {
+                println!("{} {} removed!", "✓".green(), addon.name)
+            }
+        }
+        Outcome {
+            removed,
+            errors: service.errors(),
+            ..Default::default()
+        }
+        .finish(format, candidates.len())
+    }
+
+    fn show_addons_to_remove(&self, format: OutputFormat, addons: &[AddonRef]) {
+        if format.is_json() {
+            return;
+        }
+        println!("{} Unused libraries:", "🗑".red());
+
+        for addon in addons {
+            println!("- {} ({})", addon.name, addon.id)
+        }
+
+        println!()
+    }
+}
@@ -0,0 +1,253 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use colored::*;
+use eso_addons_core::config::Config;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::backup::BackupOptions;
+use eso_addons_core::service::result::SnapshotTrigger;
+use serde::Serialize;
+
+use crate::output::{OutputFormat, check_failed, print_changes, print_json};
+use crate::{Result, Wait, confirm};
+
+#[derive(Parser)]
+pub struct BackupsCommand {
+    #[clap(subcommand)]
+    subcmd: BackupsSubCommand,
+}
+
+#[derive(Parser)]
+enum BackupsSubCommand {
+    #[clap(about = "List backups, newest first")]
+    List,
+    #[clap(about = "Back up installed addons and SavedVariables now")]
+    Create,
+    #[clap(about = "Compare a backup with the installed addons")]
+    Diff { backup: String },
+    #[clap(about = "Reinstall the addons and restore the files of a backup")]
+    Restore {
+        backup: String,
+        #[clap(long, help = "Only show what would change")]
+        dry_run: bool,
+        #[clap(long, short, help = "Restore without asking")]
+        yes: bool,
+    },
+}
+
+#[derive(Parser)]
+pub struct BackupCommand {
+    #[clap(help = "File to write, a zip archive when game files are included")]
+    file: PathBuf,
+    #[clap(long, help = "Include the SavedVariables folder")]
+    saved_variables: bool,
+    #[clap(long, help = "Include AddOnSettings.txt")]
+    addon_settings: bool,
+}
+
+/// `backup --format json` output.
+#[derive(Serialize)]
+struct BackupOutput<'a> {
+    written: &'a PathBuf,
+}
+
+impl BackupCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let options = BackupOptions {
+            saved_variables: self.saved_variables,
+            addon_settings: self.addon_settings,
+        };
+        service
+            .backup_data(self.file.clone(), options)
+            .wait()
+            .await?;
+        if format.is_json() {
+            print_json(&BackupOutput {
+                written: &self.file,
+            });
+        } else {
+            println!("{} Backed up to {}", "✔".green(), self.file.display());
+        }
+        Ok(())
+    }
+}
+
+#[derive(Parser)]
+pub struct RestoreCommand {
+    #[clap(help = "Backup file written by backup, or a name in the backup dir")]
+    file: String,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
+    #[clap(long, short, help = "Restore without asking")]
+    yes: bool,
+}
+
+impl RestoreCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        restore(
+            service,
+            backup_path(&self.file),
+            self.dry_run,
+            self.yes,
+            format,
+        )
+        .await
+    }
+}
+
+impl BackupsCommand {
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        match &self.subcmd {
+            BackupsSubCommand::List => {
+                let snapshots = service.list_snapshots().wait().await?;
+                match format {
+                    OutputFormat::Json => print_json(&snapshots),
+                    OutputFormat::Plain => {
+                        for snapshot in snapshots.iter() {
+                            println!(
+                                "{}\t{}\t{}\t{}\t{}",
+                                snapshot.created.to_rfc3339(),
+                                snapshot.path.display(),
+                                snapshot.trigger.as_str(),
+                                snapshot.addons,
+                                snapshot.size
+                            );
+                        }
+                    }
+                    OutputFormat::Table => {
+                        if snapshots.is_empty() {
+                            println!("No backups in {}", Config::default_backup_dir().display());
+                        }
+                        for snapshot in snapshots.iter() {
+                            println!(
+                                "{} {} ({}, {} addons, {:.1} MB)",
+                                snapshot.created.format("%Y-%m-%d %H:%M UTC"),
+                                snapshot
+                                    .path
+                                    .file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy(),
+                                snapshot.trigger.as_str(),
+                                snapshot.addons,
+                                snapshot.size as f64 / 1_000_000.0
+                            );
+                        }
+                    }
+                }
+            }
+            BackupsSubCommand::Create => {
+                let snapshot = service
+                    .create_snapshot(SnapshotTrigger::Manual)
+                    .wait()
+                    .await?;
+                service.config.backup_last = Some(snapshot.created);
+                service.save_config();
+                if format.is_json() {
+                    print_json(&snapshot);
+                } else {
+                    println!("{} Backed up to {}", "✔".green(), snapshot.path.display());
+                }
+            }
+            BackupsSubCommand::Diff { backup } => {
+                let diff = service.diff_backup(backup_path(backup)).wait().await?;
+                if format.is_json() {
+                    print_json(&diff);
+                    return Ok(());
+                }
+                if diff.removed.is_empty() && diff.added.is_empty() && diff.changed.is_empty() {
+                    println!("Same addons and versions as installed now");
+                }
+                for addon in diff.removed.iter() {
+                    println!("{} {} (no longer installed)", "-".red(), addon.name);
+                }
+                for addon in diff.added.iter() {
+                    println!("{} {} (installed since)", "+".green(), addon.name);
+                }
+                for change in diff.changed.iter() {
+                    println!(
+                        "{} {} {} -> {}",
+                        "~".yellow(),
+                        change.addon.name,
+                        change.backup_version,
+                        change.installed_version
+                    );
+                }
+            }
+            BackupsSubCommand::Restore {
+                backup,
+                dry_run,
+                yes,
+            } => restore(service, backup_path(backup), *dry_run, *yes, format).await?,
+        }
+        Ok(())
+    }
+}
+
+/// Shows what restoring `file` reinstalls and writes, then restores it once confirmed.
+async fn restore(
+    service: &AddonService,
+    file: PathBuf,
+    dry_run: bool,
+    yes: bool,
+    format: OutputFormat,
+) -> Result<()> {
+    let plan = service.get_restore_plan(file).wait().await?;
+    if !format.is_json() {
+        for entry in plan.to_install.iter() {
+            println!(
+                "{} {} {}",
+                "+".green(),
+                entry.addon.name,
+                entry.available_version
+            );
+        }
+        for entry in plan.present.iter() {
+            println!("{} {} (already present)", "=".normal(), entry.addon.name);
+        }
+        for entry in plan.unavailable.iter() {
+            println!(
+                "{} {} (no longer available)",
+                "!".yellow(),
+                entry.addon.name
+            );
+        }
+        for file in plan.files.iter() {
+            let label = if file.conflict { "replace" } else { "write" };
+            println!("{} {} ({label})", "*".cyan(), file.path.display());
+        }
+    }
+    if dry_run {
+        print_changes(format, &service.plan_restore(plan).wait().await?);
+        return Ok(());
+    }
+    if !yes {
+        confirm("Restore this backup?", format)?;
+    }
+
+    let total = plan.to_install.len();
+    let report = service.restore_plan(plan).wait().await?;
+    if format.is_json() {
+        print_json(&report);
+    } else {
+        for addon in report.installed.iter() {
+            println!("{} Installed {}!", "✔".green(), addon.name);
+        }
+        for (addon, error) in report.failed.iter() {
+            eprintln!("{} {}: {}", "✗".red(), addon.name, error);
+        }
+        if report.files_restored > 0 {
+            println!("{} Restored {} files", "✔".green(), report.files_restored);
+        }
+    }
+    check_failed(report.failed.len(), total)
+}
+
+/// A file name in the backup dir, or a path to a backup anywhere else.
+fn backup_path(backup: &str) -> PathBuf {
+    let in_backup_dir = Config::default_backup_dir().join(backup);
+    if in_backup_dir.is_file() {
+        in_backup_dir
+    } else {
+        PathBuf::from(backup)
+    }
+}
@@ -1,82 +1,66 @@
 use clap::Parser;
 use colored::*;
-use eso_addons_core::{
-    addons::{Addon, Manager},
-    config::Config,
-};
+use eso_addons_core::service::AddonService;
+use serde::Serialize;
+
+use crate::output::{OutputFormat, print_changes, print_json};
+use crate::{Result, Wait, confirm};
+
 #[derive(Parser)]
 pub struct CleanCommand {
-    #[clap(long)]
-    remove: Option<bool>,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
+    #[clap(long, short, help = "Remove without asking")]
+    yes: bool,
 }
 
-impl CleanCommand {
-    pub fn run(
-        &mut self,
-        config: &Config,
-        addon_manager: &Manager,
-    ) -> Result<(), Box<dyn std::error::Error>> {
-        let desired_addons = &config.addons;
-        let installed_addons_list = addon_manager.get_addons()?;
-
-        let unmanaged = eso_addons_core::get_unmanaged_addons(
-            &desired_addons,
-            installed_addons_list.addons.iter(),
-        );
+/// `clean --format json` output.
+#[derive(Serialize)]
+struct CleanOutput {
+    /// Dirs deleted from the AddOns folder
+    removed: Vec<String>,
+}
 
-        if unmanaged.len() > 0 {
-            match self.remove {
-                Some(true) => self.remove_addons(addon_manager, unmanaged.iter())?,
-                Some(false) => self.show_addons_to_remove(unmanaged.iter()),
-                None => {
-                    self.show_addons_to_remove(unmanaged.iter());
-                    if self.ask_for_remove_confirmation()? {
-                        println!("");
-                        self.remove_addons(addon_manager, unmanaged.iter())?;
-                    }
+impl CleanCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let untracked = service.get_untracked_dirs().wait().await?;
+        // JSON output goes on to print an empty plan or result
+        if !format.is_json() {
+            if untracked.is_empty() {
+                println!("Nothing to clean");
+                return Ok(());
+            }
+            println!("{} Addons to remove:", "🗑".red());
+            for dir in untracked.iter() {
+                let size = format!("{:.1} MB", dir.size as f64 / 1_000_000.0);
+                match dir.providers.first() {
+                    Some(addon) => println!(
+                        "- {} ({size}, shipped by {} {})",
+                        dir.dir, addon.name, addon.id
+                    ),
+                    None => println!("- {} ({size})", dir.dir),
                 }
             }
-        } else {
-            println!("Nothing to clean");
+            println!();
         }
-        Ok(())
-    }
 
-    fn ask_for_remove_confirmation(&mut self) -> Result<bool, Box<dyn std::error::Error>> {
-        let question = requestty::Question::confirm("remove")
-            .message("Do you want to remove these addons?")
-            .build();
-
-        let answer = requestty::prompt_one(question)?;
-        Ok(answer.as_bool().unwrap_or(false))
-    }
-
-    fn show_addons_to_remove<'a, I>(&self, addons: I)
-    where
-        I: Iterator<Item = &'a &'a Addon>,
-    {
-        println!("{} Addons to remove:", "🗑".red());
-
-        for addon in addons {
-            println!("- {}", addon.name)
+        let dirs: Vec<String> = untracked.into_iter().map(|d| d.dir).collect();
+        if self.dry_run {
+            print_changes(format, &service.plan_remove_untracked(dirs).wait().await?);
+            return Ok(());
         }
-
-        println!("")
-    }
-
-    fn remove_addons<'a, I>(
-        &self,
-        addon_manager: &Manager,
-        addons: I,
-    ) -> Result<(), Box<dyn std::error::Error>>
-    where
-        I: Iterator<Item = &'a &'a Addon>,
-    {
-        for addon in addons {
-            addon_manager.delete_addon(addon)?;
-            println!("{} {} removed!", "✓".green(), addon.name)
+        if !self.yes && !dirs.is_empty() {
+            confirm("Do you want to remove these addons?", format)?;
         }
 
+        let removed = service.remove_untracked_dirs(dirs).wait().await?;
+        if format.is_json() {
+            print_json(&CleanOutput { removed });
+        } else {
+            for dir in removed.iter() {
+                println!("{} {} removed!", "✓".green(), dir);
+            }
+        }
         Ok(())
     }
 }
@@ -0,0 +1,295 @@
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonDepOption, AddonRef, DepStatus, Resolution};
+use serde::Serialize;
+
+use crate::output::{Outcome, OutputFormat, print_json};
+use crate::{Result, Wait, select};
+
+#[derive(Parser)]
+pub struct DepsCommand {
+    #[clap(subcommand)]
+    subcmd: DepsSubCommand,
+}
+
+#[derive(Parser)]
+enum DepsSubCommand {
+    #[clap(about = "List dirs installed addons require that nothing provides, with candidates")]
+    Missing {
+        #[clap(long, help = "List missing optional dependencies instead")]
+        optional: bool,
+    },
+    #[clap(about = "Show what an installed addon depends on and what depends on it")]
+    Show { addon_id: i32 },
+    #[clap(about = "Treat a dir as provided, it's no longer reported missing")]
+    Ignore { dir: String },
+    #[clap(about = "Treat a dir as provided by an addon that doesn't ship it")]
+    Satisfy { dir: String, addon_id: i32 },
+    #[clap(about = "Remove the manual override of a dir")]
+    Revoke { dir: String },
+    #[clap(about = "Install an addon for each missing dependency, or ignore it")]
+    Resolve {
+        #[clap(long, help = "Resolve optional dependencies instead")]
+        optional: bool,
+        #[clap(long, help = "Install the best candidate of each dir without asking")]
+        accept_best: bool,
+    },
+}
+
+/// A missing dir with the catalog addons providing it, best candidate first.
+#[derive(Serialize)]
+struct MissingDep {
+    missing_dir: String,
+    /// Names of the installed addons requiring it, comma separated
+    required_by: String,
+    candidates: Vec<AddonRef>,
+}
+
+impl DepsCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        match &self.subcmd {
+            DepsSubCommand::Missing { optional } => {
+                let missing = get_missing(service, *optional).await?;
+                match format {
+                    OutputFormat::Json => print_json(&missing),
+                    OutputFormat::Plain => {
+                        for dep in missing.iter() {
+                            let candidates: Vec<String> =
+                                dep.candidates.iter().map(|a| a.id.to_string()).collect();
+                            println!(
+                                "{}\t{}\t{}",
+                                dep.missing_dir,
+                                dep.required_by,
+                                candidates.join(",")
+                            );
+                        }
+                    }
+                    OutputFormat::Table => {
+                        if missing.is_empty() {
+                            println!("No missing dependencies");
+                        }
+                        for dep in missing.iter() {
+                            println!(
+                                "{} {} (required by {})",
+                                "!".yellow(),
+                                dep.missing_dir.bold(),
+                                dep.required_by
+                            );
+                            if dep.candidates.is_empty() {
+                                println!("    no addon on ESOUI provides it");
+                            }
+                            for (i, addon) in dep.candidates.iter().enumerate() {
+                                let best = if i == 0 { " (best)" } else { "" };
+                                println!("    {:>5} {}{best}", addon.id, addon.name);
+                            }
+                        }
+                    }
+                }
+            }
+            DepsSubCommand::Show { addon_id } => {
+                let view = service.get_addon_dependency_view(*addon_id).wait().await?;
+                if format.is_json() {
+                    print_json(&view);
+                    return Ok(());
+                }
+                if view.forward.is_empty() && view.optional.is_empty() {
+                    println!("No dependencies");
+                }
+                for dep in view.forward.iter() {
+                    println!("{}", dep_line(dep));
+                }
+                for dep in view.optional.iter() {
+                    println!("{} (optional)", dep_line(dep));
+                }
+                if !view.dependents.is_empty() {
+                    println!();
+                    println!("Required by:");
+                    for addon in view.dependents.iter() {
+                        println!("- {} ({})", addon.name, addon.id);
+                    }
+                }
+            }
+            DepsSubCommand::Ignore { dir } => {
+                service.set_dep_ignored(dir.clone()).wait().await?;
+                print_override(format, dir, true, None);
+            }
+            DepsSubCommand::Satisfy { dir, addon_id } => {
+                service
+                    .set_dep_satisfied_by(dir.clone(), *addon_id)
+                    .wait()
+                    .await?;
+                print_override(format, dir, false, Some(*addon_id));
+            }
+            DepsSubCommand::Revoke { dir } => {
+                service.revoke_dep_override(dir.clone()).wait().await?;
+                print_override(format, dir, false, None);
+            }
+            DepsSubCommand::Resolve {
+                optional,
+                accept_best,
+            } => {
+                let missing = get_missing(service, *optional).await?;
+                if missing.is_empty() && !format.is_json() {
+                    println!("No missing dependencies");
+                    return Ok(());
+                }
+                let mut outcome = Outcome::default();
+                let mut total = 0;
+                for dep in missing.into_iter() {
+                    let choice = if *accept_best {
+                        dep.candidates.first().cloned().map(Choice::Install)
+                    } else {
+                        ask_choice(&dep)
+                    };
+                    match choice {
+                        // one addon often ships several of the missing dirs
+                        Some(Choice::Install(addon))
+                            if outcome.installed.iter().any(|a| a.id == addon.id) => {}
+                        Some(Choice::Install(addon)) => {
+                            total += 1;
+                            let result = service
+                                .install_dep_suggestions(vec![(dep.missing_dir.clone(), addon.id)])
+                                .wait()
+                                .await;
+                            match result {
+                                Ok(()) => {
+                                    if !format.is_json() {
+                                        println!(
+                                            "{} Installed {} for {}",
+                                            "✔".green(),
+                                            addon.name,
+                                            dep.missing_dir
+                                        );
+                                    }
+                                    outcome.installed.push(addon);
+                                }
+                                Err(e) => service.record_error(
+                                    format!("Error installing {} (#{})", addon.name, addon.id),
+                                    e,
+                                ),
+                            }
+                        }
+                        Some(Choice::Ignore) => {
+                            service
+                                .set_dep_ignored(dep.missing_dir.clone())
+                                .wait()
+                                .await?;
+                            if !format.is_json() {
+                                println!("{} Ignoring {}", "✔".green(), dep.missing_dir);
+                            }
+                            outcome.ignored.push(dep.missing_dir);
+                        }
+                        None => {}
+                    }
+                }
+                outcome.errors = service.errors();
+                outcome.finish(format, total)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+enum Choice {
+    Install(AddonRef),
+    Ignore,
+}
+
+/// Offers the candidates of `dep`, ignoring it, or leaving it for later.
+fn ask_choice(dep: &MissingDep) -> Option<Choice> {
+    let mut choices: Vec<String> = dep
+        .candidates
+        .iter()
+        .map(|a| format!("Install {} ({})", a.name, a.id))
+        .collect();
+    choices.push("Ignore it".to_owned());
+    choices.push("Skip".to_owned());
+    let message = format!("{} is required by {}", dep.missing_dir, dep.required_by);
+    match select(&message, choices)? {
+        i if i < dep.candidates.len() => Some(Choice::Install(dep.candidates[i].clone())),
+        i if i == dep.candidates.len() => Some(Choice::Ignore),
+        _ => None,
+    }
+}
+
+/// Missing dirs along with their candidates, in the order the service ranks them.
+async fn get_missing(service: &AddonService, optional: bool) -> Result<Vec<MissingDep>> {
+    let options: Vec<AddonDepOption> = if optional {
+        service.get_optional_dependency_options().wait().await?
+    } else {
+        service.get_missing_dependency_options().wait().await?
+    };
+    let mut missing: Vec<MissingDep> = vec![];
+    for option in options {
+        if missing
+            .last()
+            .is_none_or(|dep| dep.missing_dir != option.missing_dir)
+        {
+            missing.push(MissingDep {
+                missing_dir: option.missing_dir.clone(),
+                required_by: option.required_by.clone(),
+                candidates: vec![],
+            });
+        }
+        if let (Some(id), Some(name), Some(dep)) =
+            (option.option_id, option.option_name, missing.last_mut())
+            && !dep.candidates.iter().any(|a| a.id == id)
+        {
+            dep.candidates.push(AddonRef { id, name });
+        }
+    }
+    Ok(missing)
+}
+
+fn dep_line(dep: &DepStatus) -> String {
+    match &dep.resolution {
+        Resolution::Installed(addon) => {
+            format!("{} {} ({})", "✔".green(), dep.dep_dir, addon.name)
+        }
+        Resolution::SatisfiedBy(addon) => format!(
+            "{} {} (satisfied by {})",
+            "✔".green(),
+            dep.dep_dir,
+            addon.name
+        ),
+        Resolution::Ignored => format!("{} {} (ignored)", "-".normal(), dep.dep_dir),
+        Resolution::Unresolved { suggestions } => {
+            let names: Vec<&str> = suggestions.iter().map(|a| a.name.as_str()).collect();
+            if names.is_empty() {
+                format!("{} {} (missing)", "✗".red(), dep.dep_dir)
+            } else {
+                format!(
+                    "{} {} (missing, try {})",
+                    "✗".red(),
+                    dep.dep_dir,
+                    names.join(", ")
+                )
+            }
+        }
+    }
+}
+
+/// `deps ignore|satisfy|revoke --format json` output, the override of `dir` now.
+#[derive(Serialize)]
+struct OverrideOutput<'a> {
+    dir: &'a str,
+    ignore: bool,
+    satisfied_by: Option<i32>,
+}
+
+fn print_override(format: OutputFormat, dir: &str, ignore: bool, satisfied_by: Option<i32>) {
+    if format.is_json() {
+        print_json(&OverrideOutput {
+            dir,
+            ignore,
+            satisfied_by,
+        });
+        return;
+    }
+    match (ignore, satisfied_by) {
+        (true, _) => println!("{} Ignoring {dir}", "✔".green()),
+        (false, Some(id)) => println!("{} {dir} is provided by {id}", "✔".green()),
+        (false, None) => println!("{} Removed the override of {dir}", "✔".green()),
+    }
+}
@@ -0,0 +1,52 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{ArgEnum, Parser};
+use colored::*;
+use eso_addons_core::export::ExportFormat;
+use eso_addons_core::service::AddonService;
+
+use crate::{Result, Wait};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum ExportFormatArg {
+    Minion,
+    Urls,
+    Csv,
+    Markdown,
+}
+
+impl From<ExportFormatArg> for ExportFormat {
+    fn from(format: ExportFormatArg) -> Self {
+        match format {
+            ExportFormatArg::Minion => ExportFormat::Minion,
+            ExportFormatArg::Urls => ExportFormat::Urls,
+            ExportFormatArg::Csv => ExportFormat::Csv,
+            ExportFormatArg::Markdown => ExportFormat::Markdown,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct ExportCommand {
+    #[clap(long, short, arg_enum, default_value = "minion")]
+    format: ExportFormatArg,
+    #[clap(long, short, help = "Write the list to a file instead of stdout")]
+    output: Option<PathBuf>,
+}
+
+impl ExportCommand {
+    pub async fn run(&self, service: &AddonService) -> Result<()> {
+        let contents = service.export_installed(self.format.into()).wait().await?;
+
+        match &self.output {
+            Some(path) => {
+                fs::write(path, contents)
+                    .map_err(|e| format!("Unable to write {}: {e}", path.display()))?;
+                println!("{} Wrote {}", "✔".green(), path.display());
+            }
+            None => println!("{contents}"),
+        }
+        Ok(())
+    }
+}
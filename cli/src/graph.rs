@@ -0,0 +1,42 @@
+use std::fs;
+use std::path::PathBuf;
+
+use clap::{ArgEnum, Parser};
+use colored::*;
+use eso_addons_core::service::AddonService;
+
+use crate::{Result, Wait};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum GraphFormat {
+    Dot,
+    Json,
+}
+
+#[derive(Parser)]
+pub struct GraphCommand {
+    #[clap(long, short, arg_enum, default_value = "dot")]
+    format: GraphFormat,
+    #[clap(long, short, help = "Write the graph to a file instead of stdout")]
+    output: Option<PathBuf>,
+}
+
+impl GraphCommand {
+    pub async fn run(&self, service: &AddonService) -> Result<()> {
+        let graph = service.get_dependency_graph().wait().await?;
+        let contents = match self.format {
+            GraphFormat::Dot => graph.to_dot(),
+            GraphFormat::Json => graph.to_json()?,
+        };
+
+        match &self.output {
+            Some(path) => {
+                fs::write(path, contents)
+                    .map_err(|e| format!("Unable to write {}: {e}", path.display()))?;
+                println!("{} Wrote {}", "✔".green(), path.display());
+            }
+            None => print!("{contents}"),
+        }
+        Ok(())
+    }
+}
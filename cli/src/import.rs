@@ -0,0 +1,138 @@
+use std::fs;
+use std::io::{self, Read};
+use std::path::Path;
+
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonRef, ChangePlan};
+
+use crate::output::{Outcome, OutputFormat, print_changes};
+use crate::{Result, Wait, confirm, installed_steps};
+
+#[derive(Parser)]
+pub struct ImportCommand {
+    #[clap(help = "File with ESOUI URLs, addon ids or folder names, - for stdin")]
+    file: String,
+    #[clap(
+        long,
+        conflicts_with = "dry-run",
+        help = "Install the addons of a Minion backup, BU-addons.txt"
+    )]
+    minion: bool,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
+    #[clap(long, short, help = "Install without asking")]
+    yes: bool,
+}
+
+impl ImportCommand {
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        if self.minion {
+            return self.import_minion(service, format).await;
+        }
+        let text = if self.file == "-" {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            text
+        } else {
+            fs::read_to_string(&self.file)?
+        };
+
+        let plan = service.get_import_plan(text).wait().await?;
+        if !format.is_json() {
+            for imported in plan.addons.iter() {
+                if imported.installed {
+                    println!("{} {} (installed)", "=".normal(), imported.addon.name);
+                } else {
+                    println!("{} {}", "+".green(), imported.addon.name);
+                }
+            }
+            for source in plan.unresolved.iter() {
+                println!("{} No addon found for {}", "!".yellow(), source);
+            }
+        }
+        if plan.to_install().next().is_none() {
+            match format {
+                OutputFormat::Json if self.dry_run => print_changes(format, &ChangePlan::default()),
+                OutputFormat::Json => return Outcome::default().finish(format, 0),
+                _ => println!("Nothing to install"),
+            }
+            return Ok(());
+        }
+        if self.dry_run {
+            let ids: Vec<i32> = plan.to_install().map(|a| a.addon.id).collect();
+            print_changes(format, &service.plan_install(ids, false).wait().await?);
+            if !format.is_json() {
+                println!("Libraries these addons require are resolved when installing");
+            }
+            return Ok(());
+        }
+        if !self.yes {
+            confirm("Install these addons?", format)?;
+        }
+
+        let installed = service.import_plan(plan).wait().await?;
+        if !format.is_json() {
+            for failed in installed.failed.iter() {
+                println!("{} {}: {}", "✗".red(), failed.label(), failed.error);
+            }
+        }
+        let outcome = Outcome {
+            installed: installed_steps(service, &installed).await?,
+            errors: service.errors(),
+            ..Default::default()
+        };
+        if !format.is_json() {
+            if outcome.errors.is_empty() {
+                println!(
+                    "{} Imported {} addons, including required libraries",
+                    "✔".green(),
+                    outcome.installed.len()
+                );
+            } else {
+                println!(
+                    "{} Imported {} of {} addons, including required libraries",
+                    "!".yellow(),
+                    outcome.installed.len(),
+                    installed.addon_count()
+                );
+            }
+        }
+        outcome.finish(format, installed.addon_count())
+    }
+
+    /// Minion backups list ids only, they're installed one by one without a plan.
+    async fn import_minion(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        if !self.yes {
+            confirm("Install the addons of this Minion backup?", format)?;
+        }
+        let before = service.get_installed_addons().wait().await?;
+        service
+            .import_minion_file(Path::new(&self.file))
+            .wait()
+            .await?;
+        let outcome = Outcome {
+            installed: service
+                .get_installed_addons()
+                .wait()
+                .await?
+                .into_iter()
+                .filter(|a| !before.iter().any(|b| b.id == a.id))
+                .map(|a| AddonRef {
+                    id: a.id,
+                    name: a.name,
+                })
+                .collect(),
+            errors: service.errors(),
+            ..Default::default()
+        };
+        if !format.is_json() {
+            for addon in outcome.installed.iter() {
+                println!("{} Installed {}!", "✔".green(), addon.name);
+            }
+        }
+        let total = outcome.installed.len() + outcome.errors.len();
+        outcome.finish(format, total)
+    }
+}
@@ -1,92 +1,151 @@
-use std::collections::BTreeMap;
+use std::collections::HashMap;
 
+use clap::Parser;
 use colored::*;
-use eso_addons_core::{addons::Manager, config::Config};
-use prettytable::{format, Table};
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonShowDetails, UntrackedDir};
+use prettytable::{Table, format, row};
+use serde::Serialize;
 
-use super::Result;
+use crate::output::{OutputFormat, print_json};
+use crate::{Result, Wait};
 
 #[derive(Parser)]
-pub struct ListCommand {}
-
-impl ListCommand {
-    pub fn run(&self, addon_manager: &Manager, config: &Config) -> Result<()> {
-        let mut table = Table::new();
-
-        let mut addon_status: BTreeMap<String, Vec<String>> = BTreeMap::new();
-
-        let format = format::FormatBuilder::new()
-            .column_separator('|')
-            .borders('|')
-            .padding(1, 1)
-            .separators(
-                &[
-                    format::LinePosition::Top,
-                    format::LinePosition::Bottom,
-                    format::LinePosition::Title,
-                ],
-                format::LineSeparator::new('-', '+', '+', '+'),
-            )
-            .build();
-        table.set_format(format);
-
-        table.set_titles(row!["Name".bold(), "Status".bold()]);
-
-        let desired_addons = &config.addons;
-        let installed_addons_list = addon_manager.get_addons()?;
-
-        for addon in desired_addons {
-            addon_status.insert(addon.name.clone(), vec![]);
+pub struct ListCommand {
+    #[clap(long, help = "Leave out dirs in the AddOns folder that aren't tracked")]
+    tracked_only: bool,
+}
 
-            match installed_addons_list
-                .addons
-                .iter()
-                .find(|a| a.name == addon.name)
-            {
-                Some(addon) => addon_status
-                    .get_mut(&addon.name)
-                    .map(|x| x.push("INSTALLED".green().to_string())),
-                None => addon_status
-                    .get_mut(&addon.name)
-                    .map(|x| x.push("NOT INSTALLED".truecolor(200, 200, 0).to_string())),
-            };
-        }
+/// `list --format json` output.
+#[derive(Serialize)]
+struct ListOutput {
+    addons: Vec<ListedAddon>,
+    /// Left out with `--tracked-only`
+    untracked: Vec<UntrackedDir>,
+}
 
-        for addon in
-            eso_addons_core::get_missing_dependencies(&installed_addons_list.addons).into_iter()
-        {
-            if !addon_status.contains_key(&addon) {
-                addon_status.insert(addon.clone(), vec![]);
-            }
+#[derive(Serialize)]
+struct ListedAddon {
+    #[serde(flatten)]
+    addon: AddonShowDetails,
+    outdated: bool,
+    /// Required dirs that nothing installed provides
+    missing_dependencies: Vec<String>,
+}
 
-            addon_status
-                .get_mut(&addon)
-                .map(|x| x.push("MISSING".red().to_string()));
-        }
-        for addon in
-            eso_addons_core::get_unused_dependencies(&installed_addons_list.addons, desired_addons)
-        {
-            if !addon_status.contains_key(&addon) {
-                addon_status.insert(addon.clone(), vec![]);
+impl ListCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        // dirs missing for each installed addon, by addon name
+        let mut missing: HashMap<String, Vec<String>> = HashMap::new();
+        for option in service.get_missing_dependency_options().wait().await? {
+            for name in option.required_by.split(", ") {
+                let dirs = missing.entry(name.to_owned()).or_default();
+                if !dirs.contains(&option.missing_dir) {
+                    dirs.push(option.missing_dir.clone());
+                }
             }
-
-            addon_status
-                .get_mut(&addon)
-                .map(|x| x.push("UNUSED".truecolor(130, 130, 130).to_string()));
         }
 
-        for (k, v) in addon_status {
-            let status = v.join(", ");
-            table.add_row(row![k, status]);
+        let mut addons = service.get_installed_addons().wait().await?;
+        addons.sort_by_key(|a| a.name.to_lowercase());
+        let addons: Vec<ListedAddon> = addons
+            .into_iter()
+            .map(|addon| ListedAddon {
+                outdated: addon.is_upgradable(),
+                missing_dependencies: missing.remove(&addon.name).unwrap_or_default(),
+                addon,
+            })
+            .collect();
+        let untracked = if self.tracked_only {
+            vec![]
+        } else {
+            service.get_untracked_dirs().wait().await?
+        };
+
+        match format {
+            OutputFormat::Json => print_json(&ListOutput { addons, untracked }),
+            OutputFormat::Plain => {
+                for listed in addons.iter() {
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        listed.addon.name,
+                        listed
+                            .addon
+                            .installed_version
+                            .as_deref()
+                            .unwrap_or_default(),
+                        listed.addon.version,
+                        status(listed).join(", ")
+                    );
+                }
+                for dir in untracked.iter() {
+                    println!("{}\t\t\tUNTRACKED", dir.dir);
+                }
+            }
+            OutputFormat::Table => print_table(&addons, &untracked),
         }
+        Ok(())
+    }
+}
 
-        table.printstd();
-
-        for err in installed_addons_list.errors {
-            let msg = format!("WARNING: {}", err);
-            println!("{}", msg.yellow());
-        }
+fn status(listed: &ListedAddon) -> Vec<String> {
+    let mut status = vec![];
+    if listed.outdated {
+        status.push("OUTDATED".truecolor(200, 200, 0).to_string());
+    }
+    if !listed.missing_dependencies.is_empty() {
+        status.push(
+            format!("MISSING {}", listed.missing_dependencies.join(", "))
+                .red()
+                .to_string(),
+        );
+    }
+    if status.is_empty() {
+        status.push("OK".green().to_string());
+    }
+    status
+}
 
-        Ok(())
+fn print_table(addons: &[ListedAddon], untracked: &[UntrackedDir]) {
+    let mut table = Table::new();
+
+    let format = format::FormatBuilder::new()
+        .column_separator('|')
+        .borders('|')
+        .padding(1, 1)
+        .separators(
+            &[
+                format::LinePosition::Top,
+                format::LinePosition::Bottom,
+                format::LinePosition::Title,
+            ],
+            format::LineSeparator::new('-', '+', '+', '+'),
+        )
+        .build();
+    table.set_format(format);
+
+    table.set_titles(row![
+        "Name".bold(),
+        "Installed".bold(),
+        "Latest".bold(),
+        "Status".bold()
+    ]);
+
+    for listed in addons.iter() {
+        table.add_row(row![
+            listed.addon.name,
+            listed
+                .addon
+                .installed_version
+                .as_deref()
+                .unwrap_or_default(),
+            listed.addon.version,
+            status(listed).join(", ")
+        ]);
+    }
+    for dir in untracked.iter() {
+        table.add_row(row![dir.dir, "", "", "UNTRACKED".truecolor(130, 130, 130)]);
     }
+
+    table.printstd();
 }
@@ -1,13 +1,72 @@
+use crate::autoremove::AutoremoveCommand;
+use crate::backups::{BackupCommand, BackupsCommand, RestoreCommand};
+use crate::deps::DepsCommand;
+use crate::export::ExportCommand;
+use crate::graph::GraphCommand;
+use crate::import::ImportCommand;
+use crate::modpack::ModpackCommand;
+use crate::output::{
+    EXIT_FAILURE, Failed, Outcome, OutputFormat, Stopped, print_changes, print_json,
+};
+use crate::provides::ProvidesCommand;
+use crate::saved_vars::SavedVarsCommand;
+use crate::search::SearchCommand;
 use crate::show::ShowCommand;
-use clap::Parser;
+use crate::state::ApplyStateCommand;
+use crate::sync::SyncCommand;
+use std::io;
+use std::time::Duration;
+
+use clap::{Parser, crate_authors, crate_version};
 use colored::*;
 use dotenv::dotenv;
-use eso_addons_core::error::Result;
 use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonRef, AddonShowDetails, BackupSnapshot, InstallPlan};
+use lazy_async_promise::{ImmediateValuePromise, ImmediateValueState};
+use serde::Serialize;
 
-// mod clean;
-// mod list;
+mod autoremove;
+mod backups;
+mod clean;
+mod deps;
+mod export;
+mod graph;
+mod import;
+mod list;
+mod modpack;
+mod output;
+mod provides;
+mod saved_vars;
+mod search;
 mod show;
+mod state;
+mod sync;
+
+/// Errors from the service, or from reading input
+pub type Result<T, E = Box<dyn std::error::Error>> = std::result::Result<T, E>;
+
+/// Service calls return promises for the GUI to poll each frame, the CLI waits for
+/// one at a time.
+trait Wait<T> {
+    async fn wait(self) -> Result<T>;
+}
+
+impl<T: Send + 'static> Wait<T> for ImmediateValuePromise<T> {
+    async fn wait(mut self) -> Result<T> {
+        loop {
+            let state = self.poll_state_mut();
+            if matches!(state, ImmediateValueState::Updating) {
+                tokio::time::sleep(Duration::from_millis(10)).await;
+                continue;
+            }
+            return match std::mem::replace(state, ImmediateValueState::Empty) {
+                ImmediateValueState::Success(value) => Ok(value),
+                ImmediateValueState::Error(e) => Err(e.0),
+                _ => Err("promise was already taken".into()),
+            };
+        }
+    }
+}
 
 #[derive(Parser)]
 #[clap(
@@ -18,6 +77,14 @@ mod show;
 struct Opts {
     #[clap(short, long, help = "Path to TOML config file")]
     config: Option<String>,
+    #[clap(
+        long,
+        global = true,
+        arg_enum,
+        default_value = "table",
+        help = "How to print results, json is documented in docs/cli-json.md"
+    )]
+    format: OutputFormat,
     #[clap(subcommand)]
     subcmd: SubCommand,
 }
@@ -25,28 +92,131 @@ struct Opts {
 #[derive(Parser)]
 struct AddCommand {
     addon_id: i32,
+    #[clap(long, short, help = "Install dependencies without asking")]
+    yes: bool,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
 }
 
 impl AddCommand {
-    pub async fn run(&self, service: &mut AddonService) -> Result<()> {
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
         // update endpoints from config
         service.api.file_details_url = service.config.file_details.to_owned();
 
-        let installed = service.install(self.addon_id, false).await;
-        match installed {
-            Ok(()) => (),
-            Err(installed) => return Err(installed),
+        // resolving dependencies downloads archives to read their manifests, so a dry
+        // run only plans the addon itself
+        if self.dry_run {
+            print_changes(
+                format,
+                &service
+                    .plan_install(vec![self.addon_id], false)
+                    .wait()
+                    .await?,
+            );
+            if !format.is_json() {
+                println!("Libraries this addon requires are resolved when installing");
+            }
+            return Ok(());
+        }
+
+        // dependencies of dependencies are resolved before anything is installed
+        let plan = service
+            .resolve_install_plan(vec![self.addon_id])
+            .wait()
+            .await?;
+        if !format.is_json() {
+            for step in plan.steps.iter() {
+                match &step.required_by {
+                    Some(required_by) => println!(
+                        "{} {} {} (required by {})",
+                        "+".green(),
+                        step.addon.name,
+                        step.version,
+                        required_by
+                    ),
+                    None => println!("{} {} {}", "+".green(), step.addon.name, step.version),
+                }
+            }
+            for dep in plan.unresolved.iter() {
+                println!(
+                    "{} No addon found for {} (required by {})",
+                    "!".yellow(),
+                    dep.dir,
+                    dep.required_by
+                );
+            }
+            for failed in plan.failed.iter() {
+                println!("{} {}: {}", "✗".red(), failed.label(), failed.error);
+            }
+        }
+        if plan.steps.len() > 1 && !self.yes {
+            confirm("Install these addons?", format)?;
+        }
+
+        let total = plan.addon_count();
+        service.install_plan(plan.clone()).wait().await?;
+        let outcome = Outcome {
+            installed: installed_steps(service, &plan).await?,
+            errors: service.errors(),
+            ..Default::default()
         };
+        if !format.is_json() {
+            for addon in outcome.installed.iter() {
+                println!("{} Installed {}!", "✔".green(), addon.name);
+            }
+        }
+        outcome.finish(format, total)
+    }
+}
 
-        // check all addons installed from dependency dirs
-        // don't auto-install depends, they are only directory based and there are duplicates,
-        // instead, search addon_dirs for possible addons to install
-        let _need_installs = service.get_missing_dependency_options().await;
+/// Asks on stderr, stdout is left to the output. Anything but a yes is an error so a
+/// declined change doesn't exit with 0. `--format json` never asks, scripts pass `--yes`.
+fn confirm(message: &str, format: OutputFormat) -> Result<()> {
+    if format.is_json() {
+        return Err("Pass --yes to make changes with --format json".into());
+    }
+    let question = requestty::Question::confirm("confirm")
+        .message(message)
+        .build();
 
-        Ok(())
+    let mut backend = requestty::prompt::backend::get_backend(io::stderr().lock());
+    let mut events = requestty::prompt::events::get_events();
+    match requestty::prompt_one_with(question, &mut backend, &mut events) {
+        Ok(answer) if answer.as_bool() == Some(true) => Ok(()),
+        Ok(_) => Err("Cancelled".into()),
+        Err(e) => Err(format!("Unable to ask for confirmation, pass --yes: {e}").into()),
     }
 }
 
+/// Asks to pick one of `choices` on stderr, `None` if the prompt was cancelled.
+fn select(message: &str, choices: Vec<String>) -> Option<usize> {
+    let question = requestty::Question::select("select")
+        .message(message)
+        .choices(choices)
+        .build();
+
+    let mut backend = requestty::prompt::backend::get_backend(io::stderr().lock());
+    let mut events = requestty::prompt::events::get_events();
+    requestty::prompt_one_with(question, &mut backend, &mut events)
+        .ok()
+        .and_then(|answer| answer.as_list_item().map(|item| item.index))
+}
+
+/// Addons of `plan` installed at the planned version, once it has been installed.
+async fn installed_steps(service: &AddonService, plan: &InstallPlan) -> Result<Vec<AddonRef>> {
+    let installed = service.get_installed_addons().wait().await?;
+    Ok(plan
+        .steps
+        .iter()
+        .filter(|step| {
+            installed.iter().any(|a| {
+                a.id == step.addon.id && a.installed_version.as_ref() == Some(&step.version)
+            })
+        })
+        .map(|step| step.addon.clone())
+        .collect())
+}
+
 #[derive(Parser)]
 struct UpdateCommand {
     #[clap(
@@ -56,122 +226,374 @@ struct UpdateCommand {
         help = "Optionally only update the TamrielTradeCentre Price Table"
     )]
     ttc_pricetable: bool,
+    #[clap(
+        long,
+        conflicts_with = "ttc-pricetable",
+        help = "Optionally only sync HarvestMap data"
+    )]
+    hm_data: bool,
+    #[clap(
+        long,
+        help = "Only show what would change, with the addon catalog as last refreshed"
+    )]
+    dry_run: bool,
+}
+
+/// `update --ttc-pricetable` output.
+#[derive(Serialize)]
+struct TtcOutput {
+    /// Regions whose PriceTable was downloaded, `na` and `eu`
+    updated: Vec<&'static str>,
+}
+
+/// `update --hm-data` output.
+#[derive(Serialize)]
+struct HmOutput {
+    /// Zones whose data was merged
+    updated: Vec<String>,
 }
 
 impl UpdateCommand {
-    pub async fn run(&self, service: &mut AddonService) -> Result<()> {
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        if self.dry_run {
+            if self.ttc_pricetable {
+                print_changes(format, &service.plan_ttc_pricetable().wait().await?);
+                return Ok(());
+            }
+            if self.hm_data {
+                print_changes(format, &service.plan_hm_data().wait().await?);
+                return Ok(());
+            }
+            let updates: Vec<i32> = service
+                .get_installed_addons()
+                .wait()
+                .await?
+                .iter()
+                .filter(|a| a.is_upgradable())
+                .map(|a| a.id)
+                .collect();
+            print_changes(format, &service.plan_install(updates, true).wait().await?);
+            return Ok(());
+        }
+
         // Check if only updating PriceTable
         if self.ttc_pricetable {
-            let update = service.update_ttc_pricetable().await?;
-            service.config.apply_ttc_update(update);
-            service.save_config();
+            let updated = update_ttc(service).await?;
+            if format.is_json() {
+                print_json(&TtcOutput { updated });
+            } else if updated.is_empty() {
+                println!("PriceTable up to date!");
+            } else {
+                println!(
+                    "{} Updated PriceTable ({})",
+                    "✔".green(),
+                    updated.join(", ")
+                );
+            }
             return Ok(());
         }
-
-        let result = service.update(true).await?;
-
-        if result.addons_updated.is_empty() {
-            println!("Everything up to date!");
-        } else {
-            for addon in result.addons_updated.iter() {
-                println!("{} Updated {}!", "✔".green(), addon.name);
+        if self.hm_data {
+            let updated = update_hm(service).await?;
+            if format.is_json() {
+                print_json(&HmOutput { updated });
+            } else if updated.is_empty() {
+                println!("HarvestMap data up to date!");
+            } else {
+                println!(
+                    "{} Synced HarvestMap data ({})",
+                    "✔".green(),
+                    updated.join(", ")
+                );
             }
+            return Ok(());
         }
-        if self.ttc_pricetable && !result.ttc_updated {
-            println!("TTC PriceTable NOT updated!");
+
+        // refresh the catalog, then update what it has newer versions of
+        service.update(false).wait().await?;
+        let (mut outcome, total, _) = upgrade_installed(service, format).await?;
+        if total == 0 && !format.is_json() {
+            println!("Everything up to date!");
         }
-        if result.ttc_updated {
-            println!("TTC PriceTable updated");
+        outcome.errors = service.errors();
+
+        outcome.missing_dependencies = service.get_missing_dependency_options().wait().await?;
+        if !outcome.missing_dependencies.is_empty() && !format.is_json() {
+            println!("Missing dependencies! Found some options:");
+            for option in outcome.missing_dependencies.iter() {
+                match (&option.option_name, option.option_id) {
+                    (Some(name), Some(id)) => {
+                        println!("{} - {} ({})", option.missing_dir, name, id)
+                    }
+                    _ => println!(
+                        "{} (required by {})",
+                        option.missing_dir, option.required_by
+                    ),
+                }
+            }
         }
 
-        if !result.missing_deps.is_empty() {
-            println!("Missing dependencies! Founds some options:");
-            for need_install in result.missing_deps.iter() {
-                println!(
-                    "{} - {} ({})",
-                    need_install.dir, need_install.name, need_install.id
-                );
+        outcome.finish(format, total)
+    }
+}
+
+/// Updates the installed addons the catalog has newer versions of, one at a time so
+/// a failed addon doesn't hold back the rest. The snapshot due before updating is
+/// taken first, a failed one is recorded and doesn't hold back the updates. Returns
+/// the updated addons, how many were tried and the snapshot; failures are left in the
+/// service's errors.
+async fn upgrade_installed(
+    service: &mut AddonService,
+    format: OutputFormat,
+) -> Result<(Outcome, usize, Option<BackupSnapshot>)> {
+    let updates: Vec<AddonShowDetails> = service
+        .get_installed_addons()
+        .wait()
+        .await?
+        .into_iter()
+        .filter(|a| a.is_upgradable())
+        .collect();
+    let mut backup = None;
+    if let Some(snapshot) = service.backup_before_update(updates.len()) {
+        match snapshot.wait().await {
+            Ok(snapshot) => {
+                service.config.backup_last = Some(snapshot.created);
+                service.save_config();
+                if !format.is_json() {
+                    println!("{} Backed up to {}", "✔".green(), snapshot.path.display());
+                }
+                backup = Some(snapshot);
             }
+            Err(e) => service.record_error("Backing up addon data", e),
         }
+    }
+    let mut outcome = Outcome::default();
+    for addon in updates.iter() {
+        let errors = service.errors().len();
+        service.install(addon.id, true).wait().await?;
+        if service.errors().len() == errors {
+            if !format.is_json() {
+                println!("{} Updated {}!", "✔".green(), addon.name);
+            }
+            outcome.updated.push(AddonRef {
+                id: addon.id,
+                name: addon.name.clone(),
+            });
+        }
+    }
+    Ok((outcome, updates.len(), backup))
+}
 
-        Ok(())
+/// Downloads the PriceTable of the regions that changed and saves their versions,
+/// returns the regions downloaded.
+async fn update_ttc(service: &mut AddonService) -> Result<Vec<&'static str>> {
+    let update = service.update_ttc_pricetable().wait().await?;
+    let mut updated = vec![];
+    if update.na_version.is_some() {
+        updated.push("na");
+    }
+    if update.eu_version.is_some() {
+        updated.push("eu");
     }
+    service.config.apply_ttc_update(update);
+    service.save_config();
+    Ok(updated)
 }
 
-#[derive(Parser)]
-struct RemoveCommand {
-    addon_id: i32,
+/// Syncs the HarvestMap zones that are due and saves their hashes, returns the zones
+/// synced.
+async fn update_hm(service: &mut AddonService) -> Result<Vec<String>> {
+    let update = service.update_hm_data().wait().await?;
+    let mut updated: Vec<String> = update.zone_hashes.keys().cloned().collect();
+    updated.sort();
+    service.config.apply_hm_update(update);
+    service.save_config();
+    Ok(updated)
 }
 
-impl RemoveCommand {
-    pub async fn run(&self, service: &mut AddonService) -> Result<()> {
-        service.remove(self.addon_id).await?;
-        println!("{} Uninstalled {}!", "✔".green(), self.addon_id);
+#[derive(Parser)]
+struct ClearCacheCommand {}
+
+impl ClearCacheCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        service.clear_cache().wait().await?;
+        if format.is_json() {
+            print_json(&serde_json::json!({}));
+        } else {
+            println!(
+                "{} Cleared cached download links and addon details",
+                "✔".green()
+            );
+        }
         Ok(())
     }
 }
 
 #[derive(Parser)]
-struct SearchCommand {
-    search_string: String,
+struct RemoveCommand {
+    addon_id: i32,
+    #[clap(long, short, help = "Remove even if installed addons depend on it")]
+    force: bool,
+    #[clap(long, help = "Also remove the installed addons that depend on it")]
+    cascade: bool,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
 }
 
-impl SearchCommand {
-    pub async fn run(&self, service: &AddonService) -> Result<()> {
-        let results = service.search(&self.search_string).await?;
-        if results.is_empty() {
-            println!("No results for \"{}\"", self.search_string);
+impl RemoveCommand {
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        let plan = service.get_removal_plan(self.addon_id).wait().await?;
+        if !plan.dependents.is_empty() && !self.force && !self.cascade {
+            if format.is_json() {
+                print_json(&plan);
+            } else {
+                println!("{} {} is required by:", "!".yellow(), plan.addon.name);
+                for dependent in plan.dependents.iter() {
+                    println!("- {} ({})", dependent.name, dependent.id);
+                }
+                println!();
+                println!("Use --force to remove it anyway or --cascade to remove these too");
+            }
+            return Err(Stopped.into());
+        }
+
+        let mut removed = if self.cascade {
+            plan.cascade.clone()
+        } else {
+            vec![]
+        };
+        removed.push(plan.addon.clone());
+        if self.dry_run {
+            let ids: Vec<i32> = removed.iter().map(|a| a.id).collect();
+            print_changes(format, &service.plan_remove(ids).wait().await?);
             return Ok(());
         }
-        for addon in results.iter() {
-            let mut output = format!("{:>4} {}", addon.id, addon.name);
-            if addon.installed {
-                output.push_str(&format!(" {}", "(installed)".green().bold()));
+        service.remove_plan(plan, self.cascade).wait().await?;
+        if !format.is_json() {
+            for addon in removed.iter() {
+                println!("{} Uninstalled {}!", "✔".green(), addon.name);
             }
-            println!("{output}");
         }
-        Ok(())
+        Outcome {
+            removed,
+            ..Default::default()
+        }
+        .finish(format, 0)
     }
 }
 
 #[derive(Parser)]
 enum SubCommand {
-    // #[clap(about = "List status of addons")]
-    // List(list::ListCommand),
+    #[clap(about = "List installed addons with their versions and status")]
+    List(list::ListCommand),
     #[clap(about = "Update addons")]
     Update(UpdateCommand),
-    // #[clap(about = "Uninstall not managed addons")]
-    // Clean(clean::CleanCommand),
+    #[clap(
+        about = "Refresh the catalog, update addons, PriceTable and HarvestMap data as configured"
+    )]
+    Sync(SyncCommand),
+    #[clap(about = "Remove addon folders that no installed addon owns")]
+    Clean(clean::CleanCommand),
     #[clap(about = "Add a new addon")]
     Add(AddCommand),
+    #[clap(about = "Install addons from a list of ESOUI URLs, ids or folder names")]
+    Import(ImportCommand),
+    #[clap(about = "Export installed addons for Minion or as a shareable list")]
+    Export(ExportCommand),
+    #[clap(about = "Share addon sets as modpacks and check the installed addons against one")]
+    Modpack(ModpackCommand),
+    #[clap(about = "Install, update and remove addons to match a desired-state file")]
+    ApplyState(ApplyStateCommand),
     #[clap(about = "Uninstall addon")]
     Remove(RemoveCommand),
-    #[clap(about = "Search addons")]
+    #[clap(about = "Uninstall libraries that were only installed as a dependency")]
+    Autoremove(AutoremoveCommand),
+    #[clap(about = "Search, filter and sort catalog addons")]
     Search(SearchCommand),
+    #[clap(about = "Find which catalog addons ship a folder")]
+    Provides(ProvidesCommand),
     #[clap(about = "Show addon details")]
     Show(ShowCommand),
+    #[clap(about = "Inspect missing dependencies and resolve or override them")]
+    Deps(DepsCommand),
+    #[clap(about = "Export the installed dependency graph")]
+    Graph(GraphCommand),
+    #[clap(about = "List, compare and restore automatic backups")]
+    Backups(BackupsCommand),
+    #[clap(about = "Back up installed addons and settings to a file")]
+    Backup(BackupCommand),
+    #[clap(about = "Reinstall the addons and restore the files of a backup file")]
+    Restore(RestoreCommand),
+    #[clap(about = "Find orphaned and oversized SavedVariables and clean them up")]
+    SavedVars(SavedVarsCommand),
+    #[clap(about = "Forget cached download links and addon details")]
+    ClearCache(ClearCacheCommand),
+}
+
+/// `--format json` output when a command fails before it has anything to print.
+#[derive(Serialize)]
+struct ErrorOutput {
+    error: String,
 }
 
 #[tokio::main]
-async fn main() -> Result<()> {
+async fn main() {
     dotenv().ok();
 
     let opts: Opts = Opts::parse();
+    let format = opts.format;
+    if format != OutputFormat::Table {
+        colored::control::set_override(false);
+    }
 
     tracing_subscriber::fmt()
         .with_max_level(tracing::Level::WARN)
-        .with_test_writer()
+        .with_writer(io::stderr)
         .init();
 
     let mut service = AddonService::new().await;
 
-    match opts.subcmd {
-        // SubCommand::List(list) => list.run(&addon_manager, &config),
-        SubCommand::Update(update) => update.run(&mut service).await,
-        // SubCommand::Clean(mut clean) => clean.run(&config, &addon_manager),
-        SubCommand::Add(add) => add.run(&mut service).await,
-        SubCommand::Remove(remove) => remove.run(&mut service).await,
-        SubCommand::Search(search) => search.run(&service).await,
-        SubCommand::Show(show) => show.run(&service).await,
+    let result = match opts.subcmd {
+        SubCommand::List(list) => list.run(&service, format).await,
+        SubCommand::Update(update) => update.run(&mut service, format).await,
+        SubCommand::Sync(sync) => sync.run(&mut service, format).await,
+        SubCommand::Clean(clean) => clean.run(&service, format).await,
+        SubCommand::Add(add) => add.run(&mut service, format).await,
+        SubCommand::Import(import) => import.run(&mut service, format).await,
+        SubCommand::Export(export) => export.run(&service).await,
+        SubCommand::Modpack(modpack) => modpack.run(&service, format).await,
+        SubCommand::ApplyState(apply_state) => apply_state.run(&service, format).await,
+        SubCommand::Remove(remove) => remove.run(&mut service, format).await,
+        SubCommand::Autoremove(autoremove) => autoremove.run(&service, format).await,
+        SubCommand::Search(search) => search.run(&service, format).await,
+        SubCommand::Provides(provides) => provides.run(&service, format).await,
+        SubCommand::Show(show) => show.run(&service, format).await,
+        SubCommand::Deps(deps) => deps.run(&service, format).await,
+        SubCommand::Graph(graph) => graph.run(&service).await,
+        SubCommand::Backups(backups) => backups.run(&mut service, format).await,
+        SubCommand::Backup(backup) => backup.run(&service, format).await,
+        SubCommand::Restore(restore) => restore.run(&service, format).await,
+        SubCommand::SavedVars(saved_vars) => saved_vars.run(&service, format).await,
+        SubCommand::ClearCache(clear_cache) => clear_cache.run(&service, format).await,
+    };
+
+    if let Err(e) = result {
+        // partial failures were already reported along with the output
+        if e.is::<Stopped>() {
+            std::process::exit(EXIT_FAILURE);
+        }
+        if let Some(failed) = e.downcast_ref::<Failed>() {
+            if !format.is_json() {
+                eprintln!("{} {failed}", "✗".red());
+            }
+            std::process::exit(failed.exit_code());
+        }
+        if format.is_json() {
+            print_json(&ErrorOutput {
+                error: e.to_string(),
+            });
+        } else {
+            eprintln!("{} {e}", "✗".red());
+        }
+        std::process::exit(EXIT_FAILURE);
     }
 }
@@ -0,0 +1,228 @@
+use std::path::PathBuf;
+
+use clap::{ArgEnum, Parser};
+use colored::*;
+use eso_addons_core::modpack::PinMode;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{ChangePlan, ModpackReport};
+use serde::Serialize;
+
+use crate::output::{Outcome, OutputFormat, Stopped, print_changes, print_json};
+use crate::{Result, Wait, confirm, installed_steps};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum PinArg {
+    None,
+    Min,
+    Exact,
+}
+
+impl From<PinArg> for PinMode {
+    fn from(pin: PinArg) -> Self {
+        match pin {
+            PinArg::None => PinMode::None,
+            PinArg::Min => PinMode::Minimum,
+            PinArg::Exact => PinMode::Exact,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct ModpackCommand {
+    #[clap(subcommand)]
+    subcmd: ModpackSubCommand,
+}
+
+#[derive(Parser)]
+enum ModpackSubCommand {
+    #[clap(about = "Write the explicitly installed addons to a modpack file")]
+    Export {
+        file: PathBuf,
+        #[clap(long, short)]
+        name: String,
+        #[clap(long, short, default_value = "")]
+        description: String,
+        #[clap(
+            long,
+            arg_enum,
+            default_value = "none",
+            help = "Pin installed versions"
+        )]
+        pin: PinArg,
+    },
+    #[clap(about = "Compare the installed addons with a modpack")]
+    Check { file: PathBuf },
+    #[clap(about = "Install what a modpack is missing and update what's too old")]
+    Apply {
+        file: PathBuf,
+        #[clap(long, help = "Also install optional addons")]
+        optional: bool,
+        #[clap(long, help = "Only show what would change")]
+        dry_run: bool,
+        #[clap(long, short, help = "Install without asking")]
+        yes: bool,
+    },
+}
+
+/// `modpack export --format json` output.
+#[derive(Serialize)]
+struct ExportOutput<'a> {
+    written: &'a PathBuf,
+}
+
+impl ModpackCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        match &self.subcmd {
+            ModpackSubCommand::Export {
+                file,
+                name,
+                description,
+                pin,
+            } => {
+                service
+                    .export_modpack(
+                        file.clone(),
+                        name.clone(),
+                        description.clone(),
+                        (*pin).into(),
+                    )
+                    .wait()
+                    .await?;
+                if format.is_json() {
+                    print_json(&ExportOutput { written: file });
+                } else {
+                    println!("{} Wrote {}", "✔".green(), file.display());
+                }
+            }
+            ModpackSubCommand::Check { file } => {
+                let report = service.check_modpack(file.clone()).wait().await?;
+                print_report(format, &report);
+                if report.has_drift() {
+                    return Err(Stopped.into());
+                }
+            }
+            ModpackSubCommand::Apply {
+                file,
+                optional,
+                dry_run,
+                yes,
+            } => {
+                let report = service.check_modpack(file.clone()).wait().await?;
+                if !format.is_json() {
+                    print_report(format, &report);
+                }
+                let to_apply = report
+                    .missing
+                    .iter()
+                    .filter(|m| *optional || !m.entry.optional)
+                    .count()
+                    + report
+                        .older
+                        .iter()
+                        .filter(|o| o.available_satisfies)
+                        .count();
+                if to_apply == 0 {
+                    match format {
+                        OutputFormat::Json if *dry_run => {
+                            print_changes(format, &ChangePlan::default())
+                        }
+                        OutputFormat::Json => return Outcome::default().finish(format, 0),
+                        _ => println!("Nothing to install"),
+                    }
+                    return Ok(());
+                }
+                if *dry_run {
+                    let ids: Vec<i32> = report
+                        .missing
+                        .iter()
+                        .filter(|m| *optional || !m.entry.optional)
+                        .chain(report.older.iter().filter(|o| o.available_satisfies))
+                        .map(|e| e.addon.id)
+                        .collect();
+                    print_changes(format, &service.plan_install(ids, false).wait().await?);
+                    return Ok(());
+                }
+                if !yes {
+                    confirm("Install and update these addons?", format)?;
+                }
+                let installed = service.apply_modpack(report, *optional).wait().await?;
+                let outcome = Outcome {
+                    installed: installed_steps(service, &installed).await?,
+                    errors: service.errors(),
+                    ..Default::default()
+                };
+                if !format.is_json() {
+                    println!(
+                        "{} Installed or updated {} addons, including required libraries",
+                        "✔".green(),
+                        outcome.installed.len()
+                    );
+                }
+                outcome.finish(format, installed.addon_count())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+fn print_report(format: OutputFormat, report: &ModpackReport) {
+    if format.is_json() {
+        print_json(report);
+        return;
+    }
+    println!(
+        "{} ({} installed as listed)",
+        report.pack.name, report.matching
+    );
+    for missing in report.missing.iter() {
+        let optional = if missing.entry.optional {
+            " (optional)"
+        } else {
+            ""
+        };
+        println!(
+            "{} {} {}{optional}",
+            "+".green(),
+            missing.addon.name,
+            missing.available_version
+        );
+    }
+    for older in report.older.iter() {
+        let installed = older.installed_version.clone().unwrap_or_default();
+        if older.available_satisfies {
+            println!(
+                "{} {} {installed} -> {}",
+                "~".yellow(),
+                older.addon.name,
+                older.available_version
+            );
+        } else {
+            println!(
+                "{} {} {installed} is older than listed, ESOUI only has {}",
+                "!".yellow(),
+                older.addon.name,
+                older.available_version
+            );
+        }
+    }
+    for newer in report.newer.iter() {
+        println!(
+            "{} {} {} is newer than listed {}",
+            "!".yellow(),
+            newer.addon.name,
+            newer.installed_version.clone().unwrap_or_default(),
+            newer.entry.version.clone().unwrap_or_default()
+        );
+    }
+    for extra in report.extra.iter() {
+        println!("{} {} (not in the modpack)", "?".normal(), extra.name);
+    }
+    for entry in report.unavailable.iter() {
+        println!(
+            "{} {} (#{}) not found on ESOUI",
+            "!".red(),
+            entry.name,
+            entry.id
+        );
+    }
+}
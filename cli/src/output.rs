@@ -0,0 +1,174 @@
+use std::fmt;
+
+use clap::ArgEnum;
+use colored::*;
+use eso_addons_core::service::result::{AddonDepOption, AddonRef, ChangePlan, ErrorRecord};
+use serde::Serialize;
+
+use crate::Result;
+
+/// Nothing was done, or every step failed
+pub const EXIT_FAILURE: i32 = 1;
+/// Some steps succeeded and some failed
+pub const EXIT_PARTIAL: i32 = 3;
+
+/// How commands print their results, see `docs/cli-json.md` for the JSON.
+#[derive(ArgEnum, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    /// Colored text and tables
+    Table,
+    /// Text without colors, tables as tab separated lines
+    Plain,
+    /// One JSON document on stdout
+    Json,
+}
+
+impl OutputFormat {
+    pub fn is_json(self) -> bool {
+        self == OutputFormat::Json
+    }
+}
+
+/// Returned by commands that didn't do all they were asked, mapped to
+/// [`EXIT_PARTIAL`], or [`EXIT_FAILURE`] when nothing succeeded.
+#[derive(Debug)]
+pub struct Failed {
+    pub failed: usize,
+    pub total: usize,
+}
+
+impl Failed {
+    pub fn exit_code(&self) -> i32 {
+        if self.failed < self.total {
+            EXIT_PARTIAL
+        } else {
+            EXIT_FAILURE
+        }
+    }
+}
+
+impl fmt::Display for Failed {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} of {} failed", self.failed, self.total)
+    }
+}
+
+impl std::error::Error for Failed {}
+
+/// Returned by commands that already printed why they stopped, mapped to
+/// [`EXIT_FAILURE`] without printing anything more.
+#[derive(Debug)]
+pub struct Stopped;
+
+impl fmt::Display for Stopped {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stopped")
+    }
+}
+
+impl std::error::Error for Stopped {}
+
+/// What a command that installs, updates or removes addons did.
+#[derive(Serialize, Default)]
+pub struct Outcome {
+    pub installed: Vec<AddonRef>,
+    pub updated: Vec<AddonRef>,
+    pub removed: Vec<AddonRef>,
+    /// Dependency dirs marked as ignored
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub ignored: Vec<String>,
+    /// Errors the service recorded, one for each addon that failed
+    pub errors: Vec<ErrorRecord>,
+    /// Required dirs no installed addon provides, with an addon that does
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub missing_dependencies: Vec<AddonDepOption>,
+}
+
+impl Outcome {
+    /// Prints the outcome as JSON, or the errors on stderr as the other formats print
+    /// the rest as it happens. `Err(Failed)` if any of `total` steps failed.
+    pub fn finish(self, format: OutputFormat, total: usize) -> Result<()> {
+        if format.is_json() {
+            print_json(&self);
+        } else {
+            print_errors(&self.errors);
+        }
+        check_failed(self.errors.len(), total)
+    }
+}
+
+pub fn print_errors(errors: &[ErrorRecord]) {
+    for error in errors.iter() {
+        eprintln!("{} {}: {}", "✗".red(), error.context, error.message);
+    }
+}
+
+/// `Err(Failed)` if any of `total` steps failed.
+pub fn check_failed(failed: usize, total: usize) -> Result<()> {
+    if failed > 0 {
+        return Err(Failed {
+            failed,
+            total: total.max(failed),
+        }
+        .into());
+    }
+    Ok(())
+}
+
+pub fn print_json<T: Serialize + ?Sized>(value: &T) {
+    println!(
+        "{}",
+        serde_json::to_string_pretty(value).expect("cli output serializes to json")
+    );
+}
+
+/// Prints what an operation would change, for `--dry-run`.
+pub fn print_changes(format: OutputFormat, changes: &ChangePlan) {
+    if format.is_json() {
+        print_json(changes);
+        return;
+    }
+    if changes.is_empty() {
+        println!("No changes");
+        return;
+    }
+    for download in changes.downloads.iter() {
+        let size = match download.size {
+            Some(size) => format!("{:.1} MB", size as f64 / 1_000_000.0),
+            None => "unknown size".to_owned(),
+        };
+        println!(
+            "{} download {} ({size}) {}",
+            "↓".cyan(),
+            download.label,
+            download.url
+        );
+    }
+    for dir in changes.dirs_written.iter() {
+        println!("{} write {}", "+".green(), dir.display());
+    }
+    for file in changes.files_written.iter() {
+        println!("{} write {}", "+".green(), file.display());
+    }
+    for dir in changes.dirs_deleted.iter() {
+        println!("{} delete {}", "-".red(), dir.display());
+    }
+    for change in changes.db_changes.iter() {
+        println!(
+            "{} {} {} {}",
+            "*".yellow(),
+            change.action.as_str(),
+            change.table,
+            change.rows
+        );
+    }
+    let (size, unknown) = changes.download_size();
+    if !changes.downloads.is_empty() {
+        let more = if unknown { " and more" } else { "" };
+        println!(
+            "{} downloads, {:.1} MB{more}",
+            changes.downloads.len(),
+            size as f64 / 1_000_000.0
+        );
+    }
+}
@@ -0,0 +1,76 @@
+use clap::{ArgEnum, Parser};
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::search::DirMatch;
+
+use crate::output::{OutputFormat, print_json};
+use crate::{Result, Wait};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum MatchArg {
+    Exact,
+    IgnoreCase,
+    Fuzzy,
+}
+
+impl From<MatchArg> for DirMatch {
+    fn from(mode: MatchArg) -> Self {
+        match mode {
+            MatchArg::Exact => DirMatch::Exact,
+            MatchArg::IgnoreCase => DirMatch::IgnoreCase,
+            MatchArg::Fuzzy => DirMatch::Fuzzy,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct ProvidesCommand {
+    #[clap(help = "Folder name, e.g. a missing dependency like LibMediaProvider-1.0")]
+    dir: String,
+    #[clap(
+        long = "match",
+        arg_enum,
+        default_value = "fuzzy",
+        help = "Fuzzy also ignores punctuation and version suffixes and allows typos"
+    )]
+    mode: MatchArg,
+}
+
+impl ProvidesCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let providers = service
+            .find_dir_providers(self.dir.clone(), self.mode.into())
+            .wait()
+            .await?;
+        match format {
+            OutputFormat::Json => print_json(&providers),
+            OutputFormat::Plain => {
+                for provider in providers.iter() {
+                    let addon = &provider.addon;
+                    println!(
+                        "{}\t{}\t{}\t{}",
+                        provider.dir, addon.id, addon.name, addon.installed
+                    );
+                }
+            }
+            OutputFormat::Table => {
+                if providers.is_empty() {
+                    println!("No addon on ESOUI ships {}", self.dir);
+                }
+                for provider in providers.iter() {
+                    let addon = &provider.addon;
+                    let mut output = format!("{:>5} {}", addon.id, addon.name);
+                    if addon.installed {
+                        output.push_str(&format!(" {}", "(installed)".green().bold()));
+                    }
+                    if provider.dir != self.dir {
+                        output
+                            .push_str(&format!(" {}", format!("ships {}", provider.dir).dimmed()));
+                    }
+                    println!("{output}");
+                }
+            }
+        }
+        Ok(())
+    }
+}
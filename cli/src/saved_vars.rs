@@ -0,0 +1,122 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::SavedVariablesFile;
+use serde::Serialize;
+
+use crate::output::{OutputFormat, print_json};
+use crate::{Result, Wait, confirm};
+
+#[derive(Parser)]
+pub struct SavedVarsCommand {
+    #[clap(subcommand)]
+    subcmd: SavedVarsSubCommand,
+}
+
+#[derive(Parser)]
+enum SavedVarsSubCommand {
+    #[clap(about = "List orphaned and the largest SavedVariables files")]
+    Scan {
+        #[clap(
+            long,
+            default_value = "20",
+            help = "How many of the largest files to list"
+        )]
+        top: usize,
+    },
+    #[clap(about = "Archive SavedVariables files to the backup folder, then delete them")]
+    Clean {
+        #[clap(help = "File names in the SavedVariables folder")]
+        files: Vec<String>,
+        #[clap(long, help = "Clean up every orphaned file")]
+        orphans: bool,
+        #[clap(long, short, help = "Clean up without asking")]
+        yes: bool,
+    },
+}
+
+/// `saved-vars clean --format json` output.
+#[derive(Serialize)]
+struct CleanOutput<'a> {
+    archive: PathBuf,
+    files: Vec<&'a SavedVariablesFile>,
+}
+
+impl SavedVarsCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let scan = service.scan_saved_variables().wait().await?;
+        match &self.subcmd {
+            SavedVarsSubCommand::Scan { top } => {
+                if format.is_json() {
+                    print_json(&scan);
+                    return Ok(());
+                }
+                let total: u64 = scan.iter().map(|f| f.size).sum();
+                println!("{} files, {} in total", scan.len(), megabytes(total));
+                for file in scan.iter().filter(|f| f.is_orphan()) {
+                    println!("{} {}", "?".yellow(), file_line(file));
+                }
+                for file in scan.iter().filter(|f| !f.is_orphan()).take(*top) {
+                    println!("{} {}", "=".normal(), file_line(file));
+                }
+            }
+            SavedVarsSubCommand::Clean {
+                files,
+                orphans,
+                yes,
+            } => {
+                let selected: Vec<&SavedVariablesFile> = scan
+                    .iter()
+                    .filter(|f| (*orphans && f.is_orphan()) || files.contains(&f.file))
+                    .collect();
+                if !format.is_json() {
+                    for file in files.iter() {
+                        if !scan.iter().any(|f| &f.file == file) {
+                            println!("{} {} not found", "!".yellow(), file);
+                        }
+                    }
+                    if selected.is_empty() {
+                        println!("Nothing to clean up");
+                    }
+                    for file in selected.iter() {
+                        println!("{} {}", "-".red(), file_line(file));
+                    }
+                }
+                if selected.is_empty() {
+                    return Ok(());
+                }
+                if !yes {
+                    confirm("Archive and delete these files?", format)?;
+                }
+                let archive = service
+                    .clean_saved_variables(selected.iter().map(|f| f.file.clone()).collect())
+                    .wait()
+                    .await?;
+                if format.is_json() {
+                    print_json(&CleanOutput {
+                        archive,
+                        files: selected,
+                    });
+                } else {
+                    println!("{} Archived to {}", "✔".green(), archive.display());
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+fn megabytes(size: u64) -> String {
+    format!("{:.1} MB", size as f64 / 1_000_000.0)
+}
+
+fn file_line(file: &SavedVariablesFile) -> String {
+    let owner = match (&file.addon, &file.owner) {
+        (Some(addon), _) => addon.name.clone(),
+        (None, Some(owner)) => owner.clone(),
+        (None, None) => "orphaned".to_owned(),
+    };
+    format!("{} {} ({owner})", file.file, megabytes(file.size))
+}
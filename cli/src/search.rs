@@ -0,0 +1,177 @@
+use chrono::NaiveDate;
+use clap::{ArgEnum, Parser};
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::SearchResult;
+use eso_addons_core::service::search::{AddonQuery, AddonSort, InstalledFilter};
+
+use crate::output::{OutputFormat, print_json};
+use crate::{Result, Wait};
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum StatusArg {
+    Installed,
+    NotInstalled,
+    Outdated,
+}
+
+impl From<StatusArg> for InstalledFilter {
+    fn from(status: StatusArg) -> Self {
+        match status {
+            StatusArg::Installed => InstalledFilter::Installed,
+            StatusArg::NotInstalled => InstalledFilter::NotInstalled,
+            StatusArg::Outdated => InstalledFilter::Outdated,
+        }
+    }
+}
+
+#[derive(ArgEnum, Clone, Copy)]
+pub enum SortArg {
+    Relevance,
+    Name,
+    Author,
+    Updated,
+    Downloads,
+    MonthlyDownloads,
+    Favorites,
+    Id,
+}
+
+impl From<SortArg> for AddonSort {
+    fn from(sort: SortArg) -> Self {
+        match sort {
+            SortArg::Relevance => AddonSort::Relevance,
+            SortArg::Name => AddonSort::Name,
+            SortArg::Author => AddonSort::Author,
+            SortArg::Updated => AddonSort::Updated,
+            SortArg::Downloads => AddonSort::TotalDownloads,
+            SortArg::MonthlyDownloads => AddonSort::MonthlyDownloads,
+            SortArg::Favorites => AddonSort::Favorites,
+            SortArg::Id => AddonSort::Id,
+        }
+    }
+}
+
+#[derive(Parser)]
+pub struct SearchCommand {
+    #[clap(
+        default_value = "",
+        hide_default_value = true,
+        help = "Words to find in names, authors, folders, descriptions and change logs"
+    )]
+    search_string: String,
+    #[clap(long, help = "Category id or title, subcategories included")]
+    category: Option<String>,
+    #[clap(
+        long,
+        arg_enum,
+        help = "Only installed, not installed or outdated addons"
+    )]
+    status: Option<StatusArg>,
+    #[clap(long, help = "Only addons marked compatible with this game version")]
+    game_version: Option<String>,
+    #[clap(long, help = "Only addons downloaded at least this many times")]
+    min_downloads: Option<u64>,
+    #[clap(long, help = "Only addons favorited at least this many times")]
+    min_favorites: Option<u64>,
+    #[clap(long, help = "Only addons updated on or after this day (YYYY-MM-DD)")]
+    updated_since: Option<NaiveDate>,
+    #[clap(long, help = "Part of the author's name")]
+    author: Option<String>,
+    #[clap(long, help = "Only addons shipping this folder")]
+    provides: Option<String>,
+    #[clap(
+        long,
+        arg_enum,
+        default_value = "relevance",
+        help = "Relevance lists the newest first without search words"
+    )]
+    sort: SortArg,
+    #[clap(long, help = "Reverse the sort order")]
+    reverse: bool,
+    #[clap(long, help = "Print at most this many results")]
+    limit: Option<u64>,
+    #[clap(long, default_value = "0", help = "Skip this many results")]
+    offset: u64,
+}
+
+impl SearchCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let category = match &self.category {
+            Some(category) => Some(self.category_id(service, category).await?),
+            None => None,
+        };
+        let query = AddonQuery {
+            text: self.search_string.clone(),
+            category,
+            installed: self.status.map(Into::into).unwrap_or_default(),
+            game_version: self.game_version.clone(),
+            min_downloads: self.min_downloads,
+            min_favorites: self.min_favorites,
+            updated_since: self.updated_since,
+            author: self.author.clone(),
+            provides_dir: self.provides.clone(),
+            sort: self.sort.into(),
+            reverse: self.reverse,
+            offset: self.offset,
+            limit: self.limit,
+        };
+        let page = service.query_addons(query).wait().await?;
+        let results = page.results;
+        match format {
+            OutputFormat::Json => print_json(&results),
+            OutputFormat::Plain => {
+                for SearchResult { addon, .. } in results.iter() {
+                    println!("{}\t{}\t{}", addon.id, addon.name, addon.installed);
+                }
+            }
+            OutputFormat::Table => {
+                if results.is_empty() && self.search_string.trim().is_empty() {
+                    println!("No addons found");
+                } else if results.is_empty() {
+                    println!("No results for \"{}\"", self.search_string);
+                }
+                for SearchResult { addon, snippet } in results.iter() {
+                    let mut output = format!("{:>4} {}", addon.id, addon.name);
+                    if addon.installed {
+                        output.push_str(&format!(" {}", "(installed)".green().bold()));
+                    }
+                    println!("{output}");
+                    if let Some(snippet) = snippet {
+                        let text: String = snippet
+                            .parts()
+                            .into_iter()
+                            .map(|(text, matched)| match matched {
+                                true => text.bold().to_string(),
+                                false => text.dimmed().to_string(),
+                            })
+                            .collect();
+                        println!("     {text}");
+                    }
+                }
+                if (results.len() as u64) < page.total {
+                    println!(
+                        "Showing {}-{} of {} results",
+                        self.offset + 1,
+                        self.offset + results.len() as u64,
+                        page.total
+                    );
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Reads `--category` as an id, or else a title ignoring case.
+    async fn category_id(&self, service: &AddonService, category: &str) -> Result<i32> {
+        if let Ok(id) = category.parse() {
+            return Ok(id);
+        }
+        let categories = service.get_categories().wait().await?;
+        categories
+            .iter()
+            .find(|c| c.title.eq_ignore_ascii_case(category))
+            .map(|c| c.id)
+            .ok_or_else(|| format!("No category named \"{category}\"").into())
+    }
+}
@@ -1,21 +1,45 @@
-use eso_addons_core::error::Result;
+use clap::Parser;
 use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonDependencyView, AddonShowDetails, Resolution};
+use serde::Serialize;
 
 use colored::Colorize;
 
+use crate::output::{OutputFormat, print_json};
+use crate::{Result, Wait};
+
 #[derive(Parser)]
 pub struct ShowCommand {
     addon_id: i32,
 }
 
+/// `show --format json` output.
+#[derive(Serialize)]
+struct ShowOutput {
+    #[serde(flatten)]
+    addon: AddonShowDetails,
+    /// Only for installed addons
+    dependencies: Option<AddonDependencyView>,
+}
+
 impl ShowCommand {
-    pub async fn run(&self, service: &AddonService) -> Result<()> {
-        let addon = service.get_addon_details(self.addon_id).await?;
-        if addon.is_none() {
-            println!("No addon found with id: {}", self.addon_id);
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let Some(addon) = service.get_addon_details(self.addon_id).wait().await? else {
+            return Err(format!("No addon found with id: {}", self.addon_id).into());
+        };
+        let dependencies = if addon.installed {
+            Some(service.get_addon_dependency_view(addon.id).wait().await?)
+        } else {
+            None
+        };
+        if format.is_json() {
+            print_json(&ShowOutput {
+                addon,
+                dependencies,
+            });
             return Ok(());
         }
-        let addon = addon.unwrap();
+
         let mut lines = vec![];
         let name_line = if addon.installed {
             format!("{} (installed)", addon.name)
@@ -27,18 +51,35 @@ impl ShowCommand {
         lines.push(("Author", addon.author_name));
         lines.push(("Category", addon.category));
         lines.push(("Version", addon.version));
-        if addon.download_total.is_some() {
-            lines.push(("Downloads", addon.download_total.unwrap()));
+        if let Some(download_total) = addon.download_total {
+            lines.push(("Downloads", download_total));
         }
         lines.push(("URL", addon.file_info_url));
-        if addon.download.is_some() {
-            lines.push(("Download", addon.download.unwrap()));
+        if let Some(download) = addon.download {
+            lines.push(("Download", download));
+        }
+        if let Some(file_name) = addon.file_name {
+            lines.push(("File", file_name));
         }
-        if addon.file_name.is_some() {
-            lines.push(("File", addon.file_name.unwrap()));
+        if let Some(md5) = addon.md5 {
+            lines.push(("MD5", md5));
         }
-        if addon.md5.is_some() {
-            lines.push(("MD5", addon.md5.unwrap()));
+        if let Some(view) = dependencies {
+            if !view.forward.is_empty() {
+                let requires: Vec<String> = view
+                    .forward
+                    .iter()
+                    .map(|dep| match &dep.resolution {
+                        Resolution::Unresolved { .. } => format!("{} (missing)", dep.dep_dir),
+                        _ => dep.dep_dir.clone(),
+                    })
+                    .collect();
+                lines.push(("Requires", requires.join(", ")));
+            }
+            if !view.dependents.is_empty() {
+                let names: Vec<&str> = view.dependents.iter().map(|a| a.name.as_str()).collect();
+                lines.push(("Required by", names.join(", ")));
+            }
         }
         let heading_size = lines.iter().map(|x| x.0.chars().count()).max().unwrap();
         for (heading, data) in lines.iter() {
@@ -0,0 +1,86 @@
+use std::path::PathBuf;
+
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::ChangePlan;
+
+use crate::output::{Outcome, OutputFormat, print_changes};
+use crate::{Result, Wait, confirm};
+
+#[derive(Parser)]
+pub struct ApplyStateCommand {
+    #[clap(help = "TOML file listing the addons to have installed")]
+    file: PathBuf,
+    #[clap(long, help = "Remove installed addons the file doesn't list")]
+    remove_unlisted: bool,
+    #[clap(long, help = "Only show what would change")]
+    dry_run: bool,
+    #[clap(long, short, help = "Apply without asking")]
+    yes: bool,
+}
+
+impl ApplyStateCommand {
+    pub async fn run(&self, service: &AddonService, format: OutputFormat) -> Result<()> {
+        let plan = service
+            .get_sync_plan(self.file.clone(), self.remove_unlisted)
+            .wait()
+            .await?;
+        if !format.is_json() {
+            for addon in plan.install.iter() {
+                println!("{} {}", "+".green(), addon.name);
+            }
+            for update in plan.update.iter() {
+                println!(
+                    "{} {} {} -> {}",
+                    "~".yellow(),
+                    update.addon.name,
+                    update.installed_version,
+                    update.available_version
+                );
+            }
+            for addon in plan.remove.iter() {
+                println!("{} {}", "-".red(), addon.name);
+            }
+            for entry in plan.unresolved.iter() {
+                println!("{} {} not found on ESOUI", "!".red(), entry);
+            }
+        }
+        if plan.is_empty() {
+            match format {
+                OutputFormat::Json if self.dry_run => print_changes(format, &ChangePlan::default()),
+                OutputFormat::Json => return Outcome::default().finish(format, 0),
+                _ => println!("Installed addons match ({} listed)", plan.unchanged),
+            }
+            return Ok(());
+        }
+        if self.dry_run {
+            print_changes(format, &service.plan_sync(plan).wait().await?);
+            return Ok(());
+        }
+        if !self.yes {
+            confirm("Apply these changes?", format)?;
+        }
+
+        let total = plan.install.len() + plan.update.len() + plan.remove.len();
+        let report = service.sync(plan).wait().await?;
+        if !format.is_json() {
+            for failed in report.failed.iter() {
+                println!("{} {}: {}", "✗".red(), failed.label(), failed.error);
+            }
+            println!(
+                "{} Installed or updated {} addons, removed {}",
+                "✔".green(),
+                report.installed.len(),
+                report.removed.len()
+            );
+        }
+        Outcome {
+            installed: report.installed,
+            removed: report.removed,
+            errors: service.errors(),
+            ..Default::default()
+        }
+        .finish(format, total)
+    }
+}
@@ -0,0 +1,98 @@
+use clap::Parser;
+use colored::*;
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::BackupSnapshot;
+use serde::Serialize;
+
+use crate::output::{Outcome, OutputFormat, check_failed, print_errors, print_json};
+use crate::{Result, Wait, update_hm, update_ttc, upgrade_installed};
+
+#[derive(Parser)]
+pub struct SyncCommand {}
+
+/// `sync --format json` output.
+#[derive(Serialize)]
+struct SyncOutput {
+    #[serde(flatten)]
+    outcome: Outcome,
+    /// The backup taken first, if one was due
+    backup: Option<BackupSnapshot>,
+    /// Regions whose PriceTable was downloaded, `null` when disabled or failed
+    ttc_pricetable: Option<Vec<&'static str>>,
+    /// HarvestMap zones synced, `null` when disabled or failed
+    hm_data: Option<Vec<String>>,
+}
+
+impl SyncCommand {
+    /// Runs every step even when an earlier one fails, the failures are reported at the
+    /// end so a timer's log shows all of them.
+    pub async fn run(&self, service: &mut AddonService, format: OutputFormat) -> Result<()> {
+        let mut total = 1;
+        if let Err(e) = service.update(false).wait().await {
+            service.record_error("Refreshing the addon catalog", e);
+        }
+
+        // backs up first, same as the GUI: on schedule, or before several updates
+        let (mut outcome, updates, backup) = upgrade_installed(service, format).await?;
+        total += updates + usize::from(backup.is_some());
+
+        let mut ttc_pricetable = None;
+        if service.config.update_ttc_pricetable {
+            total += 1;
+            match update_ttc(service).await {
+                Ok(updated) => {
+                    if !updated.is_empty() && !format.is_json() {
+                        println!(
+                            "{} Updated PriceTable ({})",
+                            "✔".green(),
+                            updated.join(", ")
+                        );
+                    }
+                    ttc_pricetable = Some(updated);
+                }
+                Err(e) => service.record_error("Updating TTC PriceTable", e),
+            }
+        }
+        let mut hm_data = None;
+        if service.config.update_hm_data {
+            total += 1;
+            match update_hm(service).await {
+                Ok(updated) => {
+                    if !updated.is_empty() && !format.is_json() {
+                        println!(
+                            "{} Synced HarvestMap data ({})",
+                            "✔".green(),
+                            updated.join(", ")
+                        );
+                    }
+                    hm_data = Some(updated);
+                }
+                Err(e) => service.record_error("Updating HarvestMap data", e),
+            }
+        }
+
+        outcome.errors = service.errors();
+        outcome.missing_dependencies = service.get_missing_dependency_options().wait().await?;
+        let failed = outcome.errors.len();
+        if format.is_json() {
+            print_json(&SyncOutput {
+                outcome,
+                backup,
+                ttc_pricetable,
+                hm_data,
+            });
+        } else {
+            if !outcome.missing_dependencies.is_empty() {
+                println!(
+                    "{} Installed addons are missing dependencies, see `deps missing`",
+                    "!".yellow()
+                );
+            }
+            print_errors(&outcome.errors);
+            if failed == 0 {
+                println!("{} Synced", "✔".green());
+            }
+        }
+        check_failed(failed, total)
+    }
+}
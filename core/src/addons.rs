@@ -6,6 +6,9 @@ use std::path::{Path, PathBuf};
 pub struct Addon {
     pub name: String,
     pub depends_on: Vec<String>,
+    pub optional_depends_on: Vec<String>,
+    /// `## SavedVariables:` globals, the game writes them to `SavedVariables/<addon dir>.lua`
+    pub saved_variables: Vec<String>,
 }
 
 pub struct AddonList {
@@ -13,27 +16,73 @@ pub struct AddonList {
     pub errors: Vec<Error>,
 }
 
-pub fn get_download_url(addon_url: &str) -> Option<String> {
+/// Catalog id of an ESOUI addon page or download URL
+pub fn get_addon_id(addon_url: &str) -> Option<i32> {
     let fns: Vec<fn(&str) -> Option<String>> = vec![
         |url: &str| {
-            let re = Regex::new(r"^https://.*esoui\.com/downloads/info(\d+)-(.+)$").unwrap();
+            let re = Regex::new(r"^https?://.*esoui\.com/downloads/info(\d+)([-.].*)?$").unwrap();
             re.captures(url).map(|captures| captures[1].to_owned())
         },
         |url: &str| {
             let re =
-                Regex::new(r"^https://.+esoui\.com/downloads/fileinfo\.php\?id=(\d+)$").unwrap();
+                Regex::new(r"^https?://.*esoui\.com/downloads/fileinfo\.php\?id=(\d+)").unwrap();
+            re.captures(url).map(|captures| captures[1].to_owned())
+        },
+        |url: &str| {
+            let re = Regex::new(r"^https?://.*esoui\.com/downloads/download(\d+)").unwrap();
             re.captures(url).map(|captures| captures[1].to_owned())
         },
     ];
 
-    for f in fns {
-        let url = f(addon_url);
-        if let Some(id) = url {
-            return Some(format!("https://www.esoui.com/downloads/download{id}"));
-        }
-    }
+    fns.iter()
+        .find_map(|f| f(addon_url))
+        .and_then(|id| id.parse().ok())
+        .filter(|id| *id > 0)
+}
 
-    None
+pub fn get_download_url(addon_url: &str) -> Option<String> {
+    get_addon_id(addon_url).map(|id| format!("https://www.esoui.com/downloads/download{id}"))
+}
+
+/// One entry of a pasted or imported addon list
+#[derive(Debug, PartialEq)]
+pub enum ImportEntry {
+    /// Catalog id, given directly or as an ESOUI URL
+    Id(i32),
+    /// Addon directory name, as listed in the AddOns folder
+    Dir(String),
+    /// Anything else, like a URL to another site
+    Unknown,
+}
+
+/// Splits a list of ESOUI URLs, addon ids and directory names, one per line or comma
+/// separated, into its entries. Blank lines and lines starting with `#` are skipped.
+pub fn parse_import_list(text: &str) -> Vec<(String, ImportEntry)> {
+    let dir_re = Regex::new(r"^[\w.-]+$").unwrap();
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.starts_with('#'))
+        .flat_map(|line| line.split(','))
+        .map(str::trim)
+        .filter(|raw| !raw.is_empty())
+        .map(|raw| {
+            // catalog ids start at 1, other numbers aren't addons
+            let entry = if let Ok(id) = raw.parse::<i32>() {
+                if id > 0 {
+                    ImportEntry::Id(id)
+                } else {
+                    ImportEntry::Unknown
+                }
+            } else if let Some(id) = get_addon_id(raw) {
+                ImportEntry::Id(id)
+            } else if dir_re.is_match(raw) {
+                ImportEntry::Dir(raw.to_owned())
+            } else {
+                ImportEntry::Unknown
+            };
+            (raw.to_owned(), entry)
+        })
+        .collect()
 }
 
 pub fn get_root_dir(path: &Path) -> PathBuf {
@@ -65,6 +114,7 @@ mod tests {
                 "https://www.esoui.com/downloads/fileinfo.php?id=2817",
                 Some("https://www.esoui.com/downloads/download2817".to_string()),
             ),
+            ("https://www.esoui.com/downloads/info0-Nothing.html", None),
         ];
 
         for test in tests {
@@ -72,4 +122,30 @@ mod tests {
             assert!(url == test.1, "Got value: {url:?}");
         }
     }
+
+    #[test]
+    fn test_parse_import_list() {
+        let text = "# my addons\n\
+            https://www.esoui.com/downloads/info1360-CombatMetrics.html\n\
+            http://esoui.com/downloads/fileinfo.php?id=2817, 7\r\n\
+            \n\
+            LibAddonMenu-2.0,  https://example.com/addon\n\
+            -5, 0\n";
+        let entries: Vec<ImportEntry> = parse_import_list(text)
+            .into_iter()
+            .map(|(_, entry)| entry)
+            .collect();
+        assert_eq!(
+            entries,
+            vec![
+                ImportEntry::Id(1360),
+                ImportEntry::Id(2817),
+                ImportEntry::Id(7),
+                ImportEntry::Dir("LibAddonMenu-2.0".to_owned()),
+                ImportEntry::Unknown,
+                ImportEntry::Unknown,
+                ImportEntry::Unknown,
+            ]
+        );
+    }
 }
@@ -15,6 +15,8 @@ const GLOBAL_CONFIG: &str = "globalconfig.json";
 const GAME_ID: &str = "ESO";
 /// Current configuration for v3 API
 const ENDPOINT_URL: &str = "https://api.mmoui.com/v3";
+/// Merges uploaded HarvestMap SavedVariables with the community data
+pub const HM_DATA_URL: &str = "http://harvestmap.binaryvector.net:8081";
 /// User-Agent expected by the TamrielTradeCentre upload/version endpoints.
 const TTC_USER_AGENT: &str = "TamrielTradeCentreClient/1.0.0";
 
@@ -101,12 +103,23 @@ impl ApiClient {
         Ok(response)
     }
 
+    /// Size the server reports for `url`, without downloading it.
+    pub async fn get_download_size(&self, url: &str) -> Result<Option<u64>> {
+        let response = self
+            .client
+            .head(url)
+            .send()
+            .await
+            .context(error::ApiGetUrlSnafu { url })?;
+        Ok(response.content_length().filter(|size| *size > 0))
+    }
+
     pub async fn get_categories(&self) -> Result<Vec<Category>> {
         self.req_url::<Vec<Category>>(&self.category_list_url).await
     }
 
     pub async fn get_hm_data(&self, data: String) -> Result<Response> {
-        let url = "http://harvestmap.binaryvector.net:8081";
+        let url = HM_DATA_URL;
         self.client
             .post(url)
             .body(data)
@@ -13,6 +13,8 @@ use tracing::log::info;
 pub const EAM_DATA_DIR: &str = "eso-addons";
 pub const EAM_CONF: &str = "config.json";
 pub const EAM_DB: &str = "addons.db";
+pub const EAM_BACKUPS: &str = "backups";
+pub const EAM_SETTINGS_SNAPSHOTS: &str = "settings-snapshots";
 
 const STEAMDECK_DEFAULT_ADDON_DIR: &str = ".local/share/Steam/steamapps/compatdata/306130/pfx/drive_c/users/steamuser/My Documents/Elder Scrolls Online/live/AddOns";
 
@@ -58,10 +60,16 @@ pub struct HmConfigUpdate {
     pub synced_at: Option<DateTime<Utc>>,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+/// An addon listed by ESOUI id, URL or name, see [`crate::state::DesiredState`]
+#[derive(Deserialize, Debug, Clone, Default)]
 pub struct AddonEntry {
+    #[serde(default)]
+    pub id: Option<i32>,
+    /// Addon folder name, or the addon's name on ESOUI
+    #[serde(default)]
     pub name: String,
     pub url: Option<String>,
+    /// Installed as a library, removed again once nothing requires it
     #[serde(default)]
     pub dependency: bool,
 }
@@ -71,6 +79,7 @@ impl serde::Serialize for AddonEntry {
         S: serde::Serializer,
     {
         let mut state = serializer.serialize_struct("AddonEntry", 0)?;
+        state.serialize_field("id", &self.id)?;
         state.serialize_field("name", &self.name)?;
         state.serialize_field("url", &self.url)?;
         state.serialize_field("dependency", &self.dependency)?;
@@ -131,8 +140,30 @@ pub struct Config {
     pub style: Style,
     #[serde(default)]
     pub ttc_region: TTCRegion,
+    /// Snapshot the installed set and SavedVariables before bulk updates and every
+    /// `backup_interval_days`
+    #[serde(default = "default_true")]
+    pub auto_backup: bool,
+    /// 0 only backs up before bulk updates
+    #[serde(default = "default_backup_interval_days")]
+    pub backup_interval_days: u32,
+    /// Number of snapshots kept in the backup dir, older ones are pruned
+    #[serde(default = "default_backup_keep")]
+    pub backup_keep: usize,
+    #[serde(default)]
+    pub backup_last: Option<DateTime<Utc>>,
 }
 impl Config {
+    /// Defaults for a missing or empty config file
+    fn new_install() -> Config {
+        Config {
+            onboard: true,
+            auto_backup: true,
+            backup_interval_days: default_backup_interval_days(),
+            backup_keep: default_backup_keep(),
+            ..Default::default()
+        }
+    }
     pub fn load() -> Config {
         // check config dir exists
         let config_dir = Self::default_config_dir();
@@ -155,10 +186,7 @@ impl Config {
                         "Empty config data, loading defaults to: {}",
                         config_filepath.display()
                     );
-                    Config {
-                        onboard: true,
-                        ..Default::default()
-                    }
+                    Config::new_install()
                 } else {
                     info!("Loading config data at: {}", config_filepath.display());
                     serde_json::from_str(&config_data)
@@ -176,10 +204,7 @@ impl Config {
                     .write(true)
                     .open(&config_filepath)
                     .unwrap();
-                Config {
-                    onboard: true,
-                    ..Default::default()
-                }
+                Config::new_install()
             }
         };
 
@@ -237,6 +262,12 @@ impl Config {
     pub fn default_db_path() -> PathBuf {
         Self::default_config_dir().join(EAM_DB)
     }
+    pub fn default_backup_dir() -> PathBuf {
+        Self::default_config_dir().join(EAM_BACKUPS)
+    }
+    pub fn default_settings_snapshot_dir() -> PathBuf {
+        Self::default_config_dir().join(EAM_SETTINGS_SNAPSHOTS)
+    }
 }
 
 fn default_str() -> String {
@@ -245,6 +276,12 @@ fn default_str() -> String {
 fn default_true() -> bool {
     true
 }
+fn default_backup_interval_days() -> u32 {
+    7
+}
+fn default_backup_keep() -> usize {
+    10
+}
 
 fn default_version() -> String {
     "0.1.1".to_string()
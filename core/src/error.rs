@@ -116,6 +116,52 @@ pub enum Error {
 
     #[snafu(display("Addon {} has no download URL", id))]
     AddonMissingDownloadUrl { id: i32 },
+
+    #[snafu(display("Unable to read backup {}: {}", path.display(), source))]
+    BackupRead { source: io::Error, path: PathBuf },
+
+    #[snafu(display("Unable to write backup {}: {}", path.display(), source))]
+    BackupWrite { source: io::Error, path: PathBuf },
+
+    #[snafu(display("Error with backup archive: {}", source))]
+    BackupArchive { source: zip::result::ZipError },
+
+    #[snafu(display("Backup {} was not written", path.display()))]
+    BackupSnapshotMissing { path: PathBuf },
+
+    #[snafu(display("Unable to parse backup: {}", source))]
+    BackupParse { source: serde_json::Error },
+
+    #[snafu(display("Unable to serialize backup: {}", source))]
+    BackupSerialize { source: serde_json::Error },
+
+    #[snafu(display("Unable to parse modpack: {}", source))]
+    ModpackParse { source: toml::de::Error },
+
+    #[snafu(display("Unable to serialize modpack: {}", source))]
+    ModpackSerialize { source: toml::ser::Error },
+
+    #[snafu(display("Unable to read modpack {}: {}", path.display(), source))]
+    ModpackRead { source: io::Error, path: PathBuf },
+
+    #[snafu(display("Unable to write modpack {}: {}", path.display(), source))]
+    ModpackWrite { source: io::Error, path: PathBuf },
+
+    #[snafu(display("Unable to parse desired state: {}", source))]
+    StateParse { source: toml::de::Error },
+
+    #[snafu(display("Unable to read desired state {}: {}", path.display(), source))]
+    StateRead { source: io::Error, path: PathBuf },
+
+    #[snafu(display("Unable to delete SavedVariables file {}: {}", path.display(), source))]
+    SavedVariablesDelete { source: io::Error, path: PathBuf },
+
+    #[snafu(display(
+        "Backup format version {} is newer than supported version {}",
+        version,
+        supported
+    ))]
+    BackupVersionUnsupported { version: u64, supported: u64 },
 }
 
 pub type Result<T, E = Error> = std::result::Result<T, E>;
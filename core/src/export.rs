@@ -0,0 +1,153 @@
+use std::fmt::Write;
+
+use crate::service::result::AddonShowDetails;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// Comma separated ids, as in Minion's `BU-addons.txt` backups
+    #[default]
+    Minion,
+    /// One ESOUI link per line
+    Urls,
+    Csv,
+    /// A table with linked names, for forum posts and guild notes
+    Markdown,
+}
+
+impl ExportFormat {
+    pub const ALL: [ExportFormat; 4] = [
+        ExportFormat::Minion,
+        ExportFormat::Urls,
+        ExportFormat::Csv,
+        ExportFormat::Markdown,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportFormat::Minion => "Minion backup",
+            ExportFormat::Urls => "ESOUI links",
+            ExportFormat::Csv => "CSV",
+            ExportFormat::Markdown => "Markdown",
+        }
+    }
+
+    /// Default name when saving to a file, Minion only restores files named like its backups
+    pub fn file_name(&self) -> &'static str {
+        match self {
+            ExportFormat::Minion => "BU-addons.txt",
+            ExportFormat::Urls => "addons.txt",
+            ExportFormat::Csv => "addons.csv",
+            ExportFormat::Markdown => "addons.md",
+        }
+    }
+}
+
+pub fn export_addons(addons: &[AddonShowDetails], format: ExportFormat) -> String {
+    match format {
+        ExportFormat::Minion => {
+            let ids: Vec<String> = addons.iter().map(|a| a.id.to_string()).collect();
+            ids.join(",")
+        }
+        ExportFormat::Urls => addons.iter().fold(String::new(), |mut out, addon| {
+            let _ = writeln!(out, "{}", addon_url(addon));
+            out
+        }),
+        ExportFormat::Csv => {
+            let mut csv = String::from("id,name,author,version,url\n");
+            for addon in addons {
+                let _ = writeln!(
+                    csv,
+                    "{},{},{},{},{}",
+                    addon.id,
+                    csv_field(&addon.name),
+                    csv_field(&addon.author_name),
+                    csv_field(installed_version(addon)),
+                    csv_field(&addon_url(addon))
+                );
+            }
+            csv
+        }
+        ExportFormat::Markdown => {
+            let mut md = String::from("| Name | Author | Version |\n| --- | --- | --- |\n");
+            for addon in addons {
+                let _ = writeln!(
+                    md,
+                    "| [{}]({}) | {} | {} |",
+                    markdown_cell(&addon.name)
+                        .replace('[', "\\[")
+                        .replace(']', "\\]"),
+                    addon_url(addon),
+                    markdown_cell(&addon.author_name),
+                    markdown_cell(installed_version(addon))
+                );
+            }
+            md
+        }
+    }
+}
+
+/// The addon's ESOUI page
+fn addon_url(addon: &AddonShowDetails) -> String {
+    if addon.file_info_url.is_empty() {
+        format!("https://www.esoui.com/downloads/info{}", addon.id)
+    } else {
+        addon.file_info_url.clone()
+    }
+}
+
+fn installed_version(addon: &AddonShowDetails) -> &str {
+    addon.installed_version.as_deref().unwrap_or(&addon.version)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_owned()
+    }
+}
+
+fn markdown_cell(value: &str) -> String {
+    value.replace('|', "\\|").replace('\n', " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addon(id: i32, name: &str, author: &str) -> AddonShowDetails {
+        AddonShowDetails {
+            id,
+            name: name.to_owned(),
+            author_name: author.to_owned(),
+            version: "2.0".to_owned(),
+            installed_version: Some("1.5".to_owned()),
+            file_info_url: format!("https://www.esoui.com/downloads/info{id}-Addon.html"),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_export_addons() {
+        let addons = vec![
+            addon(7, "LibAddonMenu", "sirinsidiator"),
+            addon(1360, "Combat Metrics, \"CMX\"", "Solinur | Kyzer"),
+        ];
+
+        assert_eq!(export_addons(&addons, ExportFormat::Minion), "7,1360");
+        assert_eq!(
+            export_addons(&addons, ExportFormat::Urls),
+            "https://www.esoui.com/downloads/info7-Addon.html\n\
+            https://www.esoui.com/downloads/info1360-Addon.html\n"
+        );
+        let csv = export_addons(&addons, ExportFormat::Csv);
+        assert!(csv.starts_with("id,name,author,version,url\n"));
+        assert!(csv.contains(
+            "1360,\"Combat Metrics, \"\"CMX\"\"\",Solinur | Kyzer,1.5,https://www.esoui.com/downloads/info1360-Addon.html\n"
+        ));
+        let md = export_addons(&addons, ExportFormat::Markdown);
+        assert!(md.contains(
+            "| [Combat Metrics, \"CMX\"](https://www.esoui.com/downloads/info1360-Addon.html) | Solinur \\| Kyzer | 1.5 |\n"
+        ));
+    }
+}
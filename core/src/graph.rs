@@ -0,0 +1,262 @@
+use std::collections::HashSet;
+use std::fmt::Write;
+
+use serde_derive::Serialize;
+
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum NodeKind {
+    /// An installed addon
+    Addon,
+    /// A required dependency dir that no installed addon provides
+    Unresolved,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphNode {
+    pub kind: NodeKind,
+    pub addon_id: Option<i32>,
+    pub label: String,
+    /// Installed to satisfy another addon rather than by the user
+    pub dependency: bool,
+}
+
+/// `from` depends on `to` through the `dir` it ships.
+#[derive(Clone, Debug, Serialize)]
+pub struct GraphEdge {
+    pub from: usize,
+    pub to: usize,
+    pub dir: String,
+    pub optional: bool,
+}
+
+/// Installed addons and what they depend on, edges point from an addon to its dependencies.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct DependencyGraph {
+    pub nodes: Vec<GraphNode>,
+    pub edges: Vec<GraphEdge>,
+}
+
+impl DependencyGraph {
+    /// Groups of nodes that depend on each other in a loop, using required edges only.
+    pub fn cycles(&self) -> Vec<Vec<usize>> {
+        let mut adjacency = vec![vec![]; self.nodes.len()];
+        for edge in self.edges.iter().filter(|e| !e.optional) {
+            adjacency[edge.from].push(edge.to);
+        }
+
+        // Tarjan's strongly connected components
+        struct State {
+            index: Vec<Option<usize>>,
+            low: Vec<usize>,
+            on_stack: Vec<bool>,
+            stack: Vec<usize>,
+            next: usize,
+            components: Vec<Vec<usize>>,
+        }
+        fn connect(v: usize, adjacency: &[Vec<usize>], state: &mut State) {
+            state.index[v] = Some(state.next);
+            state.low[v] = state.next;
+            state.next += 1;
+            state.stack.push(v);
+            state.on_stack[v] = true;
+            for &w in adjacency[v].iter() {
+                match state.index[w] {
+                    None => {
+                        connect(w, adjacency, state);
+                        state.low[v] = state.low[v].min(state.low[w]);
+                    }
+                    Some(index) if state.on_stack[w] => {
+                        state.low[v] = state.low[v].min(index);
+                    }
+                    _ => {}
+                }
+            }
+            if Some(state.low[v]) == state.index[v] {
+                let mut component = vec![];
+                while let Some(w) = state.stack.pop() {
+                    state.on_stack[w] = false;
+                    component.push(w);
+                    if w == v {
+                        break;
+                    }
+                }
+                state.components.push(component);
+            }
+        }
+
+        let mut state = State {
+            index: vec![None; self.nodes.len()],
+            low: vec![0; self.nodes.len()],
+            on_stack: vec![false; self.nodes.len()],
+            stack: vec![],
+            next: 0,
+            components: vec![],
+        };
+        for v in 0..self.nodes.len() {
+            if state.index[v].is_none() {
+                connect(v, &adjacency, &mut state);
+            }
+        }
+
+        state
+            .components
+            .into_iter()
+            .filter(|c| c.len() > 1 || adjacency[c[0]].contains(&c[0]))
+            .map(|mut c| {
+                c.sort();
+                c
+            })
+            .collect()
+    }
+
+    /// Addons installed as a dependency that no installed addon requires anymore.
+    pub fn leaf_libraries(&self) -> Vec<usize> {
+        let required: HashSet<usize> = self
+            .edges
+            .iter()
+            .filter(|e| !e.optional)
+            .map(|e| e.to)
+            .collect();
+        self.nodes
+            .iter()
+            .enumerate()
+            .filter(|(i, n)| n.kind == NodeKind::Addon && n.dependency && !required.contains(i))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    pub fn to_dot(&self) -> String {
+        let cycle_nodes: HashSet<usize> = self.cycles().into_iter().flatten().collect();
+        let leaves: HashSet<usize> = self.leaf_libraries().into_iter().collect();
+
+        let mut dot =
+            String::from("digraph dependencies {\n    rankdir=LR;\n    node [shape=box];\n");
+        for (i, node) in self.nodes.iter().enumerate() {
+            let mut attrs = vec![format!("label=\"{}\"", escape(&node.label))];
+            if node.kind == NodeKind::Unresolved {
+                attrs.push("style=dashed".to_owned());
+                attrs.push("color=red".to_owned());
+            } else if leaves.contains(&i) {
+                attrs.push("style=filled".to_owned());
+                attrs.push("fillcolor=lightgray".to_owned());
+            }
+            if cycle_nodes.contains(&i) {
+                attrs.push("color=orange".to_owned());
+            }
+            let _ = writeln!(dot, "    n{i} [{}];", attrs.join(", "));
+        }
+        for edge in self.edges.iter() {
+            let mut attrs = vec![format!("label=\"{}\"", escape(&edge.dir))];
+            if edge.optional {
+                attrs.push("style=dashed".to_owned());
+            }
+            if self.nodes[edge.to].kind == NodeKind::Unresolved {
+                attrs.push("color=red".to_owned());
+            } else if cycle_nodes.contains(&edge.from) && cycle_nodes.contains(&edge.to) {
+                attrs.push("color=orange".to_owned());
+            }
+            let _ = writeln!(
+                dot,
+                "    n{} -> n{} [{}];",
+                edge.from,
+                edge.to,
+                attrs.join(", ")
+            );
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    pub fn to_json(&self) -> serde_json::Result<String> {
+        serde_json::to_string_pretty(self)
+    }
+}
+
+fn escape(label: &str) -> String {
+    label.replace('\\', "\\\\").replace('"', "\\\"")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addon(label: &str, dependency: bool) -> GraphNode {
+        GraphNode {
+            kind: NodeKind::Addon,
+            addon_id: None,
+            label: label.to_owned(),
+            dependency,
+        }
+    }
+
+    fn edge(from: usize, to: usize, optional: bool) -> GraphEdge {
+        GraphEdge {
+            from,
+            to,
+            dir: String::new(),
+            optional,
+        }
+    }
+
+    #[test]
+    fn test_cycles() {
+        let graph = DependencyGraph {
+            nodes: vec![
+                addon("Main", false),
+                addon("LibA", true),
+                addon("LibB", true),
+                addon("LibC", true),
+            ],
+            edges: vec![
+                edge(0, 1, false),
+                edge(1, 2, false),
+                edge(2, 1, false),
+                // optional edges never form a cycle
+                edge(3, 0, true),
+                edge(0, 3, false),
+            ],
+        };
+        assert_eq!(graph.cycles(), vec![vec![1, 2]]);
+    }
+
+    #[test]
+    fn test_leaf_libraries() {
+        let graph = DependencyGraph {
+            nodes: vec![
+                addon("Main", false),
+                addon("LibA", true),
+                addon("LibB", true),
+                addon("LibC", true),
+                addon("Explicit", false),
+            ],
+            edges: vec![edge(0, 1, false), edge(1, 2, false), edge(0, 3, true)],
+        };
+        assert_eq!(graph.leaf_libraries(), vec![3]);
+    }
+
+    #[test]
+    fn test_to_dot() {
+        let graph = DependencyGraph {
+            nodes: vec![
+                addon("Main \"UI\"", false),
+                GraphNode {
+                    kind: NodeKind::Unresolved,
+                    addon_id: None,
+                    label: "LibMissing".to_owned(),
+                    dependency: false,
+                },
+            ],
+            edges: vec![GraphEdge {
+                from: 0,
+                to: 1,
+                dir: "LibMissing".to_owned(),
+                optional: false,
+            }],
+        };
+        let dot = graph.to_dot();
+        assert!(dot.contains("n0 [label=\"Main \\\"UI\\\"\"];"));
+        assert!(dot.contains("n1 [label=\"LibMissing\", style=dashed, color=red];"));
+        assert!(dot.contains("n0 -> n1 [label=\"LibMissing\", color=red];"));
+    }
+}
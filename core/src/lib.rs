@@ -7,7 +7,11 @@ pub mod addons;
 pub mod api;
 pub mod config;
 pub mod error;
+pub mod export;
+pub mod graph;
+pub mod modpack;
 pub mod service;
+pub mod state;
 
 pub fn get_missing_dependencies(installed: &[Addon]) -> impl Iterator<Item = String> {
     let mut missing = HashSet::new();
@@ -0,0 +1,207 @@
+use std::cmp::Ordering;
+
+use serde_derive::{Deserialize, Serialize};
+use snafu::ResultExt;
+use version_compare::{Cmp, Version};
+
+use crate::error::{self, Result};
+use crate::service::result::AddonShowDetails;
+
+/// A shareable addon set, stored as TOML:
+///
+/// ```toml
+/// name = "Trial Team"
+/// description = "Everything our raids expect"
+///
+/// [[addon]]
+/// id = 1360
+/// name = "Combat Metrics"
+/// min_version = "1.5.2"
+///
+/// [[addon]]
+/// id = 7
+/// name = "LibAddonMenu"
+/// version = "2.0 r36"
+/// optional = true
+/// ```
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct Modpack {
+    pub name: String,
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    #[serde(default, rename = "addon")]
+    pub addons: Vec<ModpackAddon>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub struct ModpackAddon {
+    /// ESOUI id, entries are matched by it
+    pub id: i32,
+    /// Only for people reading the file
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub name: String,
+    /// Exact version everyone should have
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Lowest version that will do, ignored if `version` is set
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub min_version: Option<String>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub optional: bool,
+}
+
+/// How versions are pinned when exporting a modpack
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum PinMode {
+    #[default]
+    None,
+    Minimum,
+    Exact,
+}
+
+impl PinMode {
+    pub const ALL: [PinMode; 3] = [PinMode::None, PinMode::Minimum, PinMode::Exact];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            PinMode::None => "No versions",
+            PinMode::Minimum => "Minimum versions",
+            PinMode::Exact => "Exact versions",
+        }
+    }
+}
+
+/// How an installed version compares to an entry's pin
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PinStatus {
+    Satisfied,
+    Older,
+    Newer,
+}
+
+impl Modpack {
+    /// A modpack of `addons`, pinned to their installed versions as `pin` asks.
+    pub fn from_installed(
+        name: String,
+        description: String,
+        addons: &[AddonShowDetails],
+        pin: PinMode,
+    ) -> Self {
+        let addons = addons
+            .iter()
+            .map(|addon| {
+                let installed = addon
+                    .installed_version
+                    .clone()
+                    .unwrap_or_else(|| addon.version.clone());
+                ModpackAddon {
+                    id: addon.id,
+                    name: addon.name.clone(),
+                    version: (pin == PinMode::Exact).then(|| installed.clone()),
+                    min_version: (pin == PinMode::Minimum).then_some(installed),
+                    optional: false,
+                }
+            })
+            .collect();
+        Modpack {
+            name,
+            description,
+            addons,
+        }
+    }
+
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).context(error::ModpackParseSnafu)
+    }
+
+    pub fn to_toml(&self) -> Result<String> {
+        toml::to_string_pretty(self).context(error::ModpackSerializeSnafu)
+    }
+}
+
+impl ModpackAddon {
+    /// Versions that can't be compared count as older unless they're the same text.
+    pub fn check(&self, installed: &str) -> PinStatus {
+        if let Some(exact) = self.version.as_deref() {
+            return match compare_versions(installed, exact) {
+                Some(Ordering::Equal) => PinStatus::Satisfied,
+                Some(Ordering::Greater) => PinStatus::Newer,
+                _ => PinStatus::Older,
+            };
+        }
+        match self.min_version.as_deref() {
+            Some(min) => match compare_versions(installed, min) {
+                Some(Ordering::Equal | Ordering::Greater) => PinStatus::Satisfied,
+                _ => PinStatus::Older,
+            },
+            None => PinStatus::Satisfied,
+        }
+    }
+}
+
+fn compare_versions(a: &str, b: &str) -> Option<Ordering> {
+    if a.trim() == b.trim() {
+        return Some(Ordering::Equal);
+    }
+    match Version::from(a)?.compare(Version::from(b)?) {
+        Cmp::Lt => Some(Ordering::Less),
+        Cmp::Eq => Some(Ordering::Equal),
+        Cmp::Gt => Some(Ordering::Greater),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_modpack_toml() {
+        let toml = r#"
+            name = "Trial Team"
+
+            [[addon]]
+            id = 1360
+            min_version = "1.5.2"
+
+            [[addon]]
+            id = 7
+            name = "LibAddonMenu"
+            version = "2.0"
+            optional = true
+        "#;
+        let pack = Modpack::from_toml(toml).unwrap();
+        assert_eq!(pack.name, "Trial Team");
+        assert_eq!(pack.addons.len(), 2);
+        assert_eq!(pack.addons[0].min_version.as_deref(), Some("1.5.2"));
+        assert!(!pack.addons[0].optional);
+        assert!(pack.addons[1].optional);
+
+        let written = pack.to_toml().unwrap();
+        assert!(!written.contains("description"));
+        assert!(!written.contains("optional = false"));
+        assert_eq!(Modpack::from_toml(&written).unwrap(), pack);
+
+        assert!(Modpack::from_toml("[[addon]]\nid = 7").is_err());
+    }
+
+    #[test]
+    fn test_pin_check() {
+        let entry = |version: Option<&str>, min_version: Option<&str>| ModpackAddon {
+            id: 1,
+            version: version.map(str::to_owned),
+            min_version: min_version.map(str::to_owned),
+            ..Default::default()
+        };
+        assert_eq!(entry(None, None).check("anything"), PinStatus::Satisfied);
+        assert_eq!(entry(None, Some("1.5")).check("1.10"), PinStatus::Satisfied);
+        assert_eq!(entry(None, Some("1.5")).check("1.4.9"), PinStatus::Older);
+        assert_eq!(entry(Some("2.0"), None).check("2.0"), PinStatus::Satisfied);
+        assert_eq!(entry(Some("2.0"), None).check("2.1"), PinStatus::Newer);
+        assert_eq!(entry(Some("2.0"), None).check("1.9"), PinStatus::Older);
+        assert_eq!(
+            entry(Some("2.0"), Some("3.0")).check("2.0"),
+            PinStatus::Satisfied
+        );
+    }
+}
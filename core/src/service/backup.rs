@@ -1,5 +1,35 @@
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+
 use sea_orm::FromQueryResult;
 use serde::{Deserialize, Serialize};
+use snafu::{ResultExt, ensure};
+
+use super::result::{AddonRef, BackupDiff, VersionChange};
+use crate::config::{Config, TTCRegion};
+use crate::error::{self, Result};
+
+/// Bumped whenever [`BackupData`] changes in a way older readers can't handle.
+pub const BACKUP_FORMAT_VERSION: u64 = 2;
+/// Name of the [`BackupData`] JSON inside a backup archive
+pub const BACKUP_MANIFEST: &str = "backup.json";
+/// Folder next to AddOns where the game keeps addon settings
+pub const SAVED_VARIABLES_DIR: &str = "SavedVariables";
+/// File next to AddOns holding which addons are enabled per character
+pub const ADDON_SETTINGS_FILE: &str = "AddOnSettings.txt";
+
+/// Game files to include alongside the manifest. With neither set the backup is a
+/// plain JSON file, otherwise a zip archive.
+#[derive(Clone, Copy, Default, Debug)]
+pub struct BackupOptions {
+    pub saved_variables: bool,
+    pub addon_settings: bool,
+}
+impl BackupOptions {
+    pub fn is_archive(&self) -> bool {
+        self.saved_variables || self.addon_settings
+    }
+}
 
 #[derive(FromQueryResult, Clone, Default, Debug, Serialize, Deserialize)]
 /// Backup type of InstalledAddon without version numbers (so all can be updated on backup restore)
@@ -15,8 +45,199 @@ pub struct BackupManualDependency {
     pub ignore: Option<bool>,
 }
 
+/// Format written before backups carried a `format_version`.
 #[derive(Serialize, Deserialize)]
-pub struct BackupData {
+pub struct BackupDataV1 {
     pub installed_addons: Vec<BackupInstalledAddon>,
     pub manual_dependencies: Vec<BackupManualDependency>,
 }
+
+#[derive(FromQueryResult, Clone, Default, Debug, Serialize, Deserialize)]
+pub struct BackupAddon {
+    pub addon_id: i32,
+    pub name: String,
+    /// Exact version installed when the backup was made, "0" if unknown
+    pub version: String,
+    pub date: String,
+    /// `explicit` or `dependency`
+    pub reason: String,
+}
+
+#[derive(Clone, Default, Debug, Serialize, Deserialize, PartialEq)]
+pub struct BackupConfig {
+    pub addon_dir: PathBuf,
+    pub ttc_region: TTCRegion,
+    pub update_ttc_pricetable: bool,
+    pub update_hm_data: bool,
+}
+impl From<&Config> for BackupConfig {
+    fn from(config: &Config) -> Self {
+        Self {
+            addon_dir: config.addon_dir.clone(),
+            ttc_region: config.ttc_region.clone(),
+            update_ttc_pricetable: config.update_ttc_pricetable,
+            update_hm_data: config.update_hm_data,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct BackupData {
+    pub format_version: u64,
+    pub created: String,
+    /// Missing for backups converted from v1
+    pub config: Option<BackupConfig>,
+    pub installed_addons: Vec<BackupAddon>,
+    pub manual_dependencies: Vec<BackupManualDependency>,
+}
+impl From<BackupDataV1> for BackupData {
+    fn from(v1: BackupDataV1) -> Self {
+        Self {
+            format_version: BACKUP_FORMAT_VERSION,
+            created: String::new(),
+            config: None,
+            installed_addons: v1
+                .installed_addons
+                .into_iter()
+                .map(|a| BackupAddon {
+                    addon_id: a.addon_id,
+                    version: "0".to_owned(),
+                    date: a.date,
+                    reason: "explicit".to_owned(),
+                    ..Default::default()
+                })
+                .collect(),
+            manual_dependencies: v1.manual_dependencies,
+        }
+    }
+}
+
+/// Reads any backup version this build understands, upgrading older formats.
+pub fn parse_backup(json: &str) -> Result<BackupData> {
+    let value: serde_json::Value = serde_json::from_str(json).context(error::BackupParseSnafu)?;
+    let version = value
+        .get("format_version")
+        .and_then(|v| v.as_u64())
+        .unwrap_or(1);
+    ensure!(
+        version <= BACKUP_FORMAT_VERSION,
+        error::BackupVersionUnsupportedSnafu {
+            version,
+            supported: BACKUP_FORMAT_VERSION,
+        }
+    );
+    if version == 1 {
+        let v1: BackupDataV1 = serde_json::from_value(value).context(error::BackupParseSnafu)?;
+        return Ok(v1.into());
+    }
+    serde_json::from_value(value).context(error::BackupParseSnafu)
+}
+
+/// Compares the addons of a backup with the installed ones.
+pub fn diff_addons(backup: &[BackupAddon], installed: &[BackupAddon]) -> BackupDiff {
+    let addon_ref = |a: &BackupAddon| AddonRef {
+        id: a.addon_id,
+        name: a.name.clone(),
+    };
+    let installed_by_id: HashMap<i32, &BackupAddon> =
+        installed.iter().map(|a| (a.addon_id, a)).collect();
+    let backup_ids: HashSet<i32> = backup.iter().map(|a| a.addon_id).collect();
+
+    let mut diff = BackupDiff::default();
+    for addon in backup.iter() {
+        match installed_by_id.get(&addon.addon_id) {
+            None => diff.removed.push(addon_ref(addon)),
+            // v1 backups don't know their versions
+            Some(current) if addon.version != "0" && current.version != addon.version => {
+                diff.changed.push(VersionChange {
+                    addon: addon_ref(current),
+                    backup_version: addon.version.clone(),
+                    installed_version: current.version.clone(),
+                })
+            }
+            Some(_) => {}
+        }
+    }
+    diff.added = installed
+        .iter()
+        .filter(|a| !backup_ids.contains(&a.addon_id))
+        .map(addon_ref)
+        .collect();
+    diff
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_backup_v1() {
+        let data = parse_backup(
+            r#"{"installed_addons":[{"addon_id":7,"date":"1650000000"}],"manual_dependencies":[{"addon_dir":"LibA","satisfied_by":null,"ignore":true}]}"#,
+        )
+        .unwrap();
+        assert_eq!(data.format_version, BACKUP_FORMAT_VERSION);
+        assert!(data.config.is_none());
+        assert_eq!(data.installed_addons[0].addon_id, 7);
+        assert_eq!(data.installed_addons[0].version, "0");
+        assert_eq!(data.installed_addons[0].reason, "explicit");
+        assert_eq!(data.manual_dependencies[0].ignore, Some(true));
+    }
+
+    #[test]
+    fn test_parse_backup_v2() {
+        let data = BackupData {
+            format_version: BACKUP_FORMAT_VERSION,
+            created: "2026-10-19 10:00:00 UTC".to_owned(),
+            config: Some(BackupConfig {
+                addon_dir: PathBuf::from("/games/AddOns"),
+                ttc_region: TTCRegion::EU,
+                update_ttc_pricetable: true,
+                update_hm_data: false,
+            }),
+            installed_addons: vec![BackupAddon {
+                addon_id: 7,
+                name: "LibA".to_owned(),
+                version: "1.2".to_owned(),
+                date: "1650000000".to_owned(),
+                reason: "dependency".to_owned(),
+            }],
+            manual_dependencies: vec![],
+        };
+        let parsed = parse_backup(&serde_json::to_string(&data).unwrap()).unwrap();
+        assert_eq!(parsed.config, data.config);
+        assert_eq!(parsed.installed_addons[0].version, "1.2");
+        assert_eq!(parsed.installed_addons[0].reason, "dependency");
+    }
+
+    #[test]
+    fn test_diff_addons() {
+        let addon = |addon_id: i32, version: &str| BackupAddon {
+            addon_id,
+            name: format!("Addon{addon_id}"),
+            version: version.to_owned(),
+            ..Default::default()
+        };
+        let diff = diff_addons(
+            &[addon(1, "1.0"), addon(2, "2.0"), addon(3, "0")],
+            &[addon(2, "2.1"), addon(3, "3.0"), addon(4, "4.0")],
+        );
+        assert_eq!(diff.removed.len(), 1);
+        assert_eq!(diff.removed[0].id, 1);
+        assert_eq!(diff.changed.len(), 1);
+        assert_eq!(diff.changed[0].addon.id, 2);
+        assert_eq!(diff.changed[0].backup_version, "2.0");
+        assert_eq!(diff.changed[0].installed_version, "2.1");
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].id, 4);
+    }
+
+    #[test]
+    fn test_parse_backup_unknown_version() {
+        let err = parse_backup(r#"{"format_version":99,"installed_addons":[]}"#).unwrap_err();
+        assert!(matches!(
+            err,
+            error::Error::BackupVersionUnsupported { version: 99, .. }
+        ));
+    }
+}
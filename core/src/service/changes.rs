@@ -0,0 +1,452 @@
+use std::collections::HashMap;
+
+use entity::addon as DbAddon;
+use entity::addon_detail as AddonDetail;
+use entity::addon_dir as AddonDir;
+use entity::installed_addon::{self as InstalledAddon, InstallReason};
+use entity::manual_dependency as ManualDependency;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, EntityTrait, PaginatorTrait, QueryFilter};
+use snafu::{OptionExt, ResultExt, ensure};
+use tracing::log::warn;
+
+use super::fs_util::{fs_find_manifest_dirs, fs_saved_variables_files};
+use super::result::{ChangePlan, DbAction, InstallPlan, PlannedDownload, RestorePlan, SyncPlan};
+use super::{AddonService, HM_DATA_ADDON_ID, fs_hm_zones_due};
+use crate::api::HM_DATA_URL;
+use crate::config::Config;
+use crate::error::{self, Result};
+
+/// What installing an addon at the catalog version does to the installed copy.
+#[derive(Debug, PartialEq)]
+enum InstallChange {
+    /// Installed and up to date
+    Nothing,
+    /// Up to date, but installed as a library, it's only marked as installed on purpose
+    MarkExplicit,
+    /// Not installed or outdated, the archive is downloaded and extracted
+    Download,
+    /// Up to date, but updating downloads and extracts the archive again
+    Reinstall,
+}
+
+impl AddonService {
+    /// What installing or updating `addon_ids` with [`Self::install`] and the same
+    /// `update` changes, read from the database, the disk and ESOUI without changing
+    /// anything. Addons that are installed and up to date change nothing, unless
+    /// `update` reinstalls them. Dependencies aren't followed, see
+    /// [`Self::plan_install_plan`] for a resolved plan.
+    pub fn plan_install(
+        &self,
+        addon_ids: Vec<i32>,
+        update: bool,
+    ) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            service
+                .p_plan_install(&addon_ids, update, &HashMap::new(), &mut changes)
+                .await?;
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::install_plan`] changes, dependencies included. Archives downloaded
+    /// while resolving are listed with their size.
+    pub fn plan_install_plan(&self, plan: InstallPlan) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let changes = service.p_plan_install_plan(&plan).await?;
+            Ok(changes)
+        })
+    }
+
+    /// What removing `addon_ids` changes, addons that aren't installed are skipped.
+    pub fn plan_remove(&self, addon_ids: Vec<i32>) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            service.p_plan_remove(&addon_ids, &mut changes).await?;
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::autoremove`] changes.
+    pub fn plan_autoremove(&self) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let ids: Vec<i32> = service
+                .p_autoremove_candidates()
+                .await?
+                .into_iter()
+                .map(|a| a.id)
+                .collect();
+            let mut changes = ChangePlan::default();
+            service.p_plan_remove(&ids, &mut changes).await?;
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::remove_untracked_dirs`] changes.
+    pub fn plan_remove_untracked(&self, dirs: Vec<String>) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            changes.dirs_deleted.extend(
+                service
+                    .p_untracked_dirs()
+                    .await?
+                    .into_iter()
+                    .filter(|d| dirs.contains(&d.dir))
+                    .map(|d| d.path),
+            );
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::restore_plan`] changes.
+    pub fn plan_restore(&self, plan: RestorePlan) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            if !plan.manual_dependencies.is_empty() {
+                changes.db("manual_dependency", DbAction::Delete, "all rows".to_owned());
+                changes.db(
+                    "manual_dependency",
+                    DbAction::Insert,
+                    format!("{} rows", plan.manual_dependencies.len()),
+                );
+            }
+            if plan.archive.is_some() {
+                let game_dir = service.get_game_dir();
+                changes.files_written.extend(
+                    plan.files
+                        .iter()
+                        .filter(|f| !f.conflict || f.overwrite)
+                        .map(|f| game_dir.join(&f.path)),
+                );
+            }
+            for entry in plan.present.iter() {
+                changes.db(
+                    "installed_addon",
+                    DbAction::Insert,
+                    format!("addon_id = {}", entry.addon.id),
+                );
+            }
+            let ids: Vec<i32> = plan.to_install.iter().map(|e| e.addon.id).collect();
+            service
+                .p_plan_install(&ids, false, &HashMap::new(), &mut changes)
+                .await?;
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::sync`] changes. Libraries only the removed addons required are
+    /// removed after them, and aren't included.
+    pub fn plan_sync(&self, plan: SyncPlan) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            let ids: Vec<i32> = plan
+                .install
+                .iter()
+                .chain(plan.update.iter().map(|u| &u.addon))
+                .map(|a| a.id)
+                .collect();
+            service
+                .p_plan_install(&ids, false, &HashMap::new(), &mut changes)
+                .await?;
+            let ids: Vec<i32> = plan.remove.iter().map(|a| a.id).collect();
+            service.p_plan_remove(&ids, &mut changes).await?;
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::update_ttc_pricetable`] downloads, PriceTables the server hasn't
+    /// changed since the last download are skipped.
+    pub fn plan_ttc_pricetable(&self) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let mut changes = ChangePlan::default();
+            for (domain, _) in service.p_ttc_downloads().await {
+                let url = format!("https://{domain}/download/PriceTable");
+                let size = service.p_download_size(&url).await;
+                changes.downloads.push(PlannedDownload {
+                    label: format!("TTC PriceTable ({domain})"),
+                    url,
+                    size,
+                });
+            }
+            if !changes.downloads.is_empty() {
+                changes
+                    .dirs_written
+                    .push(service.get_addon_dir().join("TamrielTradeCentre"));
+            }
+            Ok(changes)
+        })
+    }
+
+    /// What [`Self::update_hm_data`] writes. The merged data is the response to an
+    /// upload, so its size isn't known up front.
+    pub fn plan_hm_data(&self) -> ImmediateValuePromise<ChangePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let hmd_addon = InstalledAddon::Entity::find_by_id(HM_DATA_ADDON_ID)
+                .one(&service.db)
+                .await
+                .context(error::DbGetSnafu)?;
+            ensure!(hmd_addon.is_some(), error::HarvestMapDataNotInstalledSnafu);
+
+            let mut changes = ChangePlan::default();
+            for zone in fs_hm_zones_due(&service.config, chrono::Utc::now())? {
+                changes.downloads.push(PlannedDownload {
+                    label: format!("HarvestMap {}", zone.zone),
+                    url: HM_DATA_URL.to_owned(),
+                    size: None,
+                });
+                changes.files_written.push(zone.out_file);
+            }
+            Ok(changes)
+        })
+    }
+
+    async fn p_plan_install_plan(&self, plan: &InstallPlan) -> Result<ChangePlan> {
+        let sizes: HashMap<i32, u64> = plan
+            .steps
+            .iter()
+            .filter_map(|step| {
+                let archive = step.archive.as_ref()?;
+                Some((step.addon.id, archive.metadata().ok()?.len()))
+            })
+            .collect();
+        let ids: Vec<i32> = plan.steps.iter().map(|s| s.addon.id).collect();
+        let mut changes = ChangePlan::default();
+        self.p_plan_install(&ids, false, &sizes, &mut changes)
+            .await?;
+        Ok(changes)
+    }
+
+    /// Adds what installing each of `addon_ids` changes, `archives` holds the sizes of
+    /// archives that are already downloaded.
+    async fn p_plan_install(
+        &self,
+        addon_ids: &[i32],
+        update: bool,
+        archives: &HashMap<i32, u64>,
+        changes: &mut ChangePlan,
+    ) -> Result<()> {
+        let addon_dir = self.get_addon_dir();
+        let game_dir = self.get_game_dir();
+        // walked once, and only if an installed addon's settings are snapshot
+        let mut manifest_dirs = None;
+        for addon_id in addon_ids.iter().copied() {
+            let entry = DbAddon::Entity::find_by_id(addon_id)
+                .one(&self.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .context(error::AddonNotFoundSnafu { id: addon_id })?;
+            let installed = InstalledAddon::Entity::find_by_id(addon_id)
+                .one(&self.db)
+                .await
+                .context(error::DbGetSnafu)?;
+            let rows = format!("addon_id = {addon_id}");
+            let label = match install_change(installed.as_ref(), &entry.version, update) {
+                InstallChange::Nothing => continue,
+                InstallChange::MarkExplicit => {
+                    changes.db("installed_addon", DbAction::Update, rows);
+                    continue;
+                }
+                InstallChange::Download => format!("{} {}", entry.name, entry.version),
+                InstallChange::Reinstall => {
+                    format!("{} {} (reinstall)", entry.name, entry.version)
+                }
+            };
+
+            // details are fetched and cached before installing if they're missing or stale
+            let detail = AddonDetail::Entity::find_by_id(addon_id)
+                .one(&self.db)
+                .await
+                .context(error::DbGetSnafu)?;
+            let cached = detail.is_some_and(|d| d.version.as_ref() == Some(&entry.version))
+                && entry.file_name.is_some();
+            let url = match entry.download.clone() {
+                Some(url) if cached => url,
+                _ => {
+                    changes.db("addon", DbAction::Update, format!("id = {addon_id}"));
+                    changes.db("addon_detail", DbAction::Insert, format!("id = {addon_id}"));
+                    self.api.get_file_details(addon_id).await?.download_url
+                }
+            };
+            let size = match archives.get(&addon_id) {
+                Some(size) => Some(*size),
+                None => self.p_download_size(&url).await,
+            };
+            changes.downloads.push(PlannedDownload { label, url, size });
+
+            let dirs: Vec<String> = AddonDir::Entity::find()
+                .filter(AddonDir::Column::AddonId.eq(addon_id))
+                .all(&self.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|d| d.dir)
+                .filter(|d| !d.is_empty())
+                .collect();
+            changes
+                .dirs_written
+                .extend(dirs.iter().map(|d| addon_dir.join(d)));
+            if installed.is_some() {
+                // settings are snapshot before updates
+                let manifest_dirs =
+                    manifest_dirs.get_or_insert_with(|| fs_find_manifest_dirs(&addon_dir));
+                if !fs_saved_variables_files(manifest_dirs, &game_dir, &dirs).is_empty() {
+                    changes
+                        .dirs_written
+                        .push(Config::default_settings_snapshot_dir().join(addon_id.to_string()));
+                }
+            }
+
+            let action = if installed.is_some() {
+                DbAction::Update
+            } else {
+                DbAction::Insert
+            };
+            changes.db("installed_addon", action, rows.clone());
+            changes.db("addon_dependency", DbAction::Insert, rows);
+        }
+        Ok(())
+    }
+
+    /// Adds what removing each installed addon of `addon_ids` changes.
+    async fn p_plan_remove(&self, addon_ids: &[i32], changes: &mut ChangePlan) -> Result<()> {
+        for addon_id in addon_ids.iter().copied() {
+            if InstalledAddon::Entity::find_by_id(addon_id)
+                .one(&self.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .is_none()
+            {
+                continue;
+            }
+            let addon_dir = self.get_addon_dir();
+            changes.dirs_deleted.extend(
+                AddonDir::Entity::find()
+                    .filter(AddonDir::Column::AddonId.eq(addon_id))
+                    .filter(AddonDir::Column::Dir.ne("")) // never the AddOns dir itself
+                    .all(&self.db)
+                    .await
+                    .context(error::DbGetSnafu)?
+                    .into_iter()
+                    .map(|d| addon_dir.join(d.dir))
+                    .filter(|path| path.is_dir()),
+            );
+            changes.db(
+                "installed_addon",
+                DbAction::Delete,
+                format!("addon_id = {addon_id}"),
+            );
+            let overrides = ManualDependency::Entity::find()
+                .filter(ManualDependency::Column::SatisfiedBy.eq(addon_id))
+                .count(&self.db)
+                .await
+                .context(error::DbGetSnafu)?;
+            if overrides > 0 {
+                changes.db(
+                    "manual_dependency",
+                    DbAction::Delete,
+                    format!("satisfied_by = {addon_id}"),
+                );
+            }
+        }
+        Ok(())
+    }
+
+    /// Best effort, a failed size request doesn't fail the plan.
+    async fn p_download_size(&self, url: &str) -> Option<u64> {
+        match self.api.get_download_size(url).await {
+            Ok(size) => size,
+            Err(e) => {
+                warn!("Unable to get the download size of {url}: {e}");
+                None
+            }
+        }
+    }
+}
+
+/// Mirrors `p_install_from`: an update always downloads, even if the version is the same.
+fn install_change(
+    installed: Option<&InstalledAddon::Model>,
+    version: &str,
+    update: bool,
+) -> InstallChange {
+    match installed {
+        Some(installed) if installed.version == version => match (update, installed.reason) {
+            (true, _) => InstallChange::Reinstall,
+            (false, InstallReason::Dependency) => InstallChange::MarkExplicit,
+            (false, InstallReason::Explicit) => InstallChange::Nothing,
+        },
+        _ => InstallChange::Download,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn installed(version: &str, reason: InstallReason) -> InstalledAddon::Model {
+        InstalledAddon::Model {
+            addon_id: 7,
+            version: version.to_string(),
+            date: String::new(),
+            reason,
+        }
+    }
+
+    #[test]
+    fn test_install_change() {
+        assert_eq!(install_change(None, "2.0", false), InstallChange::Download);
+        let outdated = installed("1.0", InstallReason::Explicit);
+        assert_eq!(
+            install_change(Some(&outdated), "2.0", false),
+            InstallChange::Download
+        );
+        assert_eq!(
+            install_change(Some(&outdated), "2.0", true),
+            InstallChange::Download
+        );
+        let current = installed("2.0", InstallReason::Explicit);
+        assert_eq!(
+            install_change(Some(&current), "2.0", false),
+            InstallChange::Nothing
+        );
+        assert_eq!(
+            install_change(Some(&current), "2.0", true),
+            InstallChange::Reinstall
+        );
+        let library = installed("2.0", InstallReason::Dependency);
+        assert_eq!(
+            install_change(Some(&library), "2.0", false),
+            InstallChange::MarkExplicit
+        );
+        assert_eq!(
+            install_change(Some(&library), "2.0", true),
+            InstallChange::Reinstall
+        );
+    }
+
+    #[test]
+    fn test_download_size() {
+        let download = |size| PlannedDownload {
+            label: String::new(),
+            url: String::new(),
+            size,
+        };
+        let mut changes = ChangePlan::default();
+        assert_eq!(changes.download_size(), (0, false));
+        changes.downloads.push(download(Some(1024)));
+        changes.downloads.push(download(Some(512)));
+        assert_eq!(changes.download_size(), (1536, false));
+        changes.downloads.push(download(None));
+        assert_eq!(changes.download_size(), (1536, true));
+    }
+}
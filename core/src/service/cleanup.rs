@@ -0,0 +1,105 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::Path;
+
+use entity::addon_dir as AddonDir;
+use entity::installed_addon as InstalledAddon;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter, QuerySelect};
+use snafu::ResultExt;
+use tracing::log::info;
+
+use super::fs_util::{fs_dir_size, fs_list_addon_dirs};
+use super::result::UntrackedDir;
+use super::{AddonService, dir_providers};
+use crate::error::{self, Result};
+
+impl AddonService {
+    /// Top-level dirs in the AddOns folder that no installed addon owns, sorted by name.
+    /// These were installed by hand, by another manager, or left behind by an addon
+    /// that no longer ships them.
+    pub fn get_untracked_dirs(&self) -> ImmediateValuePromise<Vec<UntrackedDir>> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let dirs = service.p_untracked_dirs().await?;
+            Ok(dirs)
+        })
+    }
+
+    /// Deletes the untracked `dirs` from the AddOns folder. Names that aren't untracked
+    /// top-level dirs are skipped. Returns the dirs deleted.
+    pub fn remove_untracked_dirs(&self, dirs: Vec<String>) -> ImmediateValuePromise<Vec<String>> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let removed = service.p_remove_untracked_dirs(&dirs).await?;
+            Ok(removed)
+        })
+    }
+
+    pub(super) async fn p_untracked_dirs(&self) -> Result<Vec<UntrackedDir>> {
+        let owned = self.p_owned_dirs().await?;
+        let addon_dir = self.get_addon_dir();
+        let mut dirs: Vec<String> = fs_list_addon_dirs(&addon_dir)
+            .into_iter()
+            .filter(|dir| !owned.contains(&dir.to_lowercase()))
+            .collect();
+        dirs.sort_by_key(|dir| dir.to_lowercase());
+
+        let mut providers = dir_providers(&self.db, &dirs).await?;
+        Ok(dirs
+            .into_iter()
+            .map(|dir| {
+                let path = addon_dir.join(&dir);
+                UntrackedDir {
+                    size: fs_dir_size(&path),
+                    providers: providers.remove(&dir).unwrap_or_default(),
+                    dir,
+                    path,
+                }
+            })
+            .collect())
+    }
+
+    async fn p_remove_untracked_dirs(&self, dirs: &[String]) -> Result<Vec<String>> {
+        let untracked: HashSet<String> = self
+            .p_untracked_dirs()
+            .await?
+            .into_iter()
+            .map(|d| d.dir)
+            .collect();
+        let addon_dir = self.get_addon_dir();
+        let mut removed = vec![];
+        // only plain dir names, nothing outside the AddOns folder
+        for dir in dirs
+            .iter()
+            .filter(|d| Path::new(d).file_name().is_some_and(|n| n == d.as_str()))
+            .filter(|d| untracked.contains(*d))
+        {
+            let path = addon_dir.join(dir);
+            fs::remove_dir_all(&path).context(error::AddonDeleteSnafu { dir: &path })?;
+            info!("Removed untracked addon dir {}", path.display());
+            removed.push(dir.clone());
+        }
+        Ok(removed)
+    }
+
+    /// Lowercased dirs of the installed addons, the game matches them case-insensitively
+    /// on Windows.
+    async fn p_owned_dirs(&self) -> Result<HashSet<String>> {
+        let installed: Vec<i32> = InstalledAddon::Entity::find()
+            .select_only()
+            .column(InstalledAddon::Column::AddonId)
+            .into_tuple()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?;
+        Ok(AddonDir::Entity::find()
+            .filter(AddonDir::Column::AddonId.is_in(installed))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|d| d.dir.to_lowercase())
+            .collect())
+    }
+}
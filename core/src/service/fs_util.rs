@@ -1,15 +1,23 @@
 use std::{
+    collections::HashMap,
     fs::{self, File},
-    io::{BufRead, BufReader},
+    io::{self, BufRead, BufReader, Read, Seek, Write},
     path::{Path, PathBuf},
 };
 
 use entity::addon_dir as AddonDir;
+use md5::{Digest, Md5};
 use regex::Regex;
 use snafu::ResultExt;
+use tracing::log::warn;
+use walkdir::WalkDir;
+use zip::write::SimpleFileOptions;
+use zip::{ZipArchive, ZipWriter};
 
+use super::backup::{ADDON_SETTINGS_FILE, BACKUP_MANIFEST, BackupOptions, SAVED_VARIABLES_DIR};
+use super::result::{LibraryCopy, RestoreFile, SavedVariablesFile};
 use crate::{
-    addons::Addon,
+    addons::{Addon, get_root_dir},
     error::{self, Result},
 };
 
@@ -18,6 +26,17 @@ fn extract_dependency(dep: &str) -> Option<String> {
     re.captures(dep).map(|captures| captures[1].to_owned())
 }
 
+fn parse_depends(re: &Regex, line: &str) -> Vec<String> {
+    match re.captures(line) {
+        Some(ref captures) => captures[2]
+            .split(' ')
+            .map(|s| s.to_owned())
+            .filter_map(|s| extract_dependency(&s))
+            .collect(),
+        None => vec![],
+    }
+}
+
 fn fs_open_addon_metadata_file(path: &Path, addon_name: &str) -> Result<File> {
     let mut filepath = path.to_owned();
     let mut filepath_lowercase = path.to_owned();
@@ -43,6 +62,8 @@ pub fn fs_read_addon(path: &Path) -> Result<Addon> {
     let mut addon = Addon {
         name: addon_name.to_owned(),
         depends_on: vec![],
+        optional_depends_on: vec![],
+        saved_variables: vec![],
     };
 
     // Not all addons have a Metadata file but are still valid addons, such as HarvestMapData
@@ -53,25 +74,140 @@ pub fn fs_read_addon(path: &Path) -> Result<Addon> {
     }
     let addon_file = file.unwrap();
 
+    read_addon_metadata(&mut addon, BufReader::new(addon_file));
+    Ok(addon)
+}
+
+/// Reads every top level addon in a downloaded archive without extracting it,
+/// addons without a metadata file are returned without dependencies.
+pub fn zip_read_addons<R: Read + Seek>(archive: &mut ZipArchive<R>) -> Result<Vec<Addon>> {
+    let mut roots: Vec<String> = vec![];
+    for i in 0..archive.len() {
+        let file = archive
+            .by_index(i)
+            .context(error::AddonDownloadZipReadSnafu { file: i })?;
+        let name = file.mangled_name();
+        // loose files in the archive root aren't addons
+        if name.components().count() < 2 && !file.is_dir() {
+            continue;
+        }
+        let root = get_root_dir(&name).to_string_lossy().to_string();
+        if !roots.contains(&root) {
+            roots.push(root);
+        }
+    }
+
+    let mut addons = vec![];
+    for root in roots {
+        let mut addon = Addon {
+            name: root.clone(),
+            depends_on: vec![],
+            optional_depends_on: vec![],
+            saved_variables: vec![],
+        };
+        let metadata = [
+            format!("{root}/{root}.txt"),
+            format!("{root}/{}.txt", root.to_lowercase()),
+        ];
+        if let Some(index) = metadata
+            .iter()
+            .find_map(|name| archive.index_for_name(name))
+        {
+            let file = archive
+                .by_index(index)
+                .context(error::AddonDownloadZipReadSnafu { file: index })?;
+            read_addon_metadata(&mut addon, BufReader::new(file));
+        }
+        addons.push(addon);
+    }
+    Ok(addons)
+}
+
+fn read_addon_metadata(addon: &mut Addon, reader: impl BufRead) {
     let re = Regex::new(r"## (.*): (.*)").unwrap();
 
-    let reader = BufReader::new(addon_file);
     for line in reader.lines().map_while(Result::ok) {
         if line.starts_with("## DependsOn:") {
-            let depends_on = match re.captures(&line) {
-                Some(ref captures) => captures[2]
-                    .split(' ')
-                    .map(|s| s.to_owned())
-                    .filter_map(|s| extract_dependency(&s))
-                    .collect(),
-                None => vec![],
-            };
+            addon.depends_on = parse_depends(&re, &line);
+        } else if line.starts_with("## OptionalDependsOn:") {
+            addon.optional_depends_on = parse_depends(&re, &line);
+        } else if line.starts_with("## SavedVariables:")
+            && let Some(captures) = re.captures(&line)
+        {
+            addon.saved_variables = captures[2]
+                .split_whitespace()
+                .map(|s| s.to_owned())
+                .collect();
+        }
+    }
+}
 
-            addon.depends_on = depends_on;
+/// Manifests under the AddOns folder for dirs found more than once, keyed by dir name
+/// which is how the game tells addons apart.
+pub fn fs_find_addon_copies(addon_dir: &Path) -> HashMap<String, Vec<LibraryCopy>> {
+    let parser = eso_addon_manifest::AddonManifestParser::default();
+    let mut copies: HashMap<String, Vec<LibraryCopy>> = HashMap::new();
+    let walker = WalkDir::new(addon_dir)
+        .min_depth(2)
+        .max_depth(4)
+        .into_iter();
+    for entry in walker.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let (Some(dir_name), Some(stem), Some(ext)) = (
+            path.parent()
+                .and_then(|p| p.file_name())
+                .and_then(|n| n.to_str()),
+            path.file_stem(),
+            path.extension().and_then(|e| e.to_str()),
+        ) else {
+            continue;
+        };
+        if !path.is_file() || dir_name != stem || !["txt", "addon"].contains(&ext) {
+            continue;
+        }
+        let Some(path_str) = path.to_str() else {
+            continue;
+        };
+        let manifest = match parser.parse(path_str, None) {
+            Ok(manifest) => manifest,
+            Err(err) => {
+                warn!("{}", err);
+                continue;
+            }
+        };
+        let relative = path
+            .parent()
+            .and_then(|p| p.strip_prefix(addon_dir).ok())
+            .expect("walker rooted at addon_dir")
+            .to_owned();
+        let nested = relative.components().count() > 1;
+        let dir_copies = copies.entry(dir_name.to_owned()).or_default();
+        // a dir can have both a .txt and an .addon manifest
+        if dir_copies.iter().any(|c| c.path == relative) {
+            continue;
         }
+        dir_copies.push(LibraryCopy {
+            path: relative,
+            title: manifest.title,
+            addon_version: manifest.addon_version,
+            version: manifest.version,
+            nested,
+        });
     }
+    copies.retain(|_, c| c.len() > 1);
+    copies
+}
 
-    Ok(addon)
+/// Splits copies of one addon into the copy the game loads, the highest `AddOnVersion`
+/// with top-level copies winning ties, and the copies with a lower version.
+pub fn split_loaded_copy(mut copies: Vec<LibraryCopy>) -> Option<(LibraryCopy, Vec<LibraryCopy>)> {
+    copies.sort_by_key(|c| (std::cmp::Reverse(c.addon_version.unwrap_or(0)), c.nested));
+    let mut copies = copies.into_iter();
+    let loaded = copies.next()?;
+    let stale = copies
+        .filter(|c| c.addon_version.unwrap_or(0) < loaded.addon_version.unwrap_or(0))
+        .collect();
+    Some((loaded, stale))
 }
 
 pub fn fs_delete_addon(addon_path: &PathBuf, addon_dirs: &[AddonDir::Model]) -> Result<()> {
@@ -81,3 +217,529 @@ pub fn fs_delete_addon(addon_path: &PathBuf, addon_dirs: &[AddonDir::Model]) ->
     }
     Ok(())
 }
+
+/// Names of the top-level dirs in the AddOns folder.
+pub fn fs_list_addon_dirs(addon_dir: &Path) -> Vec<String> {
+    fs::read_dir(addon_dir)
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+/// Total size of the files under `path`.
+pub fn fs_dir_size(path: &Path) -> u64 {
+    WalkDir::new(path)
+        .into_iter()
+        .flatten()
+        .filter_map(|entry| entry.metadata().ok())
+        .filter(|metadata| metadata.is_file())
+        .map(|metadata| metadata.len())
+        .sum()
+}
+
+/// Game files under `game_dir` that `options` asks to back up, relative to `game_dir`.
+pub fn fs_backup_files(game_dir: &Path, options: &BackupOptions) -> Vec<PathBuf> {
+    let mut files = vec![];
+    if options.saved_variables {
+        for entry in WalkDir::new(game_dir.join(SAVED_VARIABLES_DIR))
+            .into_iter()
+            .filter_map(|e| e.ok())
+        {
+            if entry.file_type().is_file()
+                && let Ok(path) = entry.path().strip_prefix(game_dir)
+            {
+                files.push(path.to_path_buf());
+            }
+        }
+    }
+    if options.addon_settings && game_dir.join(ADDON_SETTINGS_FILE).is_file() {
+        files.push(PathBuf::from(ADDON_SETTINGS_FILE));
+    }
+    files
+}
+
+/// Dirs under the AddOns folder holding a manifest named after them, keyed by dir name.
+/// Looks as deep as the installed addon scan, so nested sub-addons such as
+/// `HarvestMapData/Modules/HarvestMapAD` are found along with the top-level ones.
+pub fn fs_find_manifest_dirs(addon_dir: &Path) -> HashMap<String, Vec<PathBuf>> {
+    let mut dirs: HashMap<String, Vec<PathBuf>> = HashMap::new();
+    let walker = WalkDir::new(addon_dir)
+        .min_depth(2)
+        .max_depth(4)
+        .into_iter();
+    for entry in walker.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let (Some(parent), Some(stem), Some(ext)) = (
+            path.parent(),
+            path.file_stem(),
+            path.extension().and_then(|e| e.to_str()),
+        ) else {
+            continue;
+        };
+        let Some(dir_name) = parent.file_name().and_then(|n| n.to_str()) else {
+            continue;
+        };
+        if !path.is_file() || dir_name != stem || !["txt", "addon"].contains(&ext) {
+            continue;
+        }
+        let paths = dirs.entry(dir_name.to_owned()).or_default();
+        // a dir can have both a .txt and an .addon manifest
+        if !paths.iter().any(|p| p == parent) {
+            paths.push(parent.to_owned());
+        }
+    }
+    dirs
+}
+
+/// SavedVariables files of the addon `dirs` that declare `## SavedVariables:` and
+/// have been written by the game, as file names in the SavedVariables folder. Nested
+/// dirs are read where they are, `manifest_dirs` is what [`fs_find_manifest_dirs`]
+/// found, so callers checking several addons walk the AddOns folder once.
+pub fn fs_saved_variables_files(
+    manifest_dirs: &HashMap<String, Vec<PathBuf>>,
+    game_dir: &Path,
+    dirs: &[String],
+) -> Vec<String> {
+    let saved_vars = game_dir.join(SAVED_VARIABLES_DIR);
+    dirs.iter()
+        .filter(|dir| {
+            manifest_dirs
+                .get(dir.as_str())
+                .into_iter()
+                .flatten()
+                .any(|path| {
+                    fs_read_addon(path).is_ok_and(|addon| !addon.saved_variables.is_empty())
+                })
+        })
+        .map(|dir| format!("{dir}.lua"))
+        .filter(|file| saved_vars.join(file).is_file())
+        .collect()
+}
+
+/// Every `.lua` file in the SavedVariables folder, largest first, with the addon dir
+/// declaring `## SavedVariables:` that the game writes it for. Nested sub-addons such
+/// as `HarvestMapData/Modules/HarvestMapAD` own their files like top-level ones. The
+/// game's own `ZO_*` files are left out.
+pub fn fs_scan_saved_variables(addon_dir: &Path, game_dir: &Path) -> Vec<SavedVariablesFile> {
+    // the game matches dirs case-insensitively on Windows
+    let mut owners: HashMap<String, String> = HashMap::new();
+    for (dir, paths) in fs_find_manifest_dirs(addon_dir) {
+        if paths
+            .iter()
+            .any(|path| fs_read_addon(path).is_ok_and(|addon| !addon.saved_variables.is_empty()))
+        {
+            owners.insert(dir.to_lowercase(), dir);
+        }
+    }
+
+    let saved_vars = game_dir.join(SAVED_VARIABLES_DIR);
+    let mut files = vec![];
+    for entry in fs::read_dir(saved_vars).into_iter().flatten().flatten() {
+        let path = entry.path();
+        let (Some(file), Some(stem)) = (
+            path.file_name().and_then(|n| n.to_str()),
+            path.file_stem().and_then(|n| n.to_str()),
+        ) else {
+            continue;
+        };
+        if !path.is_file()
+            || path.extension().and_then(|e| e.to_str()) != Some("lua")
+            || stem.starts_with("ZO_")
+        {
+            continue;
+        }
+        files.push(SavedVariablesFile {
+            file: file.to_owned(),
+            size: entry.metadata().map(|m| m.len()).unwrap_or_default(),
+            owner: owners.get(&stem.to_lowercase()).cloned(),
+            addon: None,
+        });
+    }
+    files.sort_by_key(|f| std::cmp::Reverse(f.size));
+    files
+}
+
+/// Zip entry names always use `/`, whatever the platform.
+fn zip_entry_name(path: &Path) -> String {
+    path.components()
+        .map(|c| c.as_os_str().to_string_lossy())
+        .collect::<Vec<_>>()
+        .join("/")
+}
+
+pub fn fs_write_backup_archive(
+    file: &Path,
+    manifest: &[u8],
+    game_dir: &Path,
+    files: &[PathBuf],
+) -> Result<()> {
+    let out = File::create(file).context(error::BackupWriteSnafu { path: file })?;
+    let mut writer = ZipWriter::new(out);
+    let options = SimpleFileOptions::default();
+    writer
+        .start_file(BACKUP_MANIFEST, options)
+        .context(error::BackupArchiveSnafu)?;
+    writer
+        .write_all(manifest)
+        .context(error::BackupWriteSnafu { path: file })?;
+    for path in files {
+        let source = game_dir.join(path);
+        let mut reader = File::open(&source).context(error::BackupReadSnafu { path: &source })?;
+        writer
+            .start_file(zip_entry_name(path), options)
+            .context(error::BackupArchiveSnafu)?;
+        io::copy(&mut reader, &mut writer).context(error::BackupWriteSnafu { path: file })?;
+    }
+    writer.finish().context(error::BackupArchiveSnafu)?;
+    Ok(())
+}
+
+fn md5_reader(mut reader: impl Read) -> io::Result<Vec<u8>> {
+    let mut hasher = Md5::new();
+    let mut buffer = [0; 8192];
+    loop {
+        let bytes_read = reader.read(&mut buffer)?;
+        if bytes_read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..bytes_read]);
+    }
+    Ok(hasher.finalize().to_vec())
+}
+
+pub fn zip_read_backup_manifest<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    path: &Path,
+) -> Result<String> {
+    let mut json = String::new();
+    archive
+        .by_name(BACKUP_MANIFEST)
+        .context(error::BackupArchiveSnafu)?
+        .read_to_string(&mut json)
+        .context(error::BackupReadSnafu { path })?;
+    Ok(json)
+}
+
+/// Game files stored in a backup archive, compared against the ones under `game_dir`.
+/// Returns the files that are missing or differ, and how many are identical.
+pub fn zip_backup_files<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    game_dir: &Path,
+) -> Result<(Vec<RestoreFile>, usize)> {
+    let mut files = vec![];
+    let mut unchanged = 0;
+    for i in 0..archive.len() {
+        let entry = archive
+            .by_index(i)
+            .context(error::AddonDownloadZipReadSnafu { file: i })?;
+        // only take what a backup writes, never anything outside the game folder
+        let Some(path) = entry.enclosed_name() else {
+            continue;
+        };
+        if entry.is_dir()
+            || !(path.starts_with(SAVED_VARIABLES_DIR) || path == Path::new(ADDON_SETTINGS_FILE))
+        {
+            continue;
+        }
+        let target = game_dir.join(&path);
+        let conflict = if target.is_file() {
+            let on_disk = File::open(&target).context(error::BackupReadSnafu { path: &target })?;
+            let same_size = on_disk
+                .metadata()
+                .is_ok_and(|metadata| metadata.len() == entry.size());
+            if same_size
+                && md5_reader(on_disk).context(error::BackupReadSnafu { path: &target })?
+                    == md5_reader(entry).context(error::BackupReadSnafu { path: &path })?
+            {
+                unchanged += 1;
+                continue;
+            }
+            true
+        } else {
+            false
+        };
+        files.push(RestoreFile {
+            path,
+            conflict,
+            overwrite: true,
+        });
+    }
+    Ok((files, unchanged))
+}
+
+/// `target` with `.bak` appended, or `.bak.1`, `.bak.2`... if that's taken, so an
+/// earlier backup is never overwritten.
+pub fn fs_backup_path(target: &Path) -> PathBuf {
+    let mut n = 0;
+    loop {
+        let mut backup = target.as_os_str().to_owned();
+        backup.push(".bak");
+        if n > 0 {
+            backup.push(format!(".{n}"));
+        }
+        let backup = PathBuf::from(backup);
+        if !backup.exists() {
+            return backup;
+        }
+        n += 1;
+    }
+}
+
+/// Extracts `files` from a backup archive into `game_dir`, returning how many were
+/// written. Conflicting files not marked `overwrite` are skipped, the others are kept
+/// as `.bak`, or `.bak.N` when an earlier backup is there.
+pub fn zip_restore_files<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    game_dir: &Path,
+    files: &[RestoreFile],
+) -> Result<usize> {
+    let mut restored = 0;
+    for file in files.iter().filter(|f| !f.conflict || f.overwrite) {
+        let target = game_dir.join(&file.path);
+        if file.conflict {
+            fs::rename(&target, fs_backup_path(&target))
+                .context(error::BackupWriteSnafu { path: &target })?;
+        } else if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).context(error::BackupWriteSnafu { path: parent })?;
+        }
+        let mut entry = archive
+            .by_name(&zip_entry_name(&file.path))
+            .context(error::BackupArchiveSnafu)?;
+        let mut out = File::create(&target).context(error::BackupWriteSnafu { path: &target })?;
+        io::copy(&mut entry, &mut out).context(error::BackupWriteSnafu { path: &target })?;
+        restored += 1;
+    }
+    Ok(restored)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    fn copy(path: &str, addon_version: Option<u32>) -> LibraryCopy {
+        LibraryCopy {
+            path: PathBuf::from(path),
+            title: "LibTest".to_owned(),
+            addon_version,
+            version: None,
+            nested: path.contains('/'),
+        }
+    }
+
+    #[test]
+    fn test_backup_path() {
+        let dir = tempfile::tempdir().unwrap();
+        let target = dir.path().join("MyAddon.lua");
+        assert_eq!(fs_backup_path(&target), dir.path().join("MyAddon.lua.bak"));
+        fs::write(dir.path().join("MyAddon.lua.bak"), "").unwrap();
+        fs::write(dir.path().join("MyAddon.lua.bak.1"), "").unwrap();
+        assert_eq!(
+            fs_backup_path(&target),
+            dir.path().join("MyAddon.lua.bak.2")
+        );
+    }
+
+    #[test]
+    fn test_split_loaded_copy() {
+        let (loaded, stale) = split_loaded_copy(vec![
+            copy("Addon/LibTest", Some(12)),
+            copy("LibTest", Some(10)),
+            copy("Other/LibTest", None),
+        ])
+        .unwrap();
+        assert_eq!(loaded.path, PathBuf::from("Addon/LibTest"));
+        assert_eq!(stale.len(), 2);
+
+        // same version everywhere, nothing is stale and top-level wins
+        let (loaded, stale) = split_loaded_copy(vec![
+            copy("Addon/LibTest", Some(12)),
+            copy("LibTest", Some(12)),
+        ])
+        .unwrap();
+        assert_eq!(loaded.path, PathBuf::from("LibTest"));
+        assert!(stale.is_empty());
+    }
+
+    #[test]
+    fn test_zip_read_addons() {
+        let mut writer = ZipWriter::new(Cursor::new(vec![]));
+        let options = SimpleFileOptions::default();
+        writer.add_directory("MyAddon/", options).unwrap();
+        writer.start_file("MyAddon/MyAddon.txt", options).unwrap();
+        writer
+            .write_all(
+                b"## Title: My Addon\n## DependsOn: LibA LibB>=3\n## OptionalDependsOn: LibC\n## SavedVariables: MyAddonVars  MyAddonChar\n",
+            )
+            .unwrap();
+        writer.start_file("MyAddon/MyAddon.lua", options).unwrap();
+        writer.start_file("LibD/LibD.lua", options).unwrap();
+        writer.start_file("readme.txt", options).unwrap();
+        let mut archive = ZipArchive::new(writer.finish().unwrap()).unwrap();
+
+        let addons = zip_read_addons(&mut archive).unwrap();
+
+        assert_eq!(addons.len(), 2);
+        assert_eq!(addons[0].name, "MyAddon");
+        assert_eq!(addons[0].depends_on, vec!["LibA", "LibB"]);
+        assert_eq!(addons[0].optional_depends_on, vec!["LibC"]);
+        assert_eq!(
+            addons[0].saved_variables,
+            vec!["MyAddonVars", "MyAddonChar"]
+        );
+        assert_eq!(addons[1].name, "LibD");
+        assert!(addons[1].depends_on.is_empty());
+    }
+
+    #[test]
+    fn test_scan_saved_variables() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let addon_dir = game_dir.path().join("AddOns");
+        fs::create_dir_all(addon_dir.join("MyAddon")).unwrap();
+        fs::write(
+            addon_dir.join("MyAddon/MyAddon.txt"),
+            "## Title: My Addon\n## SavedVariables: MyAddonVars\n",
+        )
+        .unwrap();
+        fs::create_dir_all(addon_dir.join("LibNoVars")).unwrap();
+        fs::write(addon_dir.join("LibNoVars/LibNoVars.txt"), "## Title: Lib\n").unwrap();
+        let module = addon_dir.join("CombatMetrics/CombatMetricsFightData");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(
+            module.join("CombatMetricsFightData.txt"),
+            "## Title: Fight Data\n## SavedVariables: CMX_FightData\n",
+        )
+        .unwrap();
+        let saved_vars = game_dir.path().join(SAVED_VARIABLES_DIR);
+        fs::create_dir_all(&saved_vars).unwrap();
+        fs::write(saved_vars.join("myaddon.lua"), "MyAddonVars = {}").unwrap();
+        fs::write(saved_vars.join("CombatMetricsFightData.lua"), "").unwrap();
+        fs::write(saved_vars.join("Removed.lua"), "RemovedVars = { a = 1 }").unwrap();
+        fs::write(saved_vars.join("LibNoVars.lua"), "Old = {}").unwrap();
+        fs::write(saved_vars.join("ZO_Ingame.lua"), "ZO_Ingame = {}").unwrap();
+        fs::write(saved_vars.join("MyAddon.lua.bak"), "").unwrap();
+
+        let files = fs_scan_saved_variables(&addon_dir, game_dir.path());
+
+        let names: Vec<&str> = files.iter().map(|f| f.file.as_str()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "Removed.lua",
+                "myaddon.lua",
+                "LibNoVars.lua",
+                "CombatMetricsFightData.lua"
+            ]
+        );
+        assert_eq!(files[0].size, 23);
+        assert!(files[0].is_orphan());
+        assert_eq!(files[1].owner.as_deref(), Some("MyAddon"));
+        assert!(files[2].is_orphan());
+        assert_eq!(files[3].owner.as_deref(), Some("CombatMetricsFightData"));
+        assert!(!files[3].is_orphan());
+    }
+
+    #[test]
+    fn test_saved_variables_files() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let addon_dir = game_dir.path().join("AddOns");
+        let module = addon_dir.join("HarvestMapData/Modules/HarvestMapAD");
+        fs::create_dir_all(&module).unwrap();
+        fs::write(
+            module.join("HarvestMapAD.txt"),
+            "## Title: HarvestMap AD\n## SavedVariables: HarvestAD_SavedVars\n",
+        )
+        .unwrap();
+        fs::create_dir_all(addon_dir.join("MyAddon")).unwrap();
+        fs::write(
+            addon_dir.join("MyAddon/MyAddon.txt"),
+            "## Title: My Addon\n## SavedVariables: MyAddonVars\n",
+        )
+        .unwrap();
+        let saved_vars = game_dir.path().join(SAVED_VARIABLES_DIR);
+        fs::create_dir_all(&saved_vars).unwrap();
+        fs::write(
+            saved_vars.join("HarvestMapAD.lua"),
+            "HarvestAD_SavedVars = {}",
+        )
+        .unwrap();
+
+        let dirs = vec![
+            "HarvestMapData".to_string(),
+            "HarvestMapAD".to_string(),
+            "MyAddon".to_string(),
+        ];
+        let files =
+            fs_saved_variables_files(&fs_find_manifest_dirs(&addon_dir), game_dir.path(), &dirs);
+
+        // MyAddon declares SavedVariables but the game hasn't written them yet
+        assert_eq!(files, vec!["HarvestMapAD.lua"]);
+    }
+
+    #[test]
+    fn test_backup_archive_roundtrip() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let saved_vars = game_dir.path().join(SAVED_VARIABLES_DIR);
+        fs::create_dir_all(&saved_vars).unwrap();
+        fs::write(saved_vars.join("Same.lua"), "Same = {}").unwrap();
+        fs::write(saved_vars.join("Changed.lua"), "Changed = { a = 1 }").unwrap();
+        fs::write(saved_vars.join("Removed.lua"), "Removed = {}").unwrap();
+        fs::write(game_dir.path().join(ADDON_SETTINGS_FILE), "#Version 1").unwrap();
+
+        let options = BackupOptions {
+            saved_variables: true,
+            addon_settings: false,
+        };
+        let mut files = fs_backup_files(game_dir.path(), &options);
+        files.sort();
+        assert_eq!(
+            files,
+            vec![
+                PathBuf::from("SavedVariables/Changed.lua"),
+                PathBuf::from("SavedVariables/Removed.lua"),
+                PathBuf::from("SavedVariables/Same.lua"),
+            ]
+        );
+        let archive = game_dir.path().join("backup.zip");
+        fs_write_backup_archive(&archive, b"{}", game_dir.path(), &files).unwrap();
+
+        fs::write(saved_vars.join("Changed.lua"), "Changed = { a = 2 }").unwrap();
+        fs::remove_file(saved_vars.join("Removed.lua")).unwrap();
+
+        let mut zip = ZipArchive::new(File::open(&archive).unwrap()).unwrap();
+        let (mut restore, unchanged) = zip_backup_files(&mut zip, game_dir.path()).unwrap();
+        restore.sort_by(|a, b| a.path.cmp(&b.path));
+        assert_eq!(unchanged, 1);
+        assert_eq!(restore.len(), 2);
+        assert!(restore[0].conflict);
+        assert!(!restore[1].conflict);
+
+        let restored = zip_restore_files(&mut zip, game_dir.path(), &restore).unwrap();
+        assert_eq!(restored, 2);
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("Changed.lua")).unwrap(),
+            "Changed = { a = 1 }"
+        );
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("Changed.lua.bak")).unwrap(),
+            "Changed = { a = 2 }"
+        );
+        assert!(saved_vars.join("Removed.lua").is_file());
+
+        // restoring again keeps the first backup
+        fs::write(saved_vars.join("Changed.lua"), "Changed = { a = 3 }").unwrap();
+        zip_restore_files(&mut zip, game_dir.path(), &restore[..1]).unwrap();
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("Changed.lua.bak")).unwrap(),
+            "Changed = { a = 2 }"
+        );
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("Changed.lua.bak.1")).unwrap(),
+            "Changed = { a = 3 }"
+        );
+    }
+}
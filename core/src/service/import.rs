@@ -0,0 +1,107 @@
+use std::collections::HashSet;
+
+use entity::addon as DbAddon;
+use entity::installed_addon as InstalledAddon;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use snafu::ResultExt;
+use tracing::log::info;
+
+use super::result::{AddonRef, ImportPlan, ImportedAddon, InstallPlan};
+use super::{AddonService, dir_providers};
+use crate::addons::{ImportEntry, parse_import_list};
+use crate::error::{self, Result};
+
+impl AddonService {
+    /// Matches a list of ESOUI URLs, addon ids and directory names against the catalog,
+    /// without installing anything. Directory names resolve to the addon shipping them.
+    pub fn get_import_plan(&self, text: String) -> ImmediateValuePromise<ImportPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let plan = service.p_import_plan(&text).await?;
+            Ok(plan)
+        })
+    }
+
+    /// Installs the addons of the plan that aren't installed yet, along with their
+    /// dependencies. Each addon is resolved on its own, those that can't be are left
+    /// out and returned in the plan's `failed` list. Returns the install plan that was
+    /// carried out.
+    pub fn import_plan(&self, plan: ImportPlan) -> ImmediateValuePromise<InstallPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let ids = plan.to_install().map(|a| a.addon.id).collect();
+            let install = service.p_resolve_install_plan(ids).await?;
+            let mut done = install.clone();
+            // the downloaded archives are removed once installed
+            done.steps.iter_mut().for_each(|step| step.archive = None);
+            service.p_install_plan(install).await;
+            Ok(done)
+        })
+    }
+
+    async fn p_import_plan(&self, text: &str) -> Result<ImportPlan> {
+        let entries = parse_import_list(text);
+        let dirs: Vec<String> = entries
+            .iter()
+            .filter_map(|(_, entry)| match entry {
+                ImportEntry::Dir(dir) => Some(dir.clone()),
+                _ => None,
+            })
+            .collect();
+        let providers = dir_providers(&self.db, &dirs).await?;
+        let ids: Vec<i32> = entries
+            .iter()
+            .filter_map(|(_, entry)| match entry {
+                ImportEntry::Id(id) => Some(*id),
+                _ => None,
+            })
+            .collect();
+        let catalog: Vec<AddonRef> = DbAddon::Entity::find()
+            .filter(DbAddon::Column::Id.is_in(ids))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|a| AddonRef {
+                id: a.id,
+                name: a.name,
+            })
+            .collect();
+        let installed: HashSet<i32> = InstalledAddon::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|i| i.addon_id)
+            .collect();
+
+        let mut plan = ImportPlan::default();
+        for (source, entry) in entries {
+            let addon = match entry {
+                ImportEntry::Id(id) => catalog.iter().find(|a| a.id == id).cloned(),
+                ImportEntry::Dir(dir) => providers.get(&dir).and_then(|p| p.first()).cloned(),
+                ImportEntry::Unknown => None,
+            };
+            let Some(addon) = addon else {
+                plan.unresolved.push(source);
+                continue;
+            };
+            if plan.addons.iter().any(|a| a.addon.id == addon.id) {
+                continue;
+            }
+            plan.addons.push(ImportedAddon {
+                source,
+                installed: installed.contains(&addon.id),
+                addon,
+            });
+        }
+        info!(
+            "Import plan: {} addons, {} to install, {} unresolved",
+            plan.addons.len(),
+            plan.to_install().count(),
+            plan.unresolved.len()
+        );
+        Ok(plan)
+    }
+}
@@ -1,25 +1,34 @@
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::{self, File};
 use std::io::{self, BufReader, Read, Seek, Write};
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, Mutex};
 
-use self::backup::{BackupData, BackupInstalledAddon, BackupManualDependency};
-use self::fs_util::{fs_delete_addon, fs_read_addon};
+use self::backup::{
+    BACKUP_FORMAT_VERSION, BackupAddon, BackupConfig, BackupData, BackupManualDependency,
+    BackupOptions, parse_backup,
+};
+use self::fs_util::{
+    fs_backup_files, fs_delete_addon, fs_find_addon_copies, fs_find_manifest_dirs, fs_read_addon,
+    fs_write_backup_archive, split_loaded_copy, zip_backup_files, zip_read_backup_manifest,
+    zip_restore_files,
+};
 use self::result::*;
 use crate::addons::{Addon, get_root_dir};
 use crate::api::ApiClient;
 use crate::config::{self, Config, HmConfigUpdate, TTCRegion, TtcConfigUpdate};
 use crate::error::{self, Result};
+use crate::export::{ExportFormat, export_addons};
+use crate::graph::{DependencyGraph, GraphEdge, GraphNode, NodeKind};
 use entity::addon as DbAddon;
-use entity::addon_dependency as AddonDep;
+use entity::addon_dependency::{self as AddonDep, DependencyKind};
 use entity::addon_detail as AddonDetail;
 use entity::addon_dir as AddonDir;
 use entity::addon_image as AddonImage;
 use entity::category as Category;
 use entity::category_parent as CategoryParent;
 use entity::game_compatibility as GameCompat;
-use entity::installed_addon as InstalledAddon;
+use entity::installed_addon::{self as InstalledAddon, InstallReason};
 use entity::manual_dependency as ManualDependency;
 use migration::{Condition, Migrator, MigratorTrait};
 
@@ -28,7 +37,7 @@ use lazy_async_promise::ImmediateValuePromise;
 use md5::{Digest, Md5};
 use sea_orm::sea_query::{Expr, OnConflict};
 use sea_orm::{
-    ActiveModelTrait, ActiveValue, ColumnTrait, ConnectOptions, ConnectionTrait,
+    ActiveEnum, ActiveModelTrait, ActiveValue, ColumnTrait, ConnectOptions, ConnectionTrait,
     DatabaseConnection, DbBackend, DbErr, EntityTrait, FromQueryResult, IntoActiveModel, JoinType,
     ModelTrait, PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, RelationTrait, Set,
     Statement, TransactionTrait, Value,
@@ -40,18 +49,37 @@ use version_compare::Version;
 use walkdir::WalkDir;
 use zip::ZipArchive;
 
-mod backup;
+pub mod backup;
+mod changes;
+mod cleanup;
 mod fs_util;
+mod import;
+mod modpacks;
+mod resolver;
 pub mod result;
+mod saved_vars;
+pub mod search;
+mod snapshots;
+mod state_sync;
 
 const TTC_NA_DOMAIN: &str = "us.tamrieltradecentre.com";
 const TTC_EU_DOMAIN: &str = "eu.tamrieltradecentre.com";
+/// HarvestMap-Data, has to be installed before its data can be updated
+const HM_DATA_ADDON_ID: i32 = 3034;
 
 /// Safe upper bound for SQLite bound parameters per statement (SQLITE_MAX_VARIABLE_NUMBER).
 /// Defaults to 32766 on 3.32.0+ (bundled by libsqlite3-sys); 32000 leaves headroom.
 /// Only relevant for `IN (?, ?, ...)` clauses — row inserts run as per-row prepared statements.
 const SQLITE_MAX_VARS: usize = 32000;
 
+/// Order for catalog addons shipping a dependency dir (`ad`), joined with their dir
+/// count (`dc`): the addon named after the dir, then the one bundling the fewest dirs,
+/// then the most downloaded and the most recent.
+const DIR_PROVIDER_RANKING: &str = r#"(a.name = ad.dir) desc,
+            coalesce(dc.dir_count, 999) asc,
+            cast(coalesce(nullif(a.download_monthly, ''), '0') as integer) desc,
+            cast(coalesce(nullif(a.date, ''), '0') as integer) desc"#;
+
 #[derive(Debug, Clone, Default)]
 pub struct AddonService {
     pub api: ApiClient,
@@ -124,7 +152,10 @@ impl AddonService {
     pub fn install(&self, addon_id: i32, update: bool) -> ImmediateValuePromise<()> {
         let service = self.clone();
         ImmediateValuePromise::new(async move {
-            if let Err(e) = service.p_install(addon_id, update).await {
+            if let Err(e) = service
+                .p_install(addon_id, update, InstallReason::Explicit)
+                .await
+            {
                 let action = if update { "updating" } else { "installing" };
                 let label = service.addon_label(addon_id).await;
                 service.record_error(format!("Error {action} {label}"), e);
@@ -141,7 +172,20 @@ impl AddonService {
             _ => format!("addon {addon_id}"),
         }
     }
-    async fn p_install(&self, addon_id: i32, update: bool) -> Result<()> {
+    /// `reason` is only recorded for new installs, or when an addon pulled in as a
+    /// dependency is later installed explicitly, updates never change it.
+    async fn p_install(&self, addon_id: i32, update: bool, reason: InstallReason) -> Result<()> {
+        self.p_install_from(addon_id, update, reason, None).await
+    }
+
+    /// Same as [`Self::p_install`] but extracts `archive` if it was already downloaded.
+    async fn p_install_from(
+        &self,
+        addon_id: i32,
+        update: bool,
+        reason: InstallReason,
+        archive: Option<&Path>,
+    ) -> Result<()> {
         self.p_update_addon_details(addon_id).await?;
         let entry = DbAddon::Entity::find_by_id(addon_id)
             .one(&self.db)
@@ -152,12 +196,23 @@ impl AddonService {
             .one(&self.db)
             .await
             .context(error::DbGetSnafu)?;
+        let was_installed = installed_entry.is_some();
 
         if let Some(installed_entry) = installed_entry
             && installed_entry.version == entry.version
             && !update
         {
             info!("Addon {} is already installed and up to date", entry.name);
+            if reason == InstallReason::Explicit
+                && installed_entry.reason == InstallReason::Dependency
+            {
+                let mut installed_entry: InstalledAddon::ActiveModel = installed_entry.into();
+                installed_entry.reason = ActiveValue::Set(reason);
+                installed_entry
+                    .update(&self.db)
+                    .await
+                    .context(error::DbPutSnafu)?;
+            }
             return Ok(());
         }
 
@@ -166,25 +221,41 @@ impl AddonService {
         } else {
             info!("Installing addon: {addon_id}");
         }
+        // updates can wipe or badly migrate settings, a failed snapshot doesn't block them
+        if was_installed && let Err(e) = self.p_snapshot_settings(addon_id).await {
+            warn!("Unable to snapshot settings of addon {addon_id}: {e}");
+        }
 
-        let download = entry
-            .download
-            .clone()
-            .context(error::AddonMissingDownloadUrlSnafu { id: addon_id })?;
-        let installed = self.fs_download_addon(&download, entry.md5).await?;
+        let installed = match archive {
+            Some(archive) => self.fs_extract_addon(
+                File::open(archive).context(error::AddonDownloadTmpFileReadSnafu)?,
+            )?,
+            None => {
+                let download = entry
+                    .download
+                    .clone()
+                    .context(error::AddonMissingDownloadUrlSnafu { id: addon_id })?;
+                self.fs_download_addon(&download, entry.md5).await?
+            }
+        };
         let installed_entry = InstalledAddon::ActiveModel {
             addon_id: ActiveValue::Set(addon_id),
             version: ActiveValue::Set(entry.version.to_string()),
             date: ActiveValue::Set(entry.date.to_string()),
+            reason: ActiveValue::Set(reason),
         };
 
+        let mut update_columns = vec![
+            InstalledAddon::Column::Date,
+            InstalledAddon::Column::Version,
+        ];
+        if !update && reason == InstallReason::Explicit {
+            update_columns.push(InstalledAddon::Column::Reason);
+        }
         let result = InstalledAddon::Entity::insert(installed_entry)
             .on_conflict(
                 OnConflict::column(InstalledAddon::Column::AddonId)
-                    .update_columns([
-                        InstalledAddon::Column::Date,
-                        InstalledAddon::Column::Version,
-                    ])
+                    .update_columns(update_columns)
                     .to_owned(),
             )
             .exec(&self.db)
@@ -192,11 +263,18 @@ impl AddonService {
         check_db_result(result)?;
 
         // get addon IDs from dependency dirs, there may be more than on for each directory
-        if !installed.depends_on.is_empty() {
-            let deps = installed.depends_on.iter().map(|x| AddonDep::ActiveModel {
-                addon_id: ActiveValue::Set(addon_id),
-                dependency_dir: ActiveValue::Set(x.to_owned()),
-            });
+        let deps: Vec<AddonDep::ActiveModel> = dependency_kinds(
+            installed.depends_on.iter().cloned(),
+            installed.optional_depends_on.iter().cloned(),
+        )
+        .into_iter()
+        .map(|(x, kind)| AddonDep::ActiveModel {
+            addon_id: ActiveValue::Set(addon_id),
+            dependency_dir: ActiveValue::Set(x),
+            kind: ActiveValue::Set(kind),
+        })
+        .collect();
+        if !deps.is_empty() {
             // insert all dependencies
             let result = AddonDep::Entity::insert_many(deps)
                 .on_conflict(
@@ -204,7 +282,7 @@ impl AddonService {
                         AddonDep::Column::AddonId,
                         AddonDep::Column::DependencyDir,
                     ])
-                    .do_nothing()
+                    .update_column(AddonDep::Column::Kind)
                     .to_owned(),
                 )
                 .exec(&self.db)
@@ -397,6 +475,7 @@ impl AddonService {
                     .await
                     .context(error::DbPutSnafu)?;
             }
+            search::rebuild_search_index(&txn).await?;
 
             txn.commit().await.context(error::DbPutSnafu)?;
 
@@ -463,6 +542,7 @@ impl AddonService {
             .exec(&self.db)
             .await
             .context(error::DbPutSnafu)?;
+        search::index_addon(&self.db, id).await?;
 
         Ok(())
     }
@@ -544,85 +624,214 @@ impl AddonService {
     pub fn remove(&self, addon_id: i32) -> ImmediateValuePromise<()> {
         let service = self.clone();
         ImmediateValuePromise::new(async move {
-            info!("Removing addon with id: {addon_id}");
-            let Some(addon) = DbAddon::Entity::find_by_id(addon_id)
-                .one(&service.db)
-                .await
-                .context(error::DbGetSnafu)?
-            else {
-                warn!("Not a valid addon ID!");
-                return Ok(());
-            };
-            let Some(installed_addon) = addon
-                .find_related(InstalledAddon::Entity)
+            service.p_remove(addon_id).await?;
+            Ok(())
+        })
+    }
+
+    async fn p_remove(&self, addon_id: i32) -> Result<()> {
+        info!("Removing addon with id: {addon_id}");
+        let Some(addon) = DbAddon::Entity::find_by_id(addon_id)
+            .one(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+        else {
+            warn!("Not a valid addon ID!");
+            return Ok(());
+        };
+        let Some(installed_addon) = addon
+            .find_related(InstalledAddon::Entity)
+            .one(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+        else {
+            warn!("Addon not installed!");
+            return Ok(());
+        };
+        // get installed dirs
+        let installed_dirs = addon
+            .find_related(AddonDir::Entity)
+            .filter(AddonDir::Column::Dir.ne("")) // don't delete main AddOns dir
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?;
+        installed_addon
+            .delete(&self.db)
+            .await
+            .context(error::DbDeleteSnafu)?;
+        // delete any manual dependency entities for this addon
+        ManualDependency::Entity::delete_many()
+            .filter(ManualDependency::Column::SatisfiedBy.eq(addon_id))
+            .exec(&self.db)
+            .await
+            .context(error::DbDeleteSnafu)?;
+        // delete installed addon directories
+        match fs_delete_addon(&self.get_addon_dir(), &installed_dirs) {
+            Ok(_) => {
+                info!("Removed addon {}", addon.name);
+            }
+            Err(err) => {
+                warn!("{err}");
+            }
+        }
+
+        Ok(())
+    }
+
+    /// What removing `addon_id` would break, so the caller can ask before removing it.
+    pub fn get_removal_plan(&self, addon_id: i32) -> ImmediateValuePromise<RemovalPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let addon = DbAddon::Entity::find_by_id(addon_id)
                 .one(&service.db)
                 .await
                 .context(error::DbGetSnafu)?
-            else {
-                warn!("Addon not installed!");
-                return Ok(());
-            };
-            // get installed dirs
-            let installed_dirs = addon
-                .find_related(AddonDir::Entity)
-                .filter(AddonDir::Column::Dir.ne("")) // don't delete main AddOns dir
-                .all(&service.db)
-                .await
-                .context(error::DbGetSnafu)?;
-            installed_addon
-                .delete(&service.db)
-                .await
-                .context(error::DbDeleteSnafu)?;
-            // delete any manual dependency entities for this addon
-            ManualDependency::Entity::delete_many()
-                .filter(ManualDependency::Column::SatisfiedBy.eq(addon_id))
-                .exec(&service.db)
-                .await
-                .context(error::DbDeleteSnafu)?;
-            // delete installed addon directories
-            match fs_delete_addon(&service.get_addon_dir(), &installed_dirs) {
-                Ok(_) => {
-                    info!("Removed addon {}", addon.name);
-                }
-                Err(err) => {
-                    warn!("{err}");
+                .context(error::AddonNotFoundSnafu { id: addon_id })?;
+            let dependents = addon_dependents(&service.db, addon_id, true).await?;
+
+            // dependents of dependents break too when removing in cascade
+            let mut cascade: Vec<AddonRef> = vec![];
+            let mut queue = dependents.clone();
+            while let Some(dependent) = queue.pop() {
+                if dependent.id == addon_id || cascade.iter().any(|c| c.id == dependent.id) {
+                    continue;
                 }
+                queue.extend(addon_dependents(&service.db, dependent.id, true).await?);
+                cascade.push(dependent);
             }
+            // remove the addons furthest from `addon_id` first
+            cascade.reverse();
+
+            Ok(RemovalPlan {
+                addon: AddonRef {
+                    id: addon.id,
+                    name: addon.name,
+                },
+                dependents,
+                cascade,
+            })
+        })
+    }
 
+    /// Removes the plan's addon, and with `cascade` everything that depends on it.
+    pub fn remove_plan(&self, plan: RemovalPlan, cascade: bool) -> ImmediateValuePromise<()> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            if cascade {
+                for dependent in plan.cascade.iter() {
+                    if let Err(e) = service.p_remove(dependent.id).await {
+                        service.record_error(
+                            format!("Error removing {} (#{})", dependent.name, dependent.id),
+                            e,
+                        );
+                    }
+                }
+            }
+            service.p_remove(plan.addon.id).await?;
             Ok(())
         })
     }
 
-    pub fn clear_installed(&self) -> ImmediateValuePromise<()> {
-        let db = self.db.clone();
+    /// Addons installed only as a dependency that nothing installed requires anymore,
+    /// including libraries that are only kept alive by other removable libraries.
+    pub fn get_autoremove_candidates(&self) -> ImmediateValuePromise<Vec<AddonRef>> {
+        let service = self.clone();
         ImmediateValuePromise::new(async move {
-            InstalledAddon::Entity::delete_many().exec(&db).await?;
-            Ok(())
+            let candidates = service.p_autoremove_candidates().await?;
+            Ok(candidates)
+        })
+    }
+
+    /// Removes everything [`Self::get_autoremove_candidates`] would return and
+    /// returns what was removed.
+    pub fn autoremove(&self) -> ImmediateValuePromise<Vec<AddonRef>> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let candidates = service.p_autoremove_candidates().await?;
+            let mut removed = vec![];
+            for candidate in candidates {
+                match service.p_remove(candidate.id).await {
+                    Ok(()) => removed.push(candidate),
+                    Err(e) => service.record_error(
+                        format!("Error removing {} (#{})", candidate.name, candidate.id),
+                        e,
+                    ),
+                }
+            }
+            Ok(removed)
         })
     }
 
-    pub fn search(&self, search_string: String) -> ImmediateValuePromise<Vec<AddonShowDetails>> {
-        // let mut results = vec![];
+    async fn p_autoremove_candidates(&self) -> Result<Vec<AddonRef>> {
+        #[derive(FromQueryResult)]
+        struct InstalledRow {
+            addon_id: i32,
+            name: String,
+            reason: String,
+        }
+        #[derive(FromQueryResult)]
+        struct EdgeRow {
+            addon_id: i32,
+            provider_id: i32,
+        }
+        let installed = InstalledRow::find_by_statement(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"select ia.addon_id, a.name, ia.reason
+            from installed_addon ia
+            inner join addon a on a.id = ia.addon_id"#,
+        ))
+        .all(&self.db)
+        .await
+        .context(error::DbGetSnafu)?;
+        // every installed addon that provides a dependency dir counts as needed,
+        // whether it's the chosen override or just ships the dir
+        let edges = EdgeRow::find_by_statement(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"select distinct adp.addon_id, ia2.addon_id as provider_id
+            from addon_dependency adp
+            inner join installed_addon ia on ia.addon_id = adp.addon_id
+            left join manual_dependency md on md.addon_dir = adp.dependency_dir
+            inner join installed_addon ia2 on ia2.addon_id = md.satisfied_by
+                or ia2.addon_id in (select ad.addon_id from addon_dir ad where ad.dir = adp.dependency_dir)
+            where ia2.addon_id != adp.addon_id"#,
+        ))
+        .all(&self.db)
+        .await
+        .context(error::DbGetSnafu)?;
+
+        let mut depends_on: HashMap<i32, Vec<i32>> = HashMap::new();
+        for edge in edges {
+            depends_on
+                .entry(edge.addon_id)
+                .or_default()
+                .push(edge.provider_id);
+        }
+        let dependency_reason = InstallReason::Dependency.to_value();
+        let libraries: Vec<i32> = installed
+            .iter()
+            .filter(|row| row.reason == dependency_reason)
+            .map(|row| row.addon_id)
+            .collect();
+        let all: Vec<i32> = installed.iter().map(|row| row.addon_id).collect();
+        let unused_ids = unused_libraries(&all, &libraries, &depends_on);
+
+        let mut candidates: Vec<AddonRef> = installed
+            .into_iter()
+            .filter(|row| unused_ids.contains(&row.addon_id))
+            .map(|row| AddonRef {
+                id: row.addon_id,
+                name: row.name,
+            })
+            .collect();
+        candidates.sort_by_key(|a| a.name.to_lowercase());
+        Ok(candidates)
+    }
+
+    pub fn clear_installed(&self) -> ImmediateValuePromise<()> {
         let db = self.db.clone();
         ImmediateValuePromise::new(async move {
-            let addons = DbAddon::Entity::find()
-                .column_as(InstalledAddon::Column::Version, "installed_version")
-                .column_as(InstalledAddon::Column::AddonId.is_not_null(), "installed")
-                .column_as(Category::Column::Title, "category")
-                .column_as(Expr::value("NULL"), "description")
-                .column_as(Expr::value("NULL"), "change_log")
-                .column_as(Expr::value("NULL"), "game_compat_version")
-                .column_as(Expr::value("NULL"), "game_compat_name")
-                .column_as(Category::Column::Icon, "category_icon")
-                .inner_join(Category::Entity)
-                .left_join(InstalledAddon::Entity)
-                .filter(DbAddon::Column::Name.like(format!("%{search_string}%").as_str()))
-                .order_by_desc(DbAddon::Column::Date)
-                .into_model::<AddonShowDetails>()
-                .all(&db)
-                .await
-                .context(error::DbGetSnafu)?;
-            Ok(addons)
+            InstalledAddon::Entity::delete_many().exec(&db).await?;
+            Ok(())
         })
     }
 
@@ -666,7 +875,7 @@ impl AddonService {
                 .min_depth(2)
                 .max_depth(4)
                 .into_iter();
-            let mut manifest_deps: HashMap<String, Vec<String>> = HashMap::new();
+            let mut manifest_deps: HashMap<String, Vec<(String, DependencyKind)>> = HashMap::new();
             let mut nested_dirs: HashMap<String, Vec<String>> = HashMap::new();
             for entry in walker.filter_map(|e| e.ok()).filter(|e| {
                 let path = e.path();
@@ -735,10 +944,13 @@ impl AddonService {
                         addon_versions.insert(dir_name.clone(), new_version);
                     }
                 }
-                if !manifest.depends_on.is_empty() {
+                if !manifest.depends_on.is_empty() || !manifest.optional_depends_on.is_empty() {
                     manifest_deps.insert(
                         dir_name,
-                        manifest.depends_on.into_iter().map(|d| d.title).collect(),
+                        dependency_kinds(
+                            manifest.depends_on.into_iter().map(|d| d.title),
+                            manifest.optional_depends_on.into_iter().map(|d| d.title),
+                        ),
                     );
                 }
             }
@@ -824,6 +1036,7 @@ where i.addon_id is null
                     addon_id: ActiveValue::Set(addon_id),
                     version: ActiveValue::Set(version),
                     date: ActiveValue::Set(now.to_string()),
+                    reason: ActiveValue::Set(InstallReason::Explicit),
                 })
                 .collect();
 
@@ -889,10 +1102,11 @@ where i.addon_id is null
                     let Some(&addon_id) = addon_for_dir.get(&dir) else {
                         continue;
                     };
-                    for dep_title in deps {
+                    for (dep_title, kind) in deps {
                         dep_inserts.push(AddonDep::ActiveModel {
                             addon_id: ActiveValue::Set(addon_id),
                             dependency_dir: ActiveValue::Set(dep_title),
+                            kind: ActiveValue::Set(kind),
                         });
                     }
                 }
@@ -903,7 +1117,7 @@ where i.addon_id is null
                                 AddonDep::Column::AddonId,
                                 AddonDep::Column::DependencyDir,
                             ])
-                            .do_nothing()
+                            .update_column(AddonDep::Column::Kind)
                             .to_owned(),
                         )
                         .exec(&db)
@@ -914,78 +1128,42 @@ where i.addon_id is null
 
             info!("Getting installed addons");
             // 3. Get the full installed set along with installed version
-            let results = DbAddon::Entity::find()
-                .column_as(DbAddon::Column::Version, "version")
-                .column_as(InstalledAddon::Column::Version, "installed_version")
-                .column_as(InstalledAddon::Column::AddonId.is_not_null(), "installed")
-                .column_as(Category::Column::Title, "category")
-                .column_as(Expr::value("NULL"), "description")
-                .column_as(Expr::value("NULL"), "change_log")
-                .column_as(Expr::value("NULL"), "game_compat_version")
-                .column_as(Expr::value("NULL"), "game_compat_name")
-                .column_as(Category::Column::Icon, "category_icon")
-                .inner_join(Category::Entity)
-                .inner_join(InstalledAddon::Entity)
-                .into_model::<AddonShowDetails>()
-                .all(&db)
-                .await
-                .context(error::DbGetSnafu)?;
+            let results = installed_addon_details(&db).await?;
             info!("Done getting addons!");
             Ok(results)
         })
     }
 
+    /// The installed set as text in `format`, sorted by name. Unlike
+    /// [`Self::get_installed_addons`] this doesn't scan the AddOns folder.
+    pub fn export_installed(&self, format: ExportFormat) -> ImmediateValuePromise<String> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
+            let mut addons = installed_addon_details(&db).await?;
+            addons.sort_by_key(|a| a.name.to_lowercase());
+            info!("Exporting {} addons as {}", addons.len(), format.label());
+            Ok(export_addons(&addons, format))
+        })
+    }
+
     pub fn get_missing_dependency_options(&self) -> ImmediateValuePromise<Vec<AddonDepOption>> {
         let db = self.db.clone();
 
         ImmediateValuePromise::new(async move {
             info!("Checking for missing dependencies");
+            let results = missing_dependency_options(&db, DependencyKind::Required).await?;
+            Ok(results)
+        })
+    }
 
-            let results = AddonDepOption::find_by_statement(Statement::from_sql_and_values(
-                DbBackend::Sqlite,
-                r#"select
-            dependency_dir missing_dir,
-            required_by,
-            a.id option_id,
-            a.name option_name
-        from (
-        select
-            adp.dependency_dir,
-            group_concat(a.name, ', ') required_by
-        from installed_addon i
-            inner join addon_dependency adp on i.addon_id = adp.addon_id
-            inner join addon a on i.addon_id = a.id
-        where
-            adp.dependency_dir not in (
-                SELECT
-                    DISTINCT ad.dir
-                FROM
-                    installed_addon i2
-                    inner join addon_dir ad on i2.addon_id = ad.addon_id
-            )
-        group by
-            adp.dependency_dir
-        )
-        left outer join addon_dir ad on dependency_dir = ad.dir
-        left outer join addon a on ad.addon_id = a.id
-        left outer join (
-            select addon_id, count(*) as dir_count
-            from addon_dir
-            group by addon_id
-        ) dc on dc.addon_id = a.id
-        where
-            dependency_dir not in (select addon_dir from manual_dependency)
-        order by
-            missing_dir,
-            (a.name = dependency_dir) desc,
-            coalesce(dc.dir_count, 999) asc,
-            cast(coalesce(nullif(a.download_monthly, ''), '0') as integer) desc,
-            cast(coalesce(nullif(a.date, ''), '0') as integer) desc"#,
-                [],
-            ))
-            .all(&db)
-            .await
-            .context(error::DbGetSnafu)?;
+    /// Missing `## OptionalDependsOn:` dirs of installed addons with install candidates.
+    /// These are only ever offered as suggestions, never required to proceed.
+    pub fn get_optional_dependency_options(&self) -> ImmediateValuePromise<Vec<AddonDepOption>> {
+        let db = self.db.clone();
+
+        ImmediateValuePromise::new(async move {
+            info!("Checking for optional dependencies");
+            let results = missing_dependency_options(&db, DependencyKind::Optional).await?;
             Ok(results)
         })
     }
@@ -1003,12 +1181,6 @@ where i.addon_id is null
                 id: i32,
                 name: String,
             }
-            #[derive(FromQueryResult)]
-            struct SuggestionRow {
-                dir: String,
-                id: i32,
-                name: String,
-            }
 
             let dep_rows = AddonDep::Entity::find()
                 .filter(AddonDep::Column::AddonId.eq(addon_id))
@@ -1017,20 +1189,7 @@ where i.addon_id is null
                 .context(error::DbGetSnafu)?;
             let dep_dirs: Vec<String> = dep_rows.iter().map(|r| r.dependency_dir.clone()).collect();
 
-            let dependents = AddonRef::find_by_statement(Statement::from_sql_and_values(
-                DbBackend::Sqlite,
-                r#"select distinct a.id as id, a.name as name
-                from addon_dir my_dir
-                inner join addon_dependency adp on adp.dependency_dir = my_dir.dir
-                inner join installed_addon i on i.addon_id = adp.addon_id
-                inner join addon a on a.id = adp.addon_id
-                where my_dir.addon_id = ? and adp.addon_id <> ?
-                order by a.name"#,
-                [addon_id.into(), addon_id.into()],
-            ))
-            .all(&db)
-            .await
-            .context(error::DbGetSnafu)?;
+            let dependents = addon_dependents(&db, addon_id, false).await?;
 
             let installed_addons = AddonRef::find_by_statement(Statement::from_sql_and_values(
                 DbBackend::Sqlite,
@@ -1047,6 +1206,7 @@ where i.addon_id is null
             if dep_dirs.is_empty() {
                 return Ok(AddonDependencyView {
                     forward: vec![],
+                    optional: vec![],
                     dependents,
                     installed_addons,
                 });
@@ -1104,41 +1264,9 @@ where i.addon_id is null
                 })
                 .collect();
 
-            let suggestion_sql = format!(
-                r#"select ad.dir as dir, a.id as id, a.name as name
-                from addon_dir ad
-                inner join addon a on a.id = ad.addon_id
-                left outer join (
-                    select addon_id, count(*) as dir_count
-                    from addon_dir
-                    group by addon_id
-                ) dc on dc.addon_id = a.id
-                where ad.dir in ({placeholders})
-                order by ad.dir,
-                    (a.name = ad.dir) desc,
-                    coalesce(dc.dir_count, 999) asc,
-                    cast(coalesce(nullif(a.download_monthly, ''), '0') as integer) desc,
-                    cast(coalesce(nullif(a.date, ''), '0') as integer) desc"#
-            );
-            let suggestion_values: Vec<sea_orm::Value> =
-                dep_dirs.iter().map(|d| d.clone().into()).collect();
-            let suggestion_rows = SuggestionRow::find_by_statement(Statement::from_sql_and_values(
-                DbBackend::Sqlite,
-                suggestion_sql,
-                suggestion_values,
-            ))
-            .all(&db)
-            .await
-            .context(error::DbGetSnafu)?;
-            let mut suggestion_map: HashMap<String, Vec<AddonRef>> = HashMap::new();
-            for row in suggestion_rows {
-                suggestion_map.entry(row.dir).or_default().push(AddonRef {
-                    id: row.id,
-                    name: row.name,
-                });
-            }
+            let mut suggestion_map = dir_providers(&db, &dep_dirs).await?;
 
-            let forward: Vec<DepStatus> = dep_dirs
+            let statuses: Vec<DepStatus> = dep_dirs
                 .iter()
                 .map(|dir| {
                     let resolution = if let Some(owner) = installed_owner_map.get(dir) {
@@ -1167,45 +1295,234 @@ where i.addon_id is null
                     }
                 })
                 .collect();
+            let optional_dirs: Vec<&String> = dep_rows
+                .iter()
+                .filter(|r| r.kind == DependencyKind::Optional)
+                .map(|r| &r.dependency_dir)
+                .collect();
+            let (optional, forward): (Vec<DepStatus>, Vec<DepStatus>) = statuses
+                .into_iter()
+                .partition(|d| optional_dirs.contains(&&d.dep_dir));
 
             Ok(AddonDependencyView {
                 forward,
+                optional,
                 dependents,
                 installed_addons,
             })
         })
     }
 
-    pub fn set_dep_ignored(&self, dep_dir: String) -> ImmediateValuePromise<()> {
+    /// Every installed addon with its dependencies resolved the same way as the missing
+    /// dependency checks. Ignored dirs are left out, unresolved required dirs become nodes.
+    pub fn get_dependency_graph(&self) -> ImmediateValuePromise<DependencyGraph> {
         let db = self.db.clone();
         ImmediateValuePromise::new(async move {
-            ManualDependency::Entity::insert(ManualDependency::ActiveModel {
-                addon_dir: ActiveValue::Set(dep_dir),
-                ignore: ActiveValue::Set(Some(true)),
-                satisfied_by: ActiveValue::Set(None),
-            })
-            .on_conflict(
-                OnConflict::column(ManualDependency::Column::AddonDir)
-                    .update_columns([
-                        ManualDependency::Column::Ignore,
-                        ManualDependency::Column::SatisfiedBy,
-                    ])
-                    .to_owned(),
-            )
-            .exec(&db)
+            #[derive(FromQueryResult)]
+            struct InstalledRow {
+                addon_id: i32,
+                name: String,
+                reason: String,
+            }
+            #[derive(FromQueryResult)]
+            struct DirRow {
+                addon_id: i32,
+                dir: String,
+            }
+            let installed = InstalledRow::find_by_statement(Statement::from_string(
+                DbBackend::Sqlite,
+                r#"select i.addon_id, a.name, i.reason
+                from installed_addon i
+                inner join addon a on a.id = i.addon_id
+                order by a.name"#,
+            ))
+            .all(&db)
             .await
-            .context(error::DbPutSnafu)?;
-            Ok(())
-        })
-    }
-
-    pub fn set_dep_satisfied_by(
-        &self,
-        dep_dir: String,
-        addon_id: i32,
-    ) -> ImmediateValuePromise<()> {
-        let db = self.db.clone();
-        ImmediateValuePromise::new(async move {
+            .context(error::DbGetSnafu)?;
+            let deps = AddonDep::Model::find_by_statement(Statement::from_string(
+                DbBackend::Sqlite,
+                r#"select adp.addon_id, adp.dependency_dir, adp.kind
+                from addon_dependency adp
+                inner join installed_addon i on i.addon_id = adp.addon_id
+                order by adp.addon_id, adp.dependency_dir"#,
+            ))
+            .all(&db)
+            .await
+            .context(error::DbGetSnafu)?;
+            let dirs = DirRow::find_by_statement(Statement::from_string(
+                DbBackend::Sqlite,
+                r#"select ad.addon_id, ad.dir
+                from addon_dir ad
+                inner join installed_addon i on i.addon_id = ad.addon_id"#,
+            ))
+            .all(&db)
+            .await
+            .context(error::DbGetSnafu)?;
+            let manual: HashMap<String, ManualDependency::Model> = ManualDependency::Entity::find()
+                .all(&db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|m| (m.addon_dir.clone(), m))
+                .collect();
+
+            let mut graph = DependencyGraph::default();
+            let dependency_reason = InstallReason::Dependency.to_value();
+            let mut node_index: HashMap<i32, usize> = HashMap::new();
+            for row in installed {
+                node_index.insert(row.addon_id, graph.nodes.len());
+                graph.nodes.push(GraphNode {
+                    kind: NodeKind::Addon,
+                    addon_id: Some(row.addon_id),
+                    label: row.name,
+                    dependency: row.reason == dependency_reason,
+                });
+            }
+            let mut providers: HashMap<String, Vec<usize>> = HashMap::new();
+            for row in dirs {
+                if let Some(&node) = node_index.get(&row.addon_id) {
+                    providers.entry(row.dir).or_default().push(node);
+                }
+            }
+
+            let mut unresolved: HashMap<String, usize> = HashMap::new();
+            for dep in deps {
+                let Some(&from) = node_index.get(&dep.addon_id) else {
+                    continue;
+                };
+                let optional = dep.kind == DependencyKind::Optional;
+                let mut targets: Vec<usize> = match manual.get(&dep.dependency_dir) {
+                    Some(m) if m.ignore.unwrap_or(false) => continue,
+                    Some(ManualDependency::Model {
+                        satisfied_by: Some(satisfied_by),
+                        ..
+                    }) if node_index.contains_key(satisfied_by) => {
+                        vec![node_index[satisfied_by]]
+                    }
+                    _ => providers
+                        .get(&dep.dependency_dir)
+                        .cloned()
+                        .unwrap_or_default(),
+                };
+                // bundled with the addon itself
+                if targets.contains(&from) {
+                    continue;
+                }
+                if targets.is_empty() {
+                    // a missing optional dependency isn't a problem, leave it out
+                    if optional {
+                        continue;
+                    }
+                    let to = *unresolved
+                        .entry(dep.dependency_dir.clone())
+                        .or_insert_with(|| {
+                            graph.nodes.push(GraphNode {
+                                kind: NodeKind::Unresolved,
+                                addon_id: None,
+                                label: dep.dependency_dir.clone(),
+                                dependency: false,
+                            });
+                            graph.nodes.len() - 1
+                        });
+                    targets.push(to);
+                }
+                for to in targets {
+                    graph.edges.push(GraphEdge {
+                        from,
+                        to,
+                        dir: dep.dependency_dir.clone(),
+                        optional,
+                    });
+                }
+            }
+            Ok(graph)
+        })
+    }
+
+    /// Libraries found more than once, e.g. bundled inside other addons, where some copies
+    /// have a lower `AddOnVersion` than the one the game loads.
+    pub fn find_shadowed_libraries(&self) -> ImmediateValuePromise<Vec<ShadowedLibrary>> {
+        let db = self.db.clone();
+        let addon_dir = self.get_addon_dir();
+        ImmediateValuePromise::new(async move {
+            let copies = fs_find_addon_copies(&addon_dir);
+            let dirs: Vec<String> = copies.keys().cloned().collect();
+            let providers = dir_providers(&db, &dirs).await?;
+            let installed: Vec<i32> = InstalledAddon::Entity::find()
+                .all(&db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|i| i.addon_id)
+                .collect();
+
+            let mut shadowed = vec![];
+            for (dir, copies) in copies {
+                // addons bundling a copy also ship the dir, they're no fix
+                let bundling_dirs: Vec<String> = copies
+                    .iter()
+                    .filter(|c| c.nested)
+                    .filter_map(|c| c.path.components().next())
+                    .map(|c| c.as_os_str().to_string_lossy().to_string())
+                    .collect();
+                let bundling = resolve_dirs_to_addons(&db, &bundling_dirs).await?;
+                let Some((loaded, stale)) = split_loaded_copy(copies) else {
+                    continue;
+                };
+                if stale.is_empty() {
+                    continue;
+                }
+                let standalone = providers.get(&dir).and_then(|p| {
+                    p.iter()
+                        .find(|a| !bundling.values().any(|id| *id == a.id))
+                        .cloned()
+                });
+                let standalone_installed = standalone
+                    .as_ref()
+                    .is_some_and(|a| installed.contains(&a.id));
+                shadowed.push(ShadowedLibrary {
+                    dir,
+                    loaded,
+                    stale,
+                    standalone,
+                    standalone_installed,
+                });
+            }
+            shadowed.sort_by_key(|s| s.dir.to_lowercase());
+            Ok(shadowed)
+        })
+    }
+
+    pub fn set_dep_ignored(&self, dep_dir: String) -> ImmediateValuePromise<()> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
+            ManualDependency::Entity::insert(ManualDependency::ActiveModel {
+                addon_dir: ActiveValue::Set(dep_dir),
+                ignore: ActiveValue::Set(Some(true)),
+                satisfied_by: ActiveValue::Set(None),
+            })
+            .on_conflict(
+                OnConflict::column(ManualDependency::Column::AddonDir)
+                    .update_columns([
+                        ManualDependency::Column::Ignore,
+                        ManualDependency::Column::SatisfiedBy,
+                    ])
+                    .to_owned(),
+            )
+            .exec(&db)
+            .await
+            .context(error::DbPutSnafu)?;
+            Ok(())
+        })
+    }
+
+    pub fn set_dep_satisfied_by(
+        &self,
+        dep_dir: String,
+        addon_id: i32,
+    ) -> ImmediateValuePromise<()> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
             ManualDependency::Entity::insert(ManualDependency::ActiveModel {
                 addon_dir: ActiveValue::Set(dep_dir),
                 ignore: ActiveValue::Set(Some(false)),
@@ -1241,7 +1558,9 @@ where i.addon_id is null
         let service = self.clone();
         ImmediateValuePromise::new(async move {
             for (dep_dir, addon_id) in items {
-                service.p_install(addon_id, false).await?;
+                service
+                    .p_install(addon_id, false, InstallReason::Dependency)
+                    .await?;
                 ManualDependency::Entity::insert(ManualDependency::ActiveModel {
                     addon_dir: ActiveValue::Set(dep_dir),
                     ignore: ActiveValue::Set(Some(false)),
@@ -1326,6 +1645,15 @@ where i.addon_id is null
         path_addr: Option<&str>,
         md5: Option<String>,
     ) -> Result<ZipArchive<File>> {
+        let tmpfile = self.fs_download(url, md5).await?;
+        let file = tmpfile
+            .reopen()
+            .context(error::AddonDownloadTmpFileReadSnafu)?;
+        self.fs_extract(file, path_addr)
+    }
+
+    /// Downloads `url` to a temp file, the hash is only checked if one is given.
+    async fn fs_download(&self, url: &str, md5: Option<String>) -> Result<NamedTempFile> {
         let response = self
             .api
             .download_file(url)
@@ -1369,13 +1697,14 @@ where i.addon_id is null
             if md5 != hash_string {
                 warn!("Expected file hash {md5}, got {hash_string}");
             }
-            r_tmpfile
-                .rewind()
-                .context(error::AddonDownloadTmpFileReadSnafu)?;
         }
 
-        let mut archive =
-            zip::ZipArchive::new(r_tmpfile).context(error::AddonDownloadZipCreateSnafu)?;
+        Ok(tmpfile)
+    }
+
+    /// Extracts an addon archive into the addon dir, or `path_addr` below it.
+    fn fs_extract(&self, file: File, path_addr: Option<&str>) -> Result<ZipArchive<File>> {
+        let mut archive = zip::ZipArchive::new(file).context(error::AddonDownloadZipCreateSnafu)?;
 
         for i in 0..archive.len() {
             let mut file = archive
@@ -1418,7 +1747,15 @@ where i.addon_id is null
     }
 
     async fn fs_download_addon(&self, url: &str, md5: Option<String>) -> Result<Addon> {
-        let mut archive = self.base_fs_download_extract(url, None, md5).await?;
+        let tmpfile = self.fs_download(url, md5).await?;
+        let file = tmpfile
+            .reopen()
+            .context(error::AddonDownloadTmpFileReadSnafu)?;
+        self.fs_extract_addon(file)
+    }
+
+    fn fs_extract_addon(&self, file: File) -> Result<Addon> {
+        let mut archive = self.fs_extract(file, None)?;
         let mut addon_path = self.get_addon_dir();
         let addon_name = archive
             .by_index(0)
@@ -1434,35 +1771,8 @@ where i.addon_id is null
         ImmediateValuePromise::new(async move {
             info!("Updating TTC PriceTable");
             let mut update = TtcConfigUpdate::default();
-            let region = &service.config.ttc_region;
-
-            let mut targets = vec![];
-            if *region == TTCRegion::NA || *region == TTCRegion::ALL {
-                targets.push((TTC_NA_DOMAIN, service.config.ttc_na_version));
-            }
-            if *region == TTCRegion::EU || *region == TTCRegion::ALL {
-                targets.push((TTC_EU_DOMAIN, service.config.ttc_eu_version));
-            }
-
             let mut downloaded = false;
-            for (domain, local_version) in targets {
-                // Conditional check: skip download when the server PriceTable
-                // version matches what we last downloaded. Fall back to
-                // downloading if the version can't be fetched.
-                let server_version = match service.api.get_ttc_pricetable_version(domain).await {
-                    Ok(v) => Some(v),
-                    Err(e) => {
-                        warn!("Could not fetch TTC version for {domain}: {e}; downloading anyway");
-                        None
-                    }
-                };
-                if let (Some(server), Some(local)) = (server_version, local_version)
-                    && server == local
-                {
-                    info!("TTC PriceTable {domain} unchanged (v{server}), skipping download");
-                    continue;
-                }
-
+            for (domain, server_version) in service.p_ttc_downloads().await {
                 let url = format!("https://{domain}/download/PriceTable");
                 service
                     .base_fs_download_extract(&url, Some("TamrielTradeCentre"), None)
@@ -1482,6 +1792,39 @@ where i.addon_id is null
         })
     }
 
+    /// PriceTable domains to download for the configured region, with the server's
+    /// version. Skips a domain when the server version matches the one last downloaded,
+    /// falls back to downloading if the version can't be fetched.
+    async fn p_ttc_downloads(&self) -> Vec<(&'static str, Option<u64>)> {
+        let region = &self.config.ttc_region;
+        let mut targets = vec![];
+        if *region == TTCRegion::NA || *region == TTCRegion::ALL {
+            targets.push((TTC_NA_DOMAIN, self.config.ttc_na_version));
+        }
+        if *region == TTCRegion::EU || *region == TTCRegion::ALL {
+            targets.push((TTC_EU_DOMAIN, self.config.ttc_eu_version));
+        }
+
+        let mut downloads = vec![];
+        for (domain, local_version) in targets {
+            let server_version = match self.api.get_ttc_pricetable_version(domain).await {
+                Ok(v) => Some(v),
+                Err(e) => {
+                    warn!("Could not fetch TTC version for {domain}: {e}; downloading anyway");
+                    None
+                }
+            };
+            if let (Some(server), Some(local)) = (server_version, local_version)
+                && server == local
+            {
+                info!("TTC PriceTable {domain} unchanged (v{server}), skipping download");
+                continue;
+            }
+            downloads.push((domain, server_version));
+        }
+        downloads
+    }
+
     pub fn import_minion_file(&mut self, file: &Path) -> ImmediateValuePromise<()> {
         // Takes a path to a minion backup file, it should be named something like `BU-addons.txt`
         // It should contain a single line of comma-separated addon IDs
@@ -1504,7 +1847,10 @@ where i.addon_id is null
             }
             // workaround for weird behavior with promise in promise, slowly install addons one at a time
             for addon_id in ids.iter() {
-                if let Err(e) = service.p_install(*addon_id, false).await {
+                if let Err(e) = service
+                    .p_install(*addon_id, false, InstallReason::Explicit)
+                    .await
+                {
                     let label = service.addon_label(*addon_id).await;
                     service.record_error(format!("Error installing {label}"), e);
                 }
@@ -1614,7 +1960,10 @@ where i.addon_id is null
                     // if it's in the options, it means not installed
                     if dep_opt.options.contains_key(&satisfied_by) {
                         // install addon
-                        if let Err(e) = service.p_install(satisfied_by, false).await {
+                        if let Err(e) = service
+                            .p_install(satisfied_by, false, InstallReason::Dependency)
+                            .await
+                        {
                             let label = service.addon_label(satisfied_by).await;
                             service.record_error(format!("Error installing {label}"), e);
                         }
@@ -1646,68 +1995,19 @@ where i.addon_id is null
         let api = self.api.clone();
         ImmediateValuePromise::new(async move {
             info!("Updating HarvestMap data...");
-            // ensure HarvestMap-Data installed (id: 3034)
-            const HMD_ID: i32 = 3034;
-
-            let hmd_addon = InstalledAddon::Entity::find_by_id(HMD_ID)
+            let hmd_addon = InstalledAddon::Entity::find_by_id(HM_DATA_ADDON_ID)
                 .one(&db)
                 .await
                 .context(error::DbGetSnafu)?;
             ensure!(hmd_addon.is_some(), error::HarvestMapDataNotInstalledSnafu);
 
-            let base_dir = config.addon_dir.parent().unwrap_or(&config.addon_dir);
-            let saved_var_dir = base_dir.join("SavedVariables");
-            let addon_dir = config.addon_dir.join("HarvestMapData");
-            let mut empty_file = addon_dir.join("Main");
-            empty_file.push("emptyTable.lua");
-
-            let empty_file_data = fs::read_to_string(empty_file)?;
-
-            // Refresh a zone at least this often even when local data is
-            // unchanged, so community data does not go stale.
-            let max_age = chrono::Duration::days(1);
             let now = chrono::Utc::now();
             let mut update = HmConfigUpdate::default();
+            for zone in fs_hm_zones_due(&config, now)? {
+                info!("Syncing HarvestMap {}...", zone.zone);
+                let response = api.get_hm_data(zone.data).await?;
 
-            // iterate over the different zones
-            for zone in ["AD", "EP", "DC", "DLC", "NF"] {
-                let file_name = format!("HarvestMap{zone}.lua");
-
-                let sv_file = saved_var_dir.join(&file_name);
-                let data = if sv_file.exists() {
-                    fs::read_to_string(&sv_file)?
-                } else {
-                    format!("Harvest{zone}_SavedVars{}", empty_file_data.as_str())
-                };
-
-                // Hash the local data so we can skip the merge when nothing has
-                // changed since our last sync.
-                let mut hasher = Md5::new();
-                hasher.update(data.as_bytes());
-                let mut hash = String::new();
-                for x in hasher.finalize().iter() {
-                    hash.push_str(format!("{x:02x}").as_str());
-                }
-
-                let mut out_file = addon_dir.join("Modules");
-                out_file.push(format!("HarvestMap{zone}"));
-                out_file.push(&file_name);
-
-                let fresh = config
-                    .hm_zone_synced
-                    .get(zone)
-                    .is_some_and(|t| now.signed_duration_since(*t) < max_age);
-                if config.hm_zone_hashes.get(zone).map(String::as_str) == Some(hash.as_str())
-                    && out_file.exists()
-                    && fresh
-                {
-                    info!("HarvestMap {zone} unchanged and fresh, skipping");
-                    continue;
-                }
-
-                info!("Syncing HarvestMap {zone}...");
-                let response = api.get_hm_data(data).await?;
-
+                let out_file = zone.out_file;
                 let out_dir = out_file.parent().unwrap();
                 if !out_dir.exists() {
                     fs::create_dir_all(out_dir)?;
@@ -1723,7 +2023,7 @@ where i.addon_id is null
                     .map_err(|e| e.error)
                     .context(error::WriteResultSnafu { path: out_file })?;
 
-                update.zone_hashes.insert(zone.to_string(), hash);
+                update.zone_hashes.insert(zone.zone.to_string(), zone.hash);
             }
 
             if !update.zone_hashes.is_empty() {
@@ -1778,80 +2078,237 @@ where i.addon_id is null
 
     // region: Backup/restore data
 
-    /// Backup installed addon data to file
-    pub fn backup_data(&self, file: PathBuf) -> ImmediateValuePromise<()> {
-        let db = self.db.clone();
+    /// Backup installed addon data, manual dependencies and settings to file, along
+    /// with SavedVariables and AddOnSettings.txt if `options` asks for them
+    pub fn backup_data(&self, file: PathBuf, options: BackupOptions) -> ImmediateValuePromise<()> {
+        let service = self.clone();
         ImmediateValuePromise::new(async move {
-            let installed_addons = InstalledAddon::Entity::find()
-                .column(InstalledAddon::Column::AddonId)
-                .column(InstalledAddon::Column::Version)
-                .into_model::<BackupInstalledAddon>()
-                .all(&db)
-                .await
-                .context(error::DbGetSnafu)?;
-            let manual_deps = ManualDependency::Entity::find()
-                .into_model::<BackupManualDependency>()
-                .all(&db)
+            service.p_backup(&file, options).await?;
+            Ok(())
+        })
+    }
+
+    /// The folder holding AddOns, SavedVariables and AddOnSettings.txt
+    pub fn get_game_dir(&self) -> PathBuf {
+        let addon_dir = &self.config.addon_dir;
+        addon_dir.parent().unwrap_or(addon_dir).to_path_buf()
+    }
+
+    /// Installed addons as they are written to a backup
+    async fn p_backup_addons(&self) -> Result<Vec<BackupAddon>> {
+        BackupAddon::find_by_statement(Statement::from_string(
+            DbBackend::Sqlite,
+            r#"select i.addon_id, a.name, i.version, i.date, i.reason
+            from installed_addon i
+            inner join addon a on a.id = i.addon_id
+            order by a.name"#,
+        ))
+        .all(&self.db)
+        .await
+        .context(error::DbGetSnafu)
+    }
+
+    async fn p_backup(&self, file: &Path, options: BackupOptions) -> Result<()> {
+        let installed_addons = self.p_backup_addons().await?;
+        let manual_deps = ManualDependency::Entity::find()
+            .into_model::<BackupManualDependency>()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?;
+        let backup_data = BackupData {
+            format_version: BACKUP_FORMAT_VERSION,
+            created: format!("{}", chrono::Utc::now().format("%Y-%m-%d %H:%M:%S UTC")),
+            config: Some(BackupConfig::from(&self.config)),
+            installed_addons,
+            manual_dependencies: manual_deps,
+        };
+        let manifest =
+            serde_json::to_vec_pretty(&backup_data).context(error::BackupSerializeSnafu)?;
+
+        if options.is_archive() {
+            let game_dir = self.get_game_dir();
+            let files = fs_backup_files(&game_dir, &options);
+            info!(
+                "Backing up {} game files to {}",
+                files.len(),
+                file.display()
+            );
+            fs_write_backup_archive(file, &manifest, &game_dir, &files)?;
+        } else {
+            fs::write(file, manifest).context(error::BackupWriteSnafu { path: file })?;
+        }
+        Ok(())
+    }
+
+    /// Reads a backup and works out what restoring it would do, without changing anything.
+    pub fn get_restore_plan(&self, file: PathBuf) -> ImmediateValuePromise<RestorePlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let plan = service.p_restore_plan(file).await?;
+            Ok(plan)
+        })
+    }
+
+    /// Installs the missing addons of a restore plan through the normal install path,
+    /// and writes back any game files from an archive backup. Addons already in the
+    /// AddOns folder are adopted with their backed up version, installed addons that
+    /// aren't in the backup are left alone.
+    pub fn restore_plan(&self, plan: RestorePlan) -> ImmediateValuePromise<RestoreReport> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let report = service.p_restore(plan).await?;
+            Ok(report)
+        })
+    }
+
+    async fn p_restore_plan(&self, file: PathBuf) -> Result<RestorePlan> {
+        let mut reader = File::open(&file).context(error::BackupReadSnafu { path: &file })?;
+        let mut magic = [0; 4];
+        let is_archive = reader.read_exact(&mut magic).is_ok() && magic == *b"PK\x03\x04";
+        reader
+            .rewind()
+            .context(error::BackupReadSnafu { path: &file })?;
+
+        let mut buf = String::new();
+        let mut plan = RestorePlan::default();
+        if is_archive {
+            let mut zip = ZipArchive::new(reader).context(error::BackupArchiveSnafu)?;
+            buf = zip_read_backup_manifest(&mut zip, &file)?;
+            (plan.files, plan.unchanged_files) = zip_backup_files(&mut zip, &self.get_game_dir())?;
+            plan.archive = Some(file);
+        } else {
+            reader
+                .read_to_string(&mut buf)
+                .context(error::BackupReadSnafu { path: &file })?;
+        }
+        let data = parse_backup(&buf)?;
+        plan.manual_dependencies = data.manual_dependencies;
+        // nested sub-addons are registered along with their parent's top-level dir
+        let manifest_dirs = fs_find_manifest_dirs(&self.config.addon_dir);
+
+        for x in data.installed_addons {
+            let reason =
+                InstallReason::try_from_value(&x.reason).unwrap_or(InstallReason::Explicit);
+            let catalog = DbAddon::Entity::find_by_id(x.addon_id)
+                .one(&self.db)
                 .await
                 .context(error::DbGetSnafu)?;
-            let backup_data = BackupData {
-                installed_addons,
-                manual_dependencies: manual_deps,
+            let mut entry = RestoreEntry {
+                addon: AddonRef {
+                    id: x.addon_id,
+                    name: x.name,
+                },
+                version: x.version,
+                available_version: String::new(),
+                reason,
+                date: x.date,
             };
+            let Some(catalog) = catalog else {
+                plan.unavailable.push(entry);
+                continue;
+            };
+            entry.addon.name = catalog.name;
+            entry.available_version = catalog.version;
 
-            serde_json::to_writer(&File::create(file)?, &backup_data)?;
-            Ok(())
-        })
+            let dirs = AddonDir::Entity::find()
+                .filter(AddonDir::Column::AddonId.eq(x.addon_id))
+                .all(&self.db)
+                .await
+                .context(error::DbGetSnafu)?;
+            let on_disk =
+                !dirs.is_empty() && dirs.iter().all(|d| manifest_dirs.contains_key(&d.dir));
+            if on_disk {
+                plan.present.push(entry);
+            } else {
+                plan.to_install.push(entry);
+            }
+        }
+        info!(
+            "Restore plan: {} to install, {} present, {} unavailable",
+            plan.to_install.len(),
+            plan.present.len(),
+            plan.unavailable.len()
+        );
+        Ok(plan)
     }
 
-    /// Restore backed up data from file to database
-    pub fn restore_backup(&self, file: PathBuf) -> ImmediateValuePromise<()> {
-        let db = self.db.clone();
-        ImmediateValuePromise::new(async move {
-            let mut f = File::open(file)?;
-            let mut buf = String::new();
-            f.read_to_string(&mut buf)?;
-
-            let data: BackupData = serde_json::from_str(&buf)?;
-
-            if !data.installed_addons.is_empty() {
-                // remove existing installed data
-                InstalledAddon::Entity::delete_many().exec(&db).await?;
-
-                // import installed addon data
-                let mut installed_addons = vec![];
-                for x in data.installed_addons {
-                    installed_addons.push(InstalledAddon::ActiveModel {
-                        addon_id: ActiveValue::Set(x.addon_id),
-                        version: ActiveValue::Set("0".to_owned()),
-                        date: ActiveValue::Set(x.date),
-                    })
-                }
-                InstalledAddon::Entity::insert_many(installed_addons)
-                    .exec(&db)
-                    .await?;
+    async fn p_restore(&self, plan: RestorePlan) -> Result<RestoreReport> {
+        if !plan.manual_dependencies.is_empty() {
+            // remove existing manual dep data
+            ManualDependency::Entity::delete_many()
+                .exec(&self.db)
+                .await
+                .context(error::DbDeleteSnafu)?;
+
+            // import manual dep data
+            let mut dep_inserts = vec![];
+            for x in plan.manual_dependencies {
+                dep_inserts.push(ManualDependency::ActiveModel {
+                    addon_dir: ActiveValue::Set(x.addon_dir),
+                    satisfied_by: ActiveValue::Set(x.satisfied_by),
+                    ignore: ActiveValue::Set(x.ignore),
+                });
             }
+            let result = ManualDependency::Entity::insert_many(dep_inserts)
+                .exec(&self.db)
+                .await;
+            check_db_result(result)?;
+        }
 
-            if !data.manual_dependencies.is_empty() {
-                // remove existing manual dep data
-                ManualDependency::Entity::delete_many().exec(&db).await?;
-
-                // import manual dep data
-                let mut dep_inserts = vec![];
-                for x in data.manual_dependencies {
-                    dep_inserts.push(ManualDependency::ActiveModel {
-                        addon_dir: ActiveValue::Set(x.addon_dir),
-                        satisfied_by: ActiveValue::Set(x.satisfied_by),
-                        ignore: ActiveValue::Set(x.ignore),
-                    });
+        let mut report = RestoreReport {
+            unavailable: plan.unavailable.into_iter().map(|e| e.addon).collect(),
+            ..Default::default()
+        };
+
+        if let Some(archive) = plan.archive.as_ref() {
+            let reader = File::open(archive).context(error::BackupReadSnafu { path: archive })?;
+            let mut zip = ZipArchive::new(reader).context(error::BackupArchiveSnafu)?;
+            report.files_restored = zip_restore_files(&mut zip, &self.get_game_dir(), &plan.files)?;
+            report.files_skipped = plan.files.len() - report.files_restored;
+            info!(
+                "Restored {} game files, skipped {}",
+                report.files_restored, report.files_skipped
+            );
+        }
+
+        for entry in plan.present {
+            let installed_entry = InstalledAddon::ActiveModel {
+                addon_id: ActiveValue::Set(entry.addon.id),
+                version: ActiveValue::Set(entry.version),
+                date: ActiveValue::Set(entry.date),
+                reason: ActiveValue::Set(entry.reason),
+            };
+            let result = InstalledAddon::Entity::insert(installed_entry)
+                .on_conflict(
+                    OnConflict::column(InstalledAddon::Column::AddonId)
+                        .update_column(InstalledAddon::Column::Reason)
+                        .to_owned(),
+                )
+                .exec(&self.db)
+                .await;
+            check_db_result(result)?;
+            report.present.push(entry.addon);
+        }
+
+        // libraries first, so addons find their dependencies already installed
+        let mut to_install = plan.to_install;
+        to_install.sort_by_key(|e| e.reason != InstallReason::Dependency);
+        for entry in to_install {
+            match self.p_install(entry.addon.id, false, entry.reason).await {
+                Ok(()) => report.installed.push(entry.addon),
+                Err(e) => {
+                    let label = format!("{} (#{})", entry.addon.name, entry.addon.id);
+                    report.failed.push((entry.addon, e.to_string()));
+                    self.record_error(format!("Error restoring {label}"), e);
                 }
-                ManualDependency::Entity::insert_many(dep_inserts)
-                    .exec(&db)
-                    .await?;
             }
-
-            Ok(())
-        })
+        }
+        info!(
+            "Restored {} addons, {} failed",
+            report.installed.len(),
+            report.failed.len()
+        );
+        Ok(report)
     }
 
     // endregion
@@ -1868,11 +2325,253 @@ where i.addon_id is null
                 .await?;
             // clear addon details
             AddonDetail::Entity::delete_many().exec(&db).await?;
+            search::rebuild_search_index(&db).await?;
             Ok(())
         })
     }
 }
 
+/// A HarvestMap zone whose local data changed or went stale since its last sync.
+struct HmZone {
+    zone: &'static str,
+    /// SavedVariables to upload, merged with the community data in the response
+    data: String,
+    hash: String,
+    out_file: PathBuf,
+}
+
+/// HarvestMap zones to sync. A zone is skipped when its local data is unchanged since
+/// the last sync and that sync is recent, so community data doesn't go stale.
+fn fs_hm_zones_due(config: &Config, now: chrono::DateTime<chrono::Utc>) -> io::Result<Vec<HmZone>> {
+    let base_dir = config.addon_dir.parent().unwrap_or(&config.addon_dir);
+    let saved_var_dir = base_dir.join("SavedVariables");
+    let addon_dir = config.addon_dir.join("HarvestMapData");
+    let mut empty_file = addon_dir.join("Main");
+    empty_file.push("emptyTable.lua");
+
+    let empty_file_data = fs::read_to_string(empty_file)?;
+
+    // Refresh a zone at least this often even when local data is unchanged
+    let max_age = chrono::Duration::days(1);
+    let mut zones = vec![];
+    // iterate over the different zones
+    for zone in ["AD", "EP", "DC", "DLC", "NF"] {
+        let file_name = format!("HarvestMap{zone}.lua");
+
+        let sv_file = saved_var_dir.join(&file_name);
+        let data = if sv_file.exists() {
+            fs::read_to_string(&sv_file)?
+        } else {
+            format!("Harvest{zone}_SavedVars{}", empty_file_data.as_str())
+        };
+
+        // Hash the local data so we can skip the merge when nothing has
+        // changed since our last sync.
+        let mut hasher = Md5::new();
+        hasher.update(data.as_bytes());
+        let mut hash = String::new();
+        for x in hasher.finalize().iter() {
+            hash.push_str(format!("{x:02x}").as_str());
+        }
+
+        let mut out_file = addon_dir.join("Modules");
+        out_file.push(format!("HarvestMap{zone}"));
+        out_file.push(&file_name);
+
+        let fresh = config
+            .hm_zone_synced
+            .get(zone)
+            .is_some_and(|t| now.signed_duration_since(*t) < max_age);
+        if config.hm_zone_hashes.get(zone).map(String::as_str) == Some(hash.as_str())
+            && out_file.exists()
+            && fresh
+        {
+            info!("HarvestMap {zone} unchanged and fresh, skipping");
+            continue;
+        }
+        zones.push(HmZone {
+            zone,
+            data,
+            hash,
+            out_file,
+        });
+    }
+    Ok(zones)
+}
+
+/// Dependency dirs of `kind` not provided by any installed addon and without a manual
+/// override, joined with every catalog addon that ships the dir, best candidate first.
+/// Optional dirs that are also required by an installed addon are left to the required list.
+async fn missing_dependency_options<C: ConnectionTrait>(
+    db: &C,
+    kind: DependencyKind,
+) -> Result<Vec<AddonDepOption>> {
+    let required_elsewhere = if kind == DependencyKind::Optional {
+        r#"and adp.dependency_dir not in (
+                select r.dependency_dir
+                from addon_dependency r
+                    inner join installed_addon ri on ri.addon_id = r.addon_id
+                where r.kind = 'required'
+            )"#
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"select
+            dependency_dir missing_dir,
+            required_by,
+            a.id option_id,
+            a.name option_name
+        from (
+        select
+            adp.dependency_dir,
+            group_concat(a.name, ', ') required_by
+        from installed_addon i
+            inner join addon_dependency adp on i.addon_id = adp.addon_id
+            inner join addon a on i.addon_id = a.id
+        where
+            adp.kind = ?
+            and adp.dependency_dir not in (
+                SELECT
+                    DISTINCT ad.dir
+                FROM
+                    installed_addon i2
+                    inner join addon_dir ad on i2.addon_id = ad.addon_id
+            )
+            {required_elsewhere}
+        group by
+            adp.dependency_dir
+        )
+        left outer join addon_dir ad on dependency_dir = ad.dir
+        left outer join addon a on ad.addon_id = a.id
+        left outer join (
+            select addon_id, count(*) as dir_count
+            from addon_dir
+            group by addon_id
+        ) dc on dc.addon_id = a.id
+        where
+            dependency_dir not in (select addon_dir from manual_dependency)
+        order by
+            missing_dir,
+            {DIR_PROVIDER_RANKING}"#
+    );
+    AddonDepOption::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        [kind.to_value().into()],
+    ))
+    .all(db)
+    .await
+    .context(error::DbGetSnafu)
+}
+
+/// Installed addons requiring a dir that `addon_id` ships. With `exclusive`, dirs
+/// also shipped by another installed addon are skipped since removing
+/// `addon_id` doesn't break them.
+async fn addon_dependents<C: ConnectionTrait>(
+    db: &C,
+    addon_id: i32,
+    exclusive: bool,
+) -> Result<Vec<AddonRef>> {
+    let exclusive_clause = if exclusive {
+        r#"and not exists (
+                    select 1
+                    from addon_dir other_dir
+                    inner join installed_addon oi on oi.addon_id = other_dir.addon_id
+                    where other_dir.dir = my_dir.dir and other_dir.addon_id <> my_dir.addon_id
+                )"#
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"select distinct a.id as id, a.name as name
+        from addon_dir my_dir
+        inner join addon_dependency adp on adp.dependency_dir = my_dir.dir
+        inner join installed_addon i on i.addon_id = adp.addon_id
+        inner join addon a on a.id = adp.addon_id
+        where my_dir.addon_id = ? and adp.addon_id <> ? and adp.kind = 'required'
+            {exclusive_clause}
+        order by a.name"#
+    );
+    AddonRef::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        [addon_id.into(), addon_id.into()],
+    ))
+    .all(db)
+    .await
+    .context(error::DbGetSnafu)
+}
+
+/// Every catalog addon that ships one of `dirs`, best candidate first using
+/// [`DIR_PROVIDER_RANKING`].
+async fn dir_providers<C: ConnectionTrait>(
+    db: &C,
+    dirs: &[String],
+) -> Result<HashMap<String, Vec<AddonRef>>> {
+    #[derive(FromQueryResult)]
+    struct ProviderRow {
+        dir: String,
+        id: i32,
+        name: String,
+    }
+    let mut providers: HashMap<String, Vec<AddonRef>> = HashMap::new();
+    if dirs.is_empty() {
+        return Ok(providers);
+    }
+    let placeholders = vec!["?"; dirs.len()].join(",");
+    let sql = format!(
+        r#"select ad.dir as dir, a.id as id, a.name as name
+        from addon_dir ad
+        inner join addon a on a.id = ad.addon_id
+        left outer join (
+            select addon_id, count(*) as dir_count
+            from addon_dir
+            group by addon_id
+        ) dc on dc.addon_id = a.id
+        where ad.dir in ({placeholders})
+        order by ad.dir,
+            {DIR_PROVIDER_RANKING}"#
+    );
+    let rows = ProviderRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        dirs.iter()
+            .map(|d| d.clone().into())
+            .collect::<Vec<Value>>(),
+    ))
+    .all(db)
+    .await
+    .context(error::DbGetSnafu)?;
+    for row in rows {
+        providers.entry(row.dir).or_default().push(AddonRef {
+            id: row.id,
+            name: row.name,
+        });
+    }
+    Ok(providers)
+}
+
+/// Installed addons with their catalog details, without descriptions and change logs.
+async fn installed_addon_details<C: ConnectionTrait>(db: &C) -> Result<Vec<AddonShowDetails>> {
+    DbAddon::Entity::find()
+        .column_as(DbAddon::Column::Version, "version")
+        .column_as(InstalledAddon::Column::Version, "installed_version")
+        .column_as(InstalledAddon::Column::AddonId.is_not_null(), "installed")
+        .column_as(Category::Column::Title, "category")
+        .column_as(Expr::value("NULL"), "description")
+        .column_as(Expr::value("NULL"), "change_log")
+        .column_as(Expr::value("NULL"), "game_compat_version")
+        .column_as(Expr::value("NULL"), "game_compat_name")
+        .column_as(Category::Column::Icon, "category_icon")
+        .inner_join(Category::Entity)
+        .inner_join(InstalledAddon::Entity)
+        .into_model::<AddonShowDetails>()
+        .all(db)
+        .await
+        .context(error::DbGetSnafu)
+}
+
 async fn resolve_dirs_to_addons<C: ConnectionTrait>(
     db: &C,
     dirs: &[String],
@@ -1905,6 +2604,61 @@ async fn resolve_dirs_to_addons<C: ConnectionTrait>(
         .collect())
 }
 
+/// Libraries among `installed` that no other installed addon depends on. Removing one
+/// can leave the libraries it depends on unused, so this repeats until nothing changes.
+/// `depends_on` maps an addon to the installed addons providing its dependencies.
+fn unused_libraries(
+    installed: &[i32],
+    libraries: &[i32],
+    depends_on: &HashMap<i32, Vec<i32>>,
+) -> Vec<i32> {
+    let mut remaining: HashSet<i32> = installed.iter().copied().collect();
+    let mut unused = vec![];
+    loop {
+        let needed: HashSet<i32> = remaining
+            .iter()
+            .filter_map(|id| depends_on.get(id))
+            .flatten()
+            .copied()
+            .collect();
+        let found: Vec<i32> = libraries
+            .iter()
+            .copied()
+            .filter(|id| remaining.contains(id) && !needed.contains(id))
+            .collect();
+        if found.is_empty() {
+            return unused;
+        }
+        for id in found {
+            remaining.remove(&id);
+            unused.push(id);
+        }
+    }
+}
+
+/// Pair each dependency dir with its kind, one entry per dir. A dir listed under both
+/// `## DependsOn:` and `## OptionalDependsOn:` stays required.
+fn dependency_kinds(
+    required: impl IntoIterator<Item = String>,
+    optional: impl IntoIterator<Item = String>,
+) -> Vec<(String, DependencyKind)> {
+    let mut deps: Vec<(String, DependencyKind)> = Vec::new();
+    let tagged = required
+        .into_iter()
+        .map(|dir| (dir, DependencyKind::Required))
+        .chain(
+            optional
+                .into_iter()
+                .map(|dir| (dir, DependencyKind::Optional)),
+        );
+    for (dir, kind) in tagged {
+        if !deps.iter().any(|(seen, _)| *seen == dir) {
+            deps.push((dir, kind));
+        }
+    }
+    deps
+}
+
 /// Use for inserts where no updates/inserts OK
 /// sea_orm now returns DbErr::RecordNotInserted when no inserts
 fn check_db_result<T>(result: Result<T, DbErr>) -> Result<()> {
@@ -1913,3 +2667,44 @@ fn check_db_result<T>(result: Result<T, DbErr>) -> Result<()> {
         Err(e) => Err(e).context(error::DbPutSnafu),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dirs(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_unused_libraries() {
+        // 1 uses 10, 10 uses 11, 12 is used by nothing, 13 by the unused 12
+        let depends_on = HashMap::from([(1, vec![10]), (10, vec![11]), (12, vec![13])]);
+        let mut unused = unused_libraries(&[1, 10, 11, 12, 13], &[10, 11, 12, 13], &depends_on);
+        unused.sort();
+        assert_eq!(unused, vec![12, 13]);
+
+        // once nothing uses 10 the library it uses goes too, explicit addons never do
+        let depends_on = HashMap::from([(10, vec![11])]);
+        let mut unused = unused_libraries(&[1, 10, 11], &[10, 11], &depends_on);
+        unused.sort();
+        assert_eq!(unused, vec![10, 11]);
+    }
+
+    #[test]
+    fn test_dependency_kinds() {
+        let deps = dependency_kinds(
+            dirs(&["LibAddonMenu-2.0", "LibMapPins-1.0"]),
+            dirs(&["LibCustomMenu", "LibAddonMenu-2.0", "LibCustomMenu"]),
+        );
+        assert_eq!(
+            deps,
+            vec![
+                ("LibAddonMenu-2.0".to_string(), DependencyKind::Required),
+                ("LibMapPins-1.0".to_string(), DependencyKind::Required),
+                ("LibCustomMenu".to_string(), DependencyKind::Optional),
+            ]
+        );
+        assert!(dependency_kinds(vec![], vec![]).is_empty());
+    }
+}
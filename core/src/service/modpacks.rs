@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+
+use entity::addon as DbAddon;
+use entity::installed_addon::{self as InstalledAddon, InstallReason};
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use snafu::ResultExt;
+use tracing::log::info;
+
+use super::result::{AddonRef, InstallPlan, ModpackEntryStatus, ModpackReport};
+use super::{AddonService, installed_addon_details};
+use crate::error::{self, Result};
+use crate::modpack::{Modpack, PinMode, PinStatus};
+
+impl AddonService {
+    /// Writes the explicitly installed addons to `file` as a modpack, libraries are left
+    /// to dependency resolution.
+    pub fn export_modpack(
+        &self,
+        file: PathBuf,
+        name: String,
+        description: String,
+        pin: PinMode,
+    ) -> ImmediateValuePromise<()> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let explicit: Vec<i32> = InstalledAddon::Entity::find()
+                .filter(InstalledAddon::Column::Reason.eq(InstallReason::Explicit))
+                .all(&service.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|i| i.addon_id)
+                .collect();
+            let mut addons = installed_addon_details(&service.db).await?;
+            addons.retain(|a| explicit.contains(&a.id));
+            addons.sort_by_key(|a| a.name.to_lowercase());
+            let pack = Modpack::from_installed(name, description, &addons, pin);
+            fs::write(&file, pack.to_toml()?).context(error::ModpackWriteSnafu { path: &file })?;
+            info!(
+                "Exported modpack {} with {} addons to {}",
+                pack.name,
+                pack.addons.len(),
+                file.display()
+            );
+            Ok(())
+        })
+    }
+
+    /// Reads a modpack and compares it with the installed set, without changing anything.
+    pub fn check_modpack(&self, file: PathBuf) -> ImmediateValuePromise<ModpackReport> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let toml =
+                fs::read_to_string(&file).context(error::ModpackReadSnafu { path: &file })?;
+            let report = service.p_check_modpack(Modpack::from_toml(&toml)?).await?;
+            Ok(report)
+        })
+    }
+
+    /// Installs the missing entries, with the optional ones if `optional`, and updates
+    /// the older ones whose catalog version satisfies their pin. Dependencies come along.
+    pub fn apply_modpack(
+        &self,
+        report: ModpackReport,
+        optional: bool,
+    ) -> ImmediateValuePromise<InstallPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let ids = report
+                .missing
+                .iter()
+                .filter(|m| optional || !m.entry.optional)
+                .chain(report.older.iter().filter(|o| o.available_satisfies))
+                .map(|s| s.addon.id)
+                .collect();
+            let install = service.p_resolve_install_plan(ids).await?;
+            let mut done = install.clone();
+            done.steps.iter_mut().for_each(|step| step.archive = None);
+            service.p_install_plan(install).await;
+            Ok(done)
+        })
+    }
+
+    async fn p_check_modpack(&self, pack: Modpack) -> Result<ModpackReport> {
+        let ids: Vec<i32> = pack.addons.iter().map(|a| a.id).collect();
+        let catalog: HashMap<i32, DbAddon::Model> = DbAddon::Entity::find()
+            .filter(DbAddon::Column::Id.is_in(ids))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|a| (a.id, a))
+            .collect();
+        let installed: HashMap<i32, InstalledAddon::Model> = InstalledAddon::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|i| (i.addon_id, i))
+            .collect();
+
+        let mut report = ModpackReport::default();
+        for entry in pack.addons.iter() {
+            let Some(addon) = catalog.get(&entry.id) else {
+                report.unavailable.push(entry.clone());
+                continue;
+            };
+            let installed_version = installed.get(&entry.id).map(|i| i.version.clone());
+            let status = ModpackEntryStatus {
+                entry: entry.clone(),
+                addon: AddonRef {
+                    id: addon.id,
+                    name: addon.name.clone(),
+                },
+                installed_version: installed_version.clone(),
+                available_version: addon.version.clone(),
+                available_satisfies: entry.check(&addon.version) == PinStatus::Satisfied,
+            };
+            match installed_version.map(|v| entry.check(&v)) {
+                None => report.missing.push(status),
+                Some(PinStatus::Satisfied) => report.matching += 1,
+                Some(PinStatus::Older) => report.older.push(status),
+                Some(PinStatus::Newer) => report.newer.push(status),
+            }
+        }
+
+        let extra: Vec<i32> = installed
+            .values()
+            .filter(|i| i.reason == InstallReason::Explicit)
+            .map(|i| i.addon_id)
+            .filter(|id| !pack.addons.iter().any(|a| a.id == *id))
+            .collect();
+        report.extra = DbAddon::Entity::find()
+            .filter(DbAddon::Column::Id.is_in(extra))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|a| AddonRef {
+                id: a.id,
+                name: a.name,
+            })
+            .collect();
+        report.extra.sort_by_key(|a| a.name.to_lowercase());
+        info!(
+            "Modpack {}: {} matching, {} missing, {} older, {} newer, {} extra, {} unavailable",
+            pack.name,
+            report.matching,
+            report.missing.len(),
+            report.older.len(),
+            report.newer.len(),
+            report.extra.len(),
+            report.unavailable.len()
+        );
+        report.pack = pack;
+        Ok(report)
+    }
+}
@@ -0,0 +1,282 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use entity::addon as DbAddon;
+use entity::addon_dependency::{self as AddonDep, DependencyKind};
+use entity::addon_dir as AddonDir;
+use entity::installed_addon::{self as InstalledAddon, InstallReason};
+use entity::manual_dependency as ManualDependency;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, QueryFilter, Statement};
+use snafu::{OptionExt, ResultExt};
+use tracing::log::info;
+use zip::ZipArchive;
+
+use super::fs_util::zip_read_addons;
+use super::result::{AddonRef, FailedStep, InstallPlan, InstallStep, UnresolvedDep};
+use super::{AddonService, dir_providers};
+use crate::error::{self, Result};
+
+/// An addon in the plan, its step is pushed once all of its dependencies are planned.
+struct Pending {
+    addon_id: i32,
+    reason: InstallReason,
+    required_by: Option<String>,
+    step: Option<InstallStep>,
+}
+
+impl AddonService {
+    /// Works out everything needed to install `addon_ids`, following the dependencies
+    /// of dependencies. Archives of addons that aren't installed are downloaded to read
+    /// their manifests and kept in the plan so [`Self::install_plan`] can reuse them.
+    pub fn resolve_install_plan(&self, addon_ids: Vec<i32>) -> ImmediateValuePromise<InstallPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let plan = service.p_resolve_install_plan(addon_ids).await?;
+            Ok(plan)
+        })
+    }
+
+    pub fn install_plan(&self, plan: InstallPlan) -> ImmediateValuePromise<()> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            service.p_install_plan(plan).await;
+            Ok(())
+        })
+    }
+
+    /// Installs every step, failures are recorded and don't stop the others. Returns
+    /// the steps that failed.
+    pub(super) async fn p_install_plan(&self, plan: InstallPlan) -> Vec<FailedStep> {
+        let mut failed = vec![];
+        for step in plan.steps {
+            let archive = step.archive.as_deref().map(|path| path.as_ref());
+            if let Err(e) = self
+                .p_install_from(step.addon.id, false, step.reason, archive)
+                .await
+            {
+                self.record_error(
+                    format!("Error installing {} (#{})", step.addon.name, step.addon.id),
+                    &e,
+                );
+                failed.push(FailedStep {
+                    addon_id: step.addon.id,
+                    name: Some(step.addon.name),
+                    required_by: None,
+                    error: e.to_string(),
+                });
+            }
+        }
+        failed
+    }
+
+    pub(super) async fn p_resolve_install_plan(&self, addon_ids: Vec<i32>) -> Result<InstallPlan> {
+        let installed: HashSet<i32> = InstalledAddon::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|i| i.addon_id)
+            .collect();
+        // dirs that are already on disk or will be once the plan is installed
+        let mut provided: HashSet<String> = self
+            .db
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                r#"select distinct ad.dir
+                from installed_addon i
+                inner join addon_dir ad on ad.addon_id = i.addon_id"#,
+            ))
+            .await
+            .context(error::DbGetSnafu)?
+            .iter()
+            .map(|row| row.try_get_by(0).expect("query selects dir as text"))
+            .collect();
+        let manual: HashMap<String, ManualDependency::Model> = ManualDependency::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|m| (m.addon_dir.clone(), m))
+            .collect();
+
+        let mut plan = InstallPlan::default();
+        let mut visited = HashSet::new();
+        let mut stack: Vec<Pending> = addon_ids
+            .into_iter()
+            .rev()
+            .map(|addon_id| Pending {
+                addon_id,
+                reason: InstallReason::Explicit,
+                required_by: None,
+                step: None,
+            })
+            .collect();
+
+        while let Some(mut pending) = stack.pop() {
+            if let Some(step) = pending.step.take() {
+                plan.steps.push(step);
+                continue;
+            }
+            // already planned, or a dependency cycle back to an addon being planned
+            if !visited.insert(pending.addon_id) {
+                continue;
+            }
+
+            // one addon failing leaves the rest of the plan to install
+            let (step, depends_on) = match self.plan_step(&pending, &installed, &mut provided).await
+            {
+                Ok(planned) => planned,
+                Err(e) => {
+                    let name = DbAddon::Entity::find_by_id(pending.addon_id)
+                        .one(&self.db)
+                        .await
+                        .ok()
+                        .flatten()
+                        .map(|a| a.name);
+                    let failed = FailedStep {
+                        addon_id: pending.addon_id,
+                        name,
+                        required_by: pending.required_by,
+                        error: e.to_string(),
+                    };
+                    self.record_error(format!("Error resolving {}", failed.label()), &failed.error);
+                    plan.failed.push(failed);
+                    continue;
+                }
+            };
+            let name = step.addon.name.clone();
+            pending.step = Some(step);
+            stack.push(pending);
+
+            for dir in depends_on {
+                if provided.contains(&dir) || plan.unresolved.iter().any(|u| u.dir == dir) {
+                    continue;
+                }
+                let candidate = match manual.get(&dir) {
+                    Some(m) if m.ignore.unwrap_or(false) => continue,
+                    Some(ManualDependency::Model {
+                        satisfied_by: Some(satisfied_by),
+                        ..
+                    }) => Some(*satisfied_by),
+                    _ => dir_providers(&self.db, std::slice::from_ref(&dir))
+                        .await?
+                        .remove(&dir)
+                        .and_then(|providers| providers.into_iter().next())
+                        .map(|provider| provider.id),
+                };
+                let Some(candidate) = candidate else {
+                    plan.unresolved.push(UnresolvedDep {
+                        dir,
+                        required_by: name.clone(),
+                    });
+                    continue;
+                };
+                provided.insert(dir);
+                if installed.contains(&candidate) || visited.contains(&candidate) {
+                    continue;
+                }
+                // claim everything the candidate ships so later dirs don't pick another provider
+                provided.extend(
+                    AddonDir::Entity::find()
+                        .filter(AddonDir::Column::AddonId.eq(candidate))
+                        .all(&self.db)
+                        .await
+                        .context(error::DbGetSnafu)?
+                        .into_iter()
+                        .map(|d| d.dir),
+                );
+                stack.push(Pending {
+                    addon_id: candidate,
+                    reason: InstallReason::Dependency,
+                    required_by: Some(name.clone()),
+                    step: None,
+                });
+            }
+        }
+
+        info!(
+            "Install plan: {} addons, {} unresolved dependencies, {} failed",
+            plan.steps.len(),
+            plan.unresolved.len(),
+            plan.failed.len()
+        );
+        Ok(plan)
+    }
+
+    /// Builds the step for one addon and returns the dirs it requires. Installed addons
+    /// use their recorded dependencies, others are downloaded to read their manifests.
+    async fn plan_step(
+        &self,
+        pending: &Pending,
+        installed: &HashSet<i32>,
+        provided: &mut HashSet<String>,
+    ) -> Result<(InstallStep, Vec<String>)> {
+        let addon_id = pending.addon_id;
+        let is_installed = installed.contains(&addon_id);
+        if !is_installed {
+            self.p_update_addon_details(addon_id).await?;
+        }
+        let entry = DbAddon::Entity::find_by_id(addon_id)
+            .one(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .context(error::AddonNotFoundSnafu { id: addon_id })?;
+
+        let mut depends_on = vec![];
+        let mut archive = None;
+        if is_installed {
+            depends_on = AddonDep::Entity::find()
+                .filter(AddonDep::Column::AddonId.eq(addon_id))
+                .filter(AddonDep::Column::Kind.eq(DependencyKind::Required))
+                .all(&self.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|d| d.dependency_dir)
+                .collect();
+        } else {
+            let download = entry
+                .download
+                .clone()
+                .context(error::AddonMissingDownloadUrlSnafu { id: addon_id })?;
+            let tmpfile = self.fs_download(&download, entry.md5.clone()).await?;
+            let file = tmpfile
+                .reopen()
+                .context(error::AddonDownloadTmpFileReadSnafu)?;
+            let mut zip = ZipArchive::new(file).context(error::AddonDownloadZipCreateSnafu)?;
+            let addons = zip_read_addons(&mut zip)?;
+            for addon in addons.iter() {
+                for dir in addon.depends_on.iter() {
+                    if !depends_on.contains(dir) {
+                        depends_on.push(dir.to_owned());
+                    }
+                }
+            }
+            // bundled dirs satisfy each other
+            provided.extend(addons.into_iter().map(|a| a.name));
+            archive = Some(Arc::new(tmpfile.into_temp_path()));
+        }
+        provided.extend(
+            AddonDir::Entity::find()
+                .filter(AddonDir::Column::AddonId.eq(addon_id))
+                .all(&self.db)
+                .await
+                .context(error::DbGetSnafu)?
+                .into_iter()
+                .map(|d| d.dir),
+        );
+
+        let step = InstallStep {
+            addon: AddonRef {
+                id: addon_id,
+                name: entry.name,
+            },
+            version: entry.version,
+            reason: pending.reason,
+            required_by: pending.required_by.clone(),
+            archive,
+        };
+        Ok((step, depends_on))
+    }
+}
@@ -1,11 +1,18 @@
 use chrono::{DateTime, Utc};
 use entity::addon as DbAddon;
 use entity::category::Model as Category;
+use entity::installed_addon::InstallReason;
 use sea_orm::FromQueryResult;
 use serde_derive::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+use tempfile::TempPath;
 
-#[derive(Debug, Clone)]
+use super::backup::BackupManualDependency;
+use crate::modpack::{Modpack, ModpackAddon};
+
+#[derive(Debug, Clone, Serialize)]
 pub struct ErrorRecord {
     pub timestamp: DateTime<Utc>,
     pub context: String,
@@ -14,7 +21,7 @@ pub struct ErrorRecord {
 
 pub type AddonMap = HashMap<i32, String>;
 
-#[derive(FromQueryResult, Clone)]
+#[derive(FromQueryResult, Clone, Serialize)]
 pub struct AddonDepOption {
     pub missing_dir: String,
     pub required_by: String,
@@ -22,13 +29,14 @@ pub struct AddonDepOption {
     pub option_name: Option<String>,
 }
 
-#[derive(FromQueryResult, Clone, Default, Debug)]
+#[derive(FromQueryResult, Clone, Default, Debug, Serialize)]
 pub struct AddonRef {
     pub id: i32,
     pub name: String,
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "status", rename_all = "snake_case")]
 pub enum Resolution {
     Installed(AddonRef),
     SatisfiedBy(AddonRef),
@@ -36,16 +44,20 @@ pub enum Resolution {
     Unresolved { suggestions: Vec<AddonRef> },
 }
 
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize)]
 pub struct DepStatus {
     pub dep_dir: String,
     pub resolution: Resolution,
 }
 
-#[derive(Clone, Default, Debug)]
+#[derive(Clone, Default, Debug, Serialize)]
 pub struct AddonDependencyView {
     pub forward: Vec<DepStatus>,
+    /// `## OptionalDependsOn:` entries, never required for the addon to load
+    pub optional: Vec<DepStatus>,
     pub dependents: Vec<AddonRef>,
+    /// Every installed addon, to pick overrides from
+    #[serde(skip)]
     pub installed_addons: Vec<AddonRef>,
 }
 
@@ -56,6 +68,8 @@ pub struct MissingDepView {
     pub options: HashMap<i32, String>,
     pub ignore: bool,
     pub satisfied_by: Option<i32>,
+    /// Offered from `## OptionalDependsOn:`, left alone unless the user opts in
+    pub optional: bool,
 }
 impl MissingDepView {
     pub fn new(required_by: String) -> Self {
@@ -66,6 +80,416 @@ impl MissingDepView {
     }
 }
 
+#[derive(Clone, Debug)]
+pub struct InstallStep {
+    pub addon: AddonRef,
+    pub version: String,
+    pub reason: InstallReason,
+    /// Addon that pulled this one in, `None` for the requested addons
+    pub required_by: Option<String>,
+    /// Archive downloaded while resolving, installing extracts it instead of downloading again
+    pub archive: Option<Arc<TempPath>>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct UnresolvedDep {
+    pub dir: String,
+    pub required_by: String,
+}
+
+/// An addon that couldn't be planned or installed, e.g. because its download failed.
+/// The addons requiring it are planned anyway.
+#[derive(Clone, Debug, Serialize)]
+pub struct FailedStep {
+    pub addon_id: i32,
+    /// `None` when the addon isn't in the catalog
+    pub name: Option<String>,
+    /// Addon that pulled this one in, `None` for the requested addons
+    pub required_by: Option<String>,
+    pub error: String,
+}
+impl FailedStep {
+    pub fn label(&self) -> String {
+        match &self.name {
+            Some(name) => name.clone(),
+            None => format!("#{}", self.addon_id),
+        }
+    }
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct InstallPlan {
+    /// Install order, dependencies always come before the addons that need them
+    pub steps: Vec<InstallStep>,
+    /// Required dirs that no addon in the catalog provides
+    pub unresolved: Vec<UnresolvedDep>,
+    /// Addons left out of `steps` because planning them failed
+    pub failed: Vec<FailedStep>,
+}
+impl InstallPlan {
+    /// Addons the plan was worked out for, the failed ones included
+    pub fn addon_count(&self) -> usize {
+        self.steps.len() + self.failed.len()
+    }
+}
+
+/// A list entry that matched a catalog addon.
+#[derive(Clone, Debug, Serialize)]
+pub struct ImportedAddon {
+    /// The entry as written in the list
+    pub source: String,
+    pub addon: AddonRef,
+    pub installed: bool,
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct ImportPlan {
+    /// Matched addons in list order, without duplicates
+    pub addons: Vec<ImportedAddon>,
+    /// Entries that matched no catalog addon
+    pub unresolved: Vec<String>,
+}
+impl ImportPlan {
+    pub fn to_install(&self) -> impl Iterator<Item = &ImportedAddon> {
+        self.addons.iter().filter(|a| !a.installed)
+    }
+}
+
+/// A modpack entry along with what's installed and what the catalog offers.
+#[derive(Clone, Debug, Serialize)]
+pub struct ModpackEntryStatus {
+    pub entry: ModpackAddon,
+    pub addon: AddonRef,
+    pub installed_version: Option<String>,
+    pub available_version: String,
+    /// Installing the catalog version would satisfy the entry's pin
+    pub available_satisfies: bool,
+}
+
+/// How the installed set differs from a modpack.
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct ModpackReport {
+    pub pack: Modpack,
+    /// Entries that aren't installed
+    pub missing: Vec<ModpackEntryStatus>,
+    /// Installed below the pinned version
+    pub older: Vec<ModpackEntryStatus>,
+    /// Installed above an exact pin
+    pub newer: Vec<ModpackEntryStatus>,
+    /// Explicitly installed addons the modpack doesn't list
+    pub extra: Vec<AddonRef>,
+    /// Entries missing from the catalog
+    pub unavailable: Vec<ModpackAddon>,
+    /// Entries installed as pinned
+    pub matching: usize,
+}
+impl ModpackReport {
+    pub fn has_drift(&self) -> bool {
+        !self.missing.iter().all(|m| m.entry.optional)
+            || !self.older.is_empty()
+            || !self.newer.is_empty()
+            || !self.extra.is_empty()
+            || !self.unavailable.is_empty()
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct SyncUpdate {
+    pub addon: AddonRef,
+    pub installed_version: String,
+    pub available_version: String,
+}
+
+/// What syncing the installed set with a desired state changes.
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct SyncPlan {
+    /// Listed addons that aren't installed
+    pub install: Vec<AddonRef>,
+    /// Listed addons with a newer version in the catalog
+    pub update: Vec<SyncUpdate>,
+    /// Unlisted addons, only filled in when removing them was asked for
+    pub remove: Vec<AddonRef>,
+    /// Entries that matched no catalog addon
+    pub unresolved: Vec<String>,
+    /// Listed addons installed and up to date
+    pub unchanged: usize,
+    /// Every listed addon, libraries among them survive the cleanup after removing
+    pub listed: Vec<i32>,
+    /// Listed with `dependency = true`
+    pub dependencies: Vec<i32>,
+}
+impl SyncPlan {
+    pub fn is_empty(&self) -> bool {
+        self.install.is_empty() && self.update.is_empty() && self.remove.is_empty()
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct SyncReport {
+    /// Installed or updated, with the libraries they required
+    pub installed: Vec<AddonRef>,
+    /// Unlisted addons and the libraries only they required
+    pub removed: Vec<AddonRef>,
+    /// Addons that couldn't be resolved or installed, the others are synced anyway
+    pub failed: Vec<FailedStep>,
+}
+
+/// A file an operation would download.
+#[derive(Clone, Debug, Serialize)]
+pub struct PlannedDownload {
+    /// What the file is, e.g. the addon name
+    pub label: String,
+    pub url: String,
+    /// As reported by the server, `None` if it didn't say or can't be asked
+    pub size: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DbAction {
+    Insert,
+    Update,
+    Delete,
+}
+impl DbAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            DbAction::Insert => "insert",
+            DbAction::Update => "update",
+            DbAction::Delete => "delete",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct DbChange {
+    pub table: &'static str,
+    pub action: DbAction,
+    /// The rows changed, e.g. `addon_id = 7`
+    pub rows: String,
+}
+
+/// Side effects of a mutating operation, worked out without any of them happening.
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct ChangePlan {
+    pub downloads: Vec<PlannedDownload>,
+    pub dirs_written: Vec<PathBuf>,
+    pub dirs_deleted: Vec<PathBuf>,
+    pub files_written: Vec<PathBuf>,
+    pub db_changes: Vec<DbChange>,
+}
+impl ChangePlan {
+    pub fn is_empty(&self) -> bool {
+        self.downloads.is_empty()
+            && self.dirs_written.is_empty()
+            && self.dirs_deleted.is_empty()
+            && self.files_written.is_empty()
+            && self.db_changes.is_empty()
+    }
+
+    /// Known download sizes added up, along with whether any size is missing
+    pub fn download_size(&self) -> (u64, bool) {
+        let total = self.downloads.iter().filter_map(|d| d.size).sum();
+        (total, self.downloads.iter().any(|d| d.size.is_none()))
+    }
+
+    pub fn extend(&mut self, other: ChangePlan) {
+        self.downloads.extend(other.downloads);
+        self.dirs_written.extend(other.dirs_written);
+        self.dirs_deleted.extend(other.dirs_deleted);
+        self.files_written.extend(other.files_written);
+        self.db_changes.extend(other.db_changes);
+    }
+
+    pub(crate) fn db(&mut self, table: &'static str, action: DbAction, rows: String) {
+        self.db_changes.push(DbChange {
+            table,
+            action,
+            rows,
+        });
+    }
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct RemovalPlan {
+    pub addon: AddonRef,
+    /// Installed addons requiring a dir that only this addon provides
+    pub dependents: Vec<AddonRef>,
+    /// `dependents` and everything depending on them, in removal order
+    pub cascade: Vec<AddonRef>,
+}
+
+#[derive(Clone, Debug)]
+pub struct RestoreEntry {
+    pub addon: AddonRef,
+    /// Version recorded in the backup
+    pub version: String,
+    /// Version ESOUI serves now, empty if the addon is unavailable
+    pub available_version: String,
+    pub reason: InstallReason,
+    pub date: String,
+}
+
+#[derive(Clone, Default, Debug)]
+pub struct RestorePlan {
+    /// In the catalog but missing from the AddOns folder
+    pub to_install: Vec<RestoreEntry>,
+    /// Every dir of the addon is already in the AddOns folder
+    pub present: Vec<RestoreEntry>,
+    /// No longer listed on ESOUI
+    pub unavailable: Vec<RestoreEntry>,
+    pub manual_dependencies: Vec<BackupManualDependency>,
+    /// Set when the backup is an archive that also holds game files
+    pub archive: Option<PathBuf>,
+    /// Game files in the archive that differ from the ones on disk
+    pub files: Vec<RestoreFile>,
+    /// Game files in the archive identical to the ones on disk
+    pub unchanged_files: usize,
+}
+
+/// A game file stored in a backup archive.
+#[derive(Clone, Debug)]
+pub struct RestoreFile {
+    /// Relative to the folder containing AddOns, e.g. `SavedVariables/LibAddonMenu.lua`
+    pub path: PathBuf,
+    /// A different version of the file is on disk
+    pub conflict: bool,
+    /// Replace the file on disk, keeping it as `.bak`. Files without a conflict are always written.
+    pub overwrite: bool,
+}
+
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct RestoreReport {
+    pub installed: Vec<AddonRef>,
+    /// Addons that failed to install, with the error
+    pub failed: Vec<(AddonRef, String)>,
+    pub present: Vec<AddonRef>,
+    pub unavailable: Vec<AddonRef>,
+    pub files_restored: usize,
+    /// Conflicting files left as they were
+    pub files_skipped: usize,
+}
+
+#[derive(Clone, Copy, Default, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SnapshotTrigger {
+    #[default]
+    Manual,
+    Scheduled,
+    /// Taken before updating several addons at once
+    Update,
+}
+impl SnapshotTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SnapshotTrigger::Manual => "manual",
+            SnapshotTrigger::Scheduled => "scheduled",
+            SnapshotTrigger::Update => "update",
+        }
+    }
+    pub fn parse(s: &str) -> Option<Self> {
+        match s {
+            "manual" => Some(SnapshotTrigger::Manual),
+            "scheduled" => Some(SnapshotTrigger::Scheduled),
+            "update" => Some(SnapshotTrigger::Update),
+            _ => None,
+        }
+    }
+}
+
+/// A backup archive in the managed backup dir.
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct BackupSnapshot {
+    pub path: PathBuf,
+    pub created: DateTime<Utc>,
+    pub trigger: SnapshotTrigger,
+    pub addons: usize,
+    /// Archive size in bytes
+    pub size: u64,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct VersionChange {
+    pub addon: AddonRef,
+    pub backup_version: String,
+    pub installed_version: String,
+}
+
+/// Differences between a backup and what is installed now.
+#[derive(Clone, Default, Debug, Serialize)]
+pub struct BackupDiff {
+    /// In the backup but no longer installed
+    pub removed: Vec<AddonRef>,
+    /// Installed since the backup was made
+    pub added: Vec<AddonRef>,
+    pub changed: Vec<VersionChange>,
+}
+
+/// SavedVariables of an addon, copied before it was updated.
+#[derive(Clone, Default, Debug, Serialize, Deserialize)]
+pub struct SettingsSnapshot {
+    /// Folder holding the copies
+    #[serde(skip)]
+    pub path: PathBuf,
+    pub addon_id: i32,
+    /// Version installed when the snapshot was taken
+    pub version: String,
+    pub created: DateTime<Utc>,
+    /// File names in the SavedVariables folder
+    pub files: Vec<String>,
+}
+
+/// A file in the SavedVariables folder and the addon declaring it.
+#[derive(Clone, Debug, Serialize)]
+pub struct SavedVariablesFile {
+    /// File name in the SavedVariables folder
+    pub file: String,
+    pub size: u64,
+    /// Addon dir declaring `## SavedVariables:`, `None` once that addon is gone
+    pub owner: Option<String>,
+    /// Managed addon providing the owner dir
+    pub addon: Option<AddonRef>,
+}
+impl SavedVariablesFile {
+    pub fn is_orphan(&self) -> bool {
+        self.owner.is_none()
+    }
+}
+
+/// A top-level dir in the AddOns folder that no installed addon owns.
+#[derive(Clone, Debug, Serialize)]
+pub struct UntrackedDir {
+    pub dir: String,
+    pub path: PathBuf,
+    /// Total size of the files in the dir
+    pub size: u64,
+    /// Catalog addons shipping the dir, best candidate first
+    pub providers: Vec<AddonRef>,
+}
+
+/// One manifest of an addon dir, either top-level or bundled inside another addon.
+#[derive(Clone, Debug)]
+pub struct LibraryCopy {
+    /// Relative to the AddOns folder
+    pub path: PathBuf,
+    pub title: String,
+    pub addon_version: Option<u32>,
+    pub version: Option<String>,
+    pub nested: bool,
+}
+
+#[derive(Clone, Debug)]
+pub struct ShadowedLibrary {
+    pub dir: String,
+    /// Copy with the highest `AddOnVersion`, the one the game loads
+    pub loaded: LibraryCopy,
+    /// Copies with a lower `AddOnVersion` than `loaded`
+    pub stale: Vec<LibraryCopy>,
+    /// Catalog addon shipping the library on its own
+    pub standalone: Option<AddonRef>,
+    pub standalone_installed: bool,
+}
+
 #[derive(Serialize, Deserialize, Debug)]
 pub struct SearchDbAddon {
     pub id: i32,
@@ -86,7 +510,7 @@ impl From<&DbAddon::Model> for SearchDbAddon {
     }
 }
 
-#[derive(FromQueryResult, Default, Deserialize, Clone)]
+#[derive(FromQueryResult, Default, Deserialize, Clone, Serialize)]
 pub struct AddonDetails {
     pub id: i32,
     pub category_id: String,
@@ -95,7 +519,7 @@ pub struct AddonDetails {
     pub installed: bool,
 }
 
-#[derive(FromQueryResult, Clone, Default, Debug)]
+#[derive(FromQueryResult, Clone, Default, Debug, Serialize)]
 pub struct AddonShowDetails {
     pub id: i32,
     pub name: String,
@@ -130,7 +554,61 @@ impl AddonShowDetails {
     }
 }
 
-#[derive(Default)]
+/// A catalog addon matching a full-text search.
+#[derive(Clone, Debug, Serialize)]
+pub struct SearchResult {
+    #[serde(flatten)]
+    pub addon: AddonShowDetails,
+    /// Where the search matched, when it wasn't the name, author or category
+    pub snippet: Option<Snippet>,
+}
+
+/// A catalog addon shipping a dir, see [`super::AddonService::find_dir_providers`].
+#[derive(Clone, Debug, Serialize)]
+pub struct DirProvider {
+    #[serde(flatten)]
+    pub addon: AddonShowDetails,
+    /// The dir it ships that matched
+    pub dir: String,
+}
+
+/// A page of [`super::search::AddonQuery`] results.
+#[derive(Clone, Debug, Default, Serialize)]
+pub struct AddonPage {
+    /// Matches on every page
+    pub total: u64,
+    pub results: Vec<SearchResult>,
+}
+
+/// An excerpt of an addon's dirs, description or change log around the matched words.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Snippet {
+    /// `dirs`, `description` or `change_log`
+    pub field: String,
+    pub text: String,
+    /// Byte ranges of `text` that matched
+    pub highlights: Vec<(usize, usize)>,
+}
+impl Snippet {
+    /// `text` split into parts, `true` for the matched ones.
+    pub fn parts(&self) -> Vec<(&str, bool)> {
+        let mut parts = vec![];
+        let mut end = 0;
+        for &(start, stop) in self.highlights.iter() {
+            if start > end {
+                parts.push((&self.text[end..start], false));
+            }
+            parts.push((&self.text[start..stop], true));
+            end = stop;
+        }
+        if end < self.text.len() {
+            parts.push((&self.text[end..], false));
+        }
+        parts
+    }
+}
+
+#[derive(Default, Serialize)]
 pub struct UpdateResult {
     pub addons_updated: Vec<AddonDetails>,
 }
@@ -0,0 +1,385 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use chrono::Utc;
+use entity::addon as DbAddon;
+use entity::addon_dir as AddonDir;
+use entity::installed_addon as InstalledAddon;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::{ColumnTrait, EntityTrait, QueryFilter};
+use snafu::ResultExt;
+use tracing::log::{info, warn};
+
+use super::backup::{BACKUP_FORMAT_VERSION, BackupData, SAVED_VARIABLES_DIR};
+use super::fs_util::{
+    fs_backup_path, fs_find_manifest_dirs, fs_saved_variables_files, fs_scan_saved_variables,
+    fs_write_backup_archive,
+};
+use super::result::{AddonRef, SavedVariablesFile, SettingsSnapshot};
+use super::snapshots::{fs_reserve_path, snapshot_time};
+use super::{AddonService, resolve_dirs_to_addons};
+use crate::config::Config;
+use crate::error::{self, Result};
+
+/// Settings snapshots kept per addon, older ones are pruned
+const SETTINGS_SNAPSHOT_KEEP: usize = 5;
+const SNAPSHOT_MANIFEST: &str = "snapshot.json";
+/// Archives of cleaned up SavedVariables, next to the backups but never pruned
+const CLEANUP_PREFIX: &str = "savedvars-";
+
+impl AddonService {
+    /// Settings snapshots taken before updates of `addon_id`, newest first.
+    pub fn get_settings_snapshots(
+        &self,
+        addon_id: i32,
+    ) -> ImmediateValuePromise<Vec<SettingsSnapshot>> {
+        ImmediateValuePromise::new(async move {
+            let snapshots = fs_list_settings_snapshots(
+                &Config::default_settings_snapshot_dir().join(addon_id.to_string()),
+            );
+            Ok(snapshots)
+        })
+    }
+
+    /// Copies the snapshot's files back into SavedVariables, keeping the current ones
+    /// as `.bak`. The game overwrites SavedVariables on logout, so it should be closed.
+    pub fn restore_settings_snapshot(
+        &self,
+        snapshot: SettingsSnapshot,
+    ) -> ImmediateValuePromise<usize> {
+        let saved_vars = self.get_game_dir().join(SAVED_VARIABLES_DIR);
+        ImmediateValuePromise::new(async move {
+            fs_restore_settings_snapshot(&saved_vars, &snapshot)?;
+            info!(
+                "Restored {} settings files of addon {} from {}",
+                snapshot.files.len(),
+                snapshot.addon_id,
+                snapshot.created
+            );
+            Ok(snapshot.files.len())
+        })
+    }
+
+    /// Sizes of the SavedVariables files, largest first, with the addon they belong to.
+    /// Files of addons that are no longer in the AddOns folder are orphans.
+    pub fn scan_saved_variables(&self) -> ImmediateValuePromise<Vec<SavedVariablesFile>> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let files = service.p_scan_saved_variables().await?;
+            Ok(files)
+        })
+    }
+
+    /// Archives `files` from the SavedVariables folder into the backup dir, then deletes
+    /// them. The archive restores like any other backup. Returns its path.
+    pub fn clean_saved_variables(&self, files: Vec<String>) -> ImmediateValuePromise<PathBuf> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let archive = service.p_clean_saved_variables(&files).await?;
+            Ok(archive)
+        })
+    }
+
+    async fn p_scan_saved_variables(&self) -> Result<Vec<SavedVariablesFile>> {
+        let mut files = fs_scan_saved_variables(&self.config.addon_dir, &self.get_game_dir());
+        let dirs: Vec<String> = files.iter().filter_map(|f| f.owner.clone()).collect();
+        let managed = resolve_dirs_to_addons(&self.db, &dirs).await?;
+        let names: Vec<(i32, String)> = DbAddon::Entity::find()
+            .filter(DbAddon::Column::Id.is_in(managed.values().copied()))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|a| (a.id, a.name))
+            .collect();
+        for file in files.iter_mut() {
+            file.addon = file
+                .owner
+                .as_ref()
+                .and_then(|dir| managed.get(dir))
+                .and_then(|id| names.iter().find(|(n_id, _)| n_id == id))
+                .map(|(id, name)| AddonRef {
+                    id: *id,
+                    name: name.clone(),
+                });
+        }
+        Ok(files)
+    }
+
+    async fn p_clean_saved_variables(&self, files: &[String]) -> Result<PathBuf> {
+        let dir = Config::default_backup_dir();
+        fs::create_dir_all(&dir).context(error::BackupWriteSnafu { path: &dir })?;
+        let created = Utc::now();
+        let archive = fs_reserve_path(
+            created,
+            |created| dir.join(format!("{CLEANUP_PREFIX}{}.zip", snapshot_time(created))),
+            |path| File::create_new(path).map(|_| ()),
+        )?;
+        // only plain file names, nothing outside the SavedVariables folder
+        let paths: Vec<PathBuf> = files
+            .iter()
+            .filter(|f| Path::new(f).file_name().is_some_and(|n| n == f.as_str()))
+            .map(|f| Path::new(SAVED_VARIABLES_DIR).join(f))
+            .collect();
+        let backup_data = BackupData {
+            format_version: BACKUP_FORMAT_VERSION,
+            created: format!("{}", created.format("%Y-%m-%d %H:%M:%S UTC")),
+            config: None,
+            installed_addons: vec![],
+            manual_dependencies: vec![],
+        };
+        let manifest =
+            serde_json::to_vec_pretty(&backup_data).context(error::BackupSerializeSnafu)?;
+        let game_dir = self.get_game_dir();
+        if let Err(e) = fs_write_backup_archive(&archive, &manifest, &game_dir, &paths) {
+            let _ = fs::remove_file(&archive);
+            return Err(e);
+        }
+
+        for path in paths.iter() {
+            let path = game_dir.join(path);
+            fs::remove_file(&path).context(error::SavedVariablesDeleteSnafu { path: &path })?;
+        }
+        info!(
+            "Archived and deleted {} SavedVariables files to {}",
+            paths.len(),
+            archive.display()
+        );
+        Ok(archive)
+    }
+
+    /// Copies the SavedVariables of an installed addon aside before it's updated.
+    /// Returns `None` when the addon has no settings on disk.
+    pub(super) async fn p_snapshot_settings(
+        &self,
+        addon_id: i32,
+    ) -> Result<Option<SettingsSnapshot>> {
+        let Some(installed) = InstalledAddon::Entity::find_by_id(addon_id)
+            .one(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+        else {
+            return Ok(None);
+        };
+        let dirs: Vec<String> = AddonDir::Entity::find()
+            .filter(AddonDir::Column::AddonId.eq(addon_id))
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|d| d.dir)
+            .collect();
+        let game_dir = self.get_game_dir();
+        let manifest_dirs = fs_find_manifest_dirs(&self.config.addon_dir);
+        let files = fs_saved_variables_files(&manifest_dirs, &game_dir, &dirs);
+        if files.is_empty() {
+            return Ok(None);
+        }
+
+        let addon_snapshots = Config::default_settings_snapshot_dir().join(addon_id.to_string());
+        let snapshot = fs_write_settings_snapshot(
+            &addon_snapshots,
+            &game_dir,
+            SettingsSnapshot {
+                path: PathBuf::new(),
+                addon_id,
+                version: installed.version,
+                created: Utc::now(),
+                files,
+            },
+        )?;
+        info!(
+            "Saved {} settings files of addon {addon_id} to {}",
+            snapshot.files.len(),
+            snapshot.path.display()
+        );
+        fs_prune_settings_snapshots(&addon_snapshots);
+        Ok(Some(snapshot))
+    }
+}
+
+/// Copies the snapshot's `files` from the SavedVariables folder into a new folder under
+/// `addon_snapshots`, named after when it was created, and fills in its `path`. A name
+/// already taken moves on a millisecond.
+fn fs_write_settings_snapshot(
+    addon_snapshots: &Path,
+    game_dir: &Path,
+    mut snapshot: SettingsSnapshot,
+) -> Result<SettingsSnapshot> {
+    fs::create_dir_all(addon_snapshots).context(error::BackupWriteSnafu {
+        path: addon_snapshots,
+    })?;
+    let path = fs_reserve_path(
+        snapshot.created,
+        |created| addon_snapshots.join(snapshot_time(created)),
+        |path| fs::create_dir(path),
+    )?;
+    for file in snapshot.files.iter() {
+        let target = path.join(file);
+        fs::copy(game_dir.join(SAVED_VARIABLES_DIR).join(file), &target)
+            .context(error::BackupWriteSnafu { path: &target })?;
+    }
+    snapshot.path = path;
+    let manifest = snapshot.path.join(SNAPSHOT_MANIFEST);
+    fs::write(
+        &manifest,
+        serde_json::to_vec_pretty(&snapshot).context(error::BackupSerializeSnafu)?,
+    )
+    .context(error::BackupWriteSnafu { path: &manifest })?;
+    Ok(snapshot)
+}
+
+/// Removes the oldest snapshots of one addon beyond `SETTINGS_SNAPSHOT_KEEP`.
+fn fs_prune_settings_snapshots(addon_snapshots: &Path) {
+    for old in fs_list_settings_snapshots(addon_snapshots)
+        .iter()
+        .skip(SETTINGS_SNAPSHOT_KEEP)
+    {
+        if let Err(e) = fs::remove_dir_all(&old.path) {
+            warn!(
+                "Unable to prune settings snapshot {}: {e}",
+                old.path.display()
+            );
+        }
+    }
+}
+
+/// Copies the snapshot's files into `saved_vars`, renaming the current ones to `.bak`,
+/// or `.bak.N` when an earlier backup is there.
+fn fs_restore_settings_snapshot(saved_vars: &Path, snapshot: &SettingsSnapshot) -> Result<()> {
+    fs::create_dir_all(saved_vars).context(error::BackupWriteSnafu { path: saved_vars })?;
+    for file in snapshot.files.iter() {
+        let target = saved_vars.join(file);
+        if target.is_file() {
+            fs::rename(&target, fs_backup_path(&target))
+                .context(error::BackupWriteSnafu { path: &target })?;
+        }
+        fs::copy(snapshot.path.join(file), &target)
+            .context(error::BackupWriteSnafu { path: &target })?;
+    }
+    Ok(())
+}
+
+/// Reads the snapshots of one addon, newest first. Folders without a readable
+/// manifest are ignored.
+fn fs_list_settings_snapshots(dir: &Path) -> Vec<SettingsSnapshot> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return vec![];
+    };
+    let mut snapshots: Vec<SettingsSnapshot> = entries
+        .filter_map(|entry| entry.ok())
+        .filter_map(|entry| {
+            let path = entry.path();
+            let json = fs::read_to_string(path.join(SNAPSHOT_MANIFEST)).ok()?;
+            match serde_json::from_str::<SettingsSnapshot>(&json) {
+                Ok(snapshot) => Some(SettingsSnapshot { path, ..snapshot }),
+                Err(e) => {
+                    warn!("Skipping settings snapshot {}: {e}", path.display());
+                    None
+                }
+            }
+        })
+        .collect();
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created));
+    snapshots
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use chrono::Duration;
+
+    fn snapshot(created: chrono::DateTime<Utc>, files: &[&str]) -> SettingsSnapshot {
+        SettingsSnapshot {
+            path: PathBuf::new(),
+            addon_id: 7,
+            version: "1.0".to_string(),
+            created,
+            files: files.iter().map(|f| f.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_settings_snapshot_roundtrip() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let addon_snapshots = game_dir.path().join("snapshots");
+        let saved_vars = game_dir.path().join(SAVED_VARIABLES_DIR);
+        fs::create_dir_all(&saved_vars).unwrap();
+        fs::write(saved_vars.join("HarvestMapAD.lua"), "Old = 1").unwrap();
+
+        let written = fs_write_settings_snapshot(
+            &addon_snapshots,
+            game_dir.path(),
+            snapshot(Utc::now(), &["HarvestMapAD.lua"]),
+        )
+        .unwrap();
+        let listed = fs_list_settings_snapshots(&addon_snapshots);
+        assert_eq!(listed.len(), 1);
+        assert_eq!(listed[0].path, written.path);
+        assert_eq!(listed[0].files, vec!["HarvestMapAD.lua"]);
+
+        fs::write(saved_vars.join("HarvestMapAD.lua"), "New = 2").unwrap();
+        fs_restore_settings_snapshot(&saved_vars, &listed[0]).unwrap();
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("HarvestMapAD.lua")).unwrap(),
+            "Old = 1"
+        );
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("HarvestMapAD.lua.bak")).unwrap(),
+            "New = 2"
+        );
+
+        // a second restore keeps the first backup
+        fs_restore_settings_snapshot(&saved_vars, &listed[0]).unwrap();
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("HarvestMapAD.lua.bak")).unwrap(),
+            "New = 2"
+        );
+        assert_eq!(
+            fs::read_to_string(saved_vars.join("HarvestMapAD.lua.bak.1")).unwrap(),
+            "Old = 1"
+        );
+    }
+
+    #[test]
+    fn test_settings_snapshots_same_time() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let addon_snapshots = game_dir.path().join("snapshots");
+        let created = Utc::now();
+        let first =
+            fs_write_settings_snapshot(&addon_snapshots, game_dir.path(), snapshot(created, &[]))
+                .unwrap();
+        let second =
+            fs_write_settings_snapshot(&addon_snapshots, game_dir.path(), snapshot(created, &[]))
+                .unwrap();
+        assert_ne!(first.path, second.path);
+        assert_eq!(fs_list_settings_snapshots(&addon_snapshots).len(), 2);
+    }
+
+    #[test]
+    fn test_prune_settings_snapshots() {
+        let game_dir = tempfile::tempdir().unwrap();
+        let addon_snapshots = game_dir.path().join("snapshots");
+        let start = Utc::now();
+        for i in 0..SETTINGS_SNAPSHOT_KEEP + 2 {
+            let created = start + Duration::seconds(i as i64);
+            fs_write_settings_snapshot(&addon_snapshots, game_dir.path(), snapshot(created, &[]))
+                .unwrap();
+        }
+
+        fs_prune_settings_snapshots(&addon_snapshots);
+
+        let kept = fs_list_settings_snapshots(&addon_snapshots);
+        assert_eq!(kept.len(), SETTINGS_SNAPSHOT_KEEP);
+        // the oldest two are gone
+        assert!(
+            kept.iter()
+                .all(|s| s.created >= start + Duration::seconds(2))
+        );
+        assert_eq!(
+            fs::read_dir(&addon_snapshots).unwrap().count(),
+            SETTINGS_SNAPSHOT_KEEP
+        );
+    }
+}
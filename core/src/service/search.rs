@@ -0,0 +1,689 @@
+use std::collections::{HashMap, HashSet};
+
+use chrono::NaiveDate;
+use entity::addon as DbAddon;
+use entity::category as Category;
+use entity::installed_addon as InstalledAddon;
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::sea_query::{Alias, Expr, Order, SimpleExpr};
+use sea_orm::{
+    ColumnTrait, ConnectionTrait, DbBackend, EntityTrait, FromQueryResult, JoinType,
+    PaginatorTrait, QueryFilter, QueryOrder, QuerySelect, QueryTrait, Statement, Value,
+};
+use snafu::ResultExt;
+
+use super::result::{AddonPage, AddonShowDetails, DirProvider, SearchResult, Snippet};
+use super::{AddonService, DIR_PROVIDER_RANKING, SQLITE_MAX_VARS};
+use crate::error::{self, Result};
+
+/// Marks matched words in snippets, never part of indexed text.
+const MATCH_START: char = '\u{2}';
+const MATCH_END: char = '\u{3}';
+
+/// Catalog addons to list with [`AddonService::query_addons`]. Filters left at their
+/// default don't filter, the ones set all have to match.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AddonQuery {
+    /// Words to search names, authors, categories, dirs, descriptions and change logs for
+    pub text: String,
+    /// Only this category and the categories below it
+    pub category: Option<i32>,
+    pub installed: InstalledFilter,
+    /// Only addons marked compatible with this game version, e.g. `10.3.5`
+    pub game_version: Option<String>,
+    pub min_downloads: Option<u64>,
+    pub min_favorites: Option<u64>,
+    /// Only addons updated on or after this day
+    pub updated_since: Option<NaiveDate>,
+    /// Part of the author's name, ignoring case
+    pub author: Option<String>,
+    /// Only addons shipping this dir, ignoring case
+    pub provides_dir: Option<String>,
+    pub sort: AddonSort,
+    /// Flips the sort's natural order
+    pub reverse: bool,
+    pub offset: u64,
+    /// Page size, `None` for every match
+    pub limit: Option<u64>,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum InstalledFilter {
+    #[default]
+    Any,
+    Installed,
+    NotInstalled,
+    /// Installed with a newer version in the catalog
+    Outdated,
+}
+
+/// Order of [`AddonQuery`] results, ties are broken by newest first.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum AddonSort {
+    /// Best match first, newest first without search text
+    #[default]
+    Relevance,
+    /// A to Z
+    Name,
+    /// A to Z
+    Author,
+    /// Newest first
+    Updated,
+    /// Most first
+    TotalDownloads,
+    /// Most first
+    MonthlyDownloads,
+    /// Most first
+    Favorites,
+    /// Lowest first
+    Id,
+}
+
+/// How [`AddonService::find_dir_providers`] compares dir names.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum DirMatch {
+    Exact,
+    IgnoreCase,
+    /// Also ignores punctuation and version suffixes and allows typos, closest first
+    #[default]
+    Fuzzy,
+}
+
+/// Most fuzzy dir matches to look up providers for.
+const MAX_FUZZY_DIRS: usize = 50;
+
+/// Weighs name and author matches the most and change log matches the least.
+const RANK: &str = "bm25(addon_search, 10.0, 5.0, 2.0, 5.0, 1.0, 0.5)";
+
+/// `addon_search` columns with snippets, with their position and alias.
+const SNIPPET_FIELDS: [(&str, usize, &str); 3] = [
+    ("dirs", 3, "snippet_dirs"),
+    ("description", 4, "snippet_description"),
+    ("change_log", 5, "snippet_change_log"),
+];
+
+/// A row of `addon_search`, read from the catalog tables.
+#[derive(FromQueryResult)]
+struct IndexRow {
+    id: i32,
+    name: String,
+    author: Option<String>,
+    category: Option<String>,
+    dirs: Option<String>,
+    description: Option<String>,
+    change_log: Option<String>,
+}
+
+impl AddonService {
+    /// Searches names, authors, categories, dirs and the cached descriptions and
+    /// change logs of catalog addons, best match first. Every word has to match,
+    /// as a word or the start of one. A blank search lists every addon, newest first.
+    pub fn search(&self, search_string: String) -> ImmediateValuePromise<Vec<SearchResult>> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
+            let query = AddonQuery {
+                text: search_string,
+                ..Default::default()
+            };
+            let page = p_query_addons(&db, &query).await?;
+            Ok(page.results)
+        })
+    }
+
+    /// Catalog addons shipping `dir`, for finding what provides a missing dependency.
+    /// Candidates for the same dir are ranked like
+    /// [`Self::get_missing_dependency_options`], fuzzy matches after closer ones.
+    pub fn find_dir_providers(
+        &self,
+        dir: String,
+        mode: DirMatch,
+    ) -> ImmediateValuePromise<Vec<DirProvider>> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
+            let providers = p_find_dir_providers(&db, dir.trim(), mode).await?;
+            Ok(providers)
+        })
+    }
+
+    /// One page of catalog addons matching `query`, filtered, sorted and paged by
+    /// the database.
+    pub fn query_addons(&self, query: AddonQuery) -> ImmediateValuePromise<AddonPage> {
+        let db = self.db.clone();
+        ImmediateValuePromise::new(async move {
+            let page = p_query_addons(&db, &query).await?;
+            Ok(page)
+        })
+    }
+}
+
+async fn p_query_addons<C: ConnectionTrait>(db: &C, query: &AddonQuery) -> Result<AddonPage> {
+    let fts = fts_query(&query.text);
+    let mut select = catalog_addons();
+    if let Some(fts) = &fts {
+        QueryTrait::query(&mut select).join(
+            JoinType::InnerJoin,
+            Alias::new("addon_search"),
+            Expr::cust("addon_search.rowid = addon.id"),
+        );
+        select = select.filter(Expr::cust_with_values(
+            "addon_search match ?",
+            [fts.clone()],
+        ));
+        for (_, column, alias) in SNIPPET_FIELDS {
+            select = select.column_as(
+                Expr::cust(format!(
+                    "snippet(addon_search, {column}, char(2), char(3), '…', 12)"
+                )),
+                alias,
+            );
+        }
+    }
+    if let Some(category) = query.category {
+        select = select.filter(Expr::cust_with_values(
+            r#"cast(addon.category_id as integer) in (
+                with recursive sub(id) as (
+                    select ?
+                    union
+                    select cp.id from category_parent cp join sub on cp.parent_id = sub.id
+                )
+                select id from sub
+            )"#,
+            [category],
+        ));
+    }
+    select = match query.installed {
+        InstalledFilter::Any => select,
+        InstalledFilter::Installed => select.filter(InstalledAddon::Column::AddonId.is_not_null()),
+        InstalledFilter::NotInstalled => select.filter(InstalledAddon::Column::AddonId.is_null()),
+        InstalledFilter::Outdated => select
+            .filter(InstalledAddon::Column::AddonId.is_not_null())
+            .filter(Expr::cust("installed_addon.version <> addon.version")),
+    };
+    if let Some(version) = &query.game_version {
+        select = select.filter(Expr::cust_with_values(
+            "exists (select 1 from game_compatibility g where g.addon_id = addon.id and g.version = ?)",
+            [version.clone()],
+        ));
+    }
+    if let Some(min) = query.min_downloads {
+        select = select.filter(Expr::expr(count_expr("download_total")).gte(min));
+    }
+    if let Some(min) = query.min_favorites {
+        select = select.filter(Expr::expr(count_expr("favorite_total")).gte(min));
+    }
+    if let Some(since) = query.updated_since {
+        // dates are stored as `2024-02-12 16:47:39 UTC`, so they compare as text
+        select = select.filter(DbAddon::Column::Date.gte(since.format("%Y-%m-%d").to_string()));
+    }
+    if let Some(author) = &query.author {
+        // instr rather than like, so `%` and `_` in names match themselves
+        select = select.filter(Expr::cust_with_values(
+            "instr(lower(addon.author_name), lower(?)) > 0",
+            [author.clone()],
+        ));
+    }
+    if let Some(dir) = &query.provides_dir {
+        select = select.filter(Expr::cust_with_values(
+            "exists (select 1 from addon_dir d where d.addon_id = addon.id and d.dir = ? collate nocase)",
+            [dir.clone()],
+        ));
+    }
+
+    let total = select.clone().count(db).await.context(error::DbGetSnafu)?;
+
+    let (order, natural) = match query.sort {
+        AddonSort::Relevance if fts.is_some() => (Expr::cust(RANK), Order::Asc),
+        AddonSort::Relevance | AddonSort::Updated => (
+            Expr::col((DbAddon::Entity, DbAddon::Column::Date)).into(),
+            Order::Desc,
+        ),
+        AddonSort::Name => (Expr::cust("addon.name collate nocase"), Order::Asc),
+        AddonSort::Author => (Expr::cust("addon.author_name collate nocase"), Order::Asc),
+        AddonSort::TotalDownloads => (count_expr("download_total"), Order::Desc),
+        AddonSort::MonthlyDownloads => (count_expr("download_monthly"), Order::Desc),
+        AddonSort::Favorites => (count_expr("favorite_total"), Order::Desc),
+        AddonSort::Id => (
+            Expr::col((DbAddon::Entity, DbAddon::Column::Id)).into(),
+            Order::Asc,
+        ),
+    };
+    select = select
+        .order_by(order, order_direction(natural, query.reverse))
+        .order_by_desc(DbAddon::Column::Date)
+        .order_by_asc(DbAddon::Column::Id)
+        // SQLite only takes an offset after a limit
+        .limit(query.limit.unwrap_or(i64::MAX as u64))
+        .offset(query.offset);
+
+    let rows = db
+        .query_all(select.build(DbBackend::Sqlite))
+        .await
+        .context(error::DbGetSnafu)?;
+    let mut results = vec![];
+    for row in rows {
+        let addon = AddonShowDetails::from_query_result(&row, "").context(error::DbGetSnafu)?;
+        let mut snippet = None;
+        if fts.is_some() {
+            for (field, _, alias) in SNIPPET_FIELDS {
+                let marked: Option<String> = row.try_get("", alias).context(error::DbGetSnafu)?;
+                snippet = marked.and_then(|marked| parse_snippet(field, &marked));
+                if snippet.is_some() {
+                    break;
+                }
+            }
+        }
+        results.push(SearchResult { addon, snippet });
+    }
+    Ok(AddonPage { total, results })
+}
+
+async fn p_find_dir_providers<C: ConnectionTrait>(
+    db: &C,
+    wanted: &str,
+    mode: DirMatch,
+) -> Result<Vec<DirProvider>> {
+    if wanted.is_empty() {
+        return Ok(vec![]);
+    }
+    // dirs to look up with how far they are from `wanted`
+    let mut dirs: Vec<(String, usize)> = if mode == DirMatch::Exact {
+        vec![(wanted.to_owned(), 0)]
+    } else {
+        let all: Vec<String> = db
+            .query_all(Statement::from_string(
+                DbBackend::Sqlite,
+                "select distinct dir from addon_dir",
+            ))
+            .await
+            .context(error::DbGetSnafu)?
+            .iter()
+            .map(|row| row.try_get("", "dir"))
+            .collect::<std::result::Result<_, _>>()
+            .context(error::DbGetSnafu)?;
+        all.into_iter()
+            .filter_map(|dir| {
+                let distance = match mode {
+                    DirMatch::IgnoreCase => dir.eq_ignore_ascii_case(wanted).then_some(0),
+                    _ => dir_distance(wanted, &dir),
+                }?;
+                Some((dir, distance))
+            })
+            .collect()
+    };
+    dirs.sort_by_key(|(_, distance)| *distance);
+    dirs.truncate(MAX_FUZZY_DIRS);
+
+    let mut providers = vec![];
+    for chunk in dirs.chunks(SQLITE_MAX_VARS) {
+        let placeholders = vec!["?"; chunk.len()].join(",");
+        let sql = format!(
+            r#"select a.id, a.name, coalesce(a.author_name, '') as author_name,
+                c.title as category, a.version, a.date,
+                i.addon_id is not null as installed, i.version as installed_version,
+                a.download_total, a.download_monthly, a.favorite_total,
+                coalesce(a.file_info_url, '') as file_info_url, a.download, a.file_name, a.md5,
+                null as description, null as change_log,
+                null as game_compat_version, null as game_compat_name,
+                c.icon as category_icon, ad.dir as matched_dir
+            from addon_dir ad
+            inner join addon a on a.id = ad.addon_id
+            inner join category c on c.id = a.category_id
+            left outer join installed_addon i on i.addon_id = a.id
+            left outer join (
+                select addon_id, count(*) as dir_count
+                from addon_dir
+                group by addon_id
+            ) dc on dc.addon_id = a.id
+            where ad.dir in ({placeholders})
+            order by {DIR_PROVIDER_RANKING}"#
+        );
+        let rows = db
+            .query_all(Statement::from_sql_and_values(
+                DbBackend::Sqlite,
+                sql,
+                chunk
+                    .iter()
+                    .map(|(dir, _)| dir.clone().into())
+                    .collect::<Vec<Value>>(),
+            ))
+            .await
+            .context(error::DbGetSnafu)?;
+        for row in rows {
+            providers.push(DirProvider {
+                addon: AddonShowDetails::from_query_result(&row, "").context(error::DbGetSnafu)?,
+                dir: row.try_get("", "matched_dir").context(error::DbGetSnafu)?,
+            });
+        }
+    }
+    // closest dirs first, keeping the ranking between addons shipping the same dir
+    let distances: HashMap<&str, usize> = dirs.iter().map(|(d, n)| (d.as_str(), *n)).collect();
+    providers.sort_by_key(|p| distances.get(p.dir.as_str()).copied().unwrap_or(usize::MAX));
+    let mut seen = HashSet::new();
+    providers.retain(|p| seen.insert(p.addon.id));
+    Ok(providers)
+}
+
+/// How far `dir` is from the `wanted` dir, `None` if it's too different to be the
+/// one meant. Equal names are 0, names differing in case 1, in punctuation or a
+/// version suffix 2, and after that typos and one name containing the other count.
+fn dir_distance(wanted: &str, dir: &str) -> Option<usize> {
+    if dir == wanted {
+        return Some(0);
+    }
+    if dir.eq_ignore_ascii_case(wanted) {
+        return Some(1);
+    }
+    let (wanted, dir) = (dir_key(wanted), dir_key(dir));
+    if wanted.is_empty() || dir.is_empty() {
+        return None;
+    }
+    if wanted == dir {
+        return Some(2);
+    }
+    let (short, long) = if wanted.len() <= dir.len() {
+        (&wanted, &dir)
+    } else {
+        (&dir, &wanted)
+    };
+    let typos = levenshtein(&wanted, &dir);
+    if typos <= (short.len() / 5).max(1) {
+        return Some(2 + typos);
+    }
+    // "LibMedia" for "LibMediaProvider", but not "Lib" for every library
+    if short.len() >= 4 && long.contains(short.as_str()) {
+        return Some(2 + long.len() - short.len());
+    }
+    None
+}
+
+/// `dir` in lower case without punctuation and version suffix,
+/// `LibMediaProvider-1.0` is `libmediaprovider`.
+fn dir_key(dir: &str) -> String {
+    dir.trim_end_matches(|c: char| c.is_ascii_digit() || matches!(c, '.' | '-' | '_' | ' '))
+        .chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let above = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                diagonal
+            } else {
+                1 + diagonal.min(above).min(row[j])
+            };
+            diagonal = above;
+        }
+    }
+    row[b.len()]
+}
+
+/// The direction to sort in, `reverse` flips the sort's natural one.
+fn order_direction(natural: Order, reverse: bool) -> Order {
+    match (natural, reverse) {
+        (Order::Asc, true) => Order::Desc,
+        (Order::Desc, true) => Order::Asc,
+        (order, _) => order,
+    }
+}
+
+/// A download or favorite count, stored as text.
+fn count_expr(column: &str) -> SimpleExpr {
+    Expr::cust(format!(
+        "cast(coalesce(nullif(addon.{column}, ''), '0') as integer)"
+    ))
+}
+
+/// Catalog addons with their installed version and category, without details.
+fn catalog_addons() -> sea_orm::Select<DbAddon::Entity> {
+    DbAddon::Entity::find()
+        .column_as(InstalledAddon::Column::Version, "installed_version")
+        .column_as(InstalledAddon::Column::AddonId.is_not_null(), "installed")
+        .column_as(Category::Column::Title, "category")
+        .column_as(Expr::value("NULL"), "description")
+        .column_as(Expr::value("NULL"), "change_log")
+        .column_as(Expr::value("NULL"), "game_compat_version")
+        .column_as(Expr::value("NULL"), "game_compat_name")
+        .column_as(Category::Column::Icon, "category_icon")
+        .inner_join(Category::Entity)
+        .left_join(InstalledAddon::Entity)
+}
+
+/// Re-indexes every catalog addon, after the catalog or the cached details changed.
+pub(super) async fn rebuild_search_index<C: ConnectionTrait>(db: &C) -> Result<()> {
+    db.execute_unprepared("delete from addon_search")
+        .await
+        .context(error::DbDeleteSnafu)?;
+    index_rows(db, None).await
+}
+
+/// Re-indexes one addon, after its details were downloaded.
+pub(super) async fn index_addon<C: ConnectionTrait>(db: &C, addon_id: i32) -> Result<()> {
+    db.execute(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        "delete from addon_search where rowid = ?",
+        [addon_id.into()],
+    ))
+    .await
+    .context(error::DbDeleteSnafu)?;
+    index_rows(db, Some(addon_id)).await
+}
+
+/// Indexes `addon_id`, or every addon, with descriptions and change logs as plain text.
+async fn index_rows<C: ConnectionTrait>(db: &C, addon_id: Option<i32>) -> Result<()> {
+    let filter = if addon_id.is_some() {
+        "where a.id = ?"
+    } else {
+        ""
+    };
+    let sql = format!(
+        r#"select a.id, a.name, a.author_name as author, c.title as category,
+            (select group_concat(dir, ' ') from addon_dir where addon_id = a.id) as dirs,
+            d.description, d.change_log
+        from addon a
+        left join category c on c.id = a.category_id
+        left join addon_detail d on d.id = a.id
+        {filter}"#
+    );
+    let rows = IndexRow::find_by_statement(Statement::from_sql_and_values(
+        DbBackend::Sqlite,
+        sql,
+        addon_id.map(Into::into),
+    ))
+    .all(db)
+    .await
+    .context(error::DbGetSnafu)?;
+
+    let plain = |text: Option<String>| text.map(|text| bbcode::parse(&text).plain_text());
+    for row in rows {
+        db.execute(Statement::from_sql_and_values(
+            DbBackend::Sqlite,
+            r#"insert into addon_search (rowid, name, author, category, dirs, description, change_log)
+            values (?, ?, ?, ?, ?, ?, ?)"#,
+            [
+                row.id.into(),
+                row.name.into(),
+                row.author.into(),
+                row.category.into(),
+                row.dirs.into(),
+                plain(row.description).into(),
+                plain(row.change_log).into(),
+            ],
+        ))
+        .await
+        .context(error::DbPutSnafu)?;
+    }
+    Ok(())
+}
+
+/// Turns what the user typed into an FTS5 query matching every word as a prefix,
+/// `None` if there's nothing to search for. Words are quoted so punctuation in
+/// names like `LibAddonMenu-2.0` isn't read as query syntax.
+fn fts_query(input: &str) -> Option<String> {
+    let terms: Vec<String> = input
+        .split_whitespace()
+        .filter(|word| word.chars().any(char::is_alphanumeric))
+        .map(|word| format!("\"{}\"*", word.replace('"', "\"\"")))
+        .collect();
+    (!terms.is_empty()).then(|| terms.join(" "))
+}
+
+/// Reads a `snippet()` with marked matches, `None` if nothing in it matched.
+fn parse_snippet(field: &str, marked: &str) -> Option<Snippet> {
+    if !marked.contains(MATCH_START) {
+        return None;
+    }
+    let mut snippet = Snippet {
+        field: field.to_owned(),
+        ..Default::default()
+    };
+    let mut start = None;
+    for c in marked.chars() {
+        match c {
+            MATCH_START => start = Some(snippet.text.len()),
+            MATCH_END => {
+                if let Some(start) = start.take() {
+                    snippet.highlights.push((start, snippet.text.len()));
+                }
+            }
+            // snippets are shown on a single line
+            c if c.is_whitespace() => {
+                if !snippet.text.ends_with(' ') {
+                    snippet.text.push(' ');
+                }
+            }
+            c => snippet.text.push(c),
+        }
+    }
+    Some(snippet)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use migration::{Migrator, MigratorTrait};
+    use sea_orm::{Database, DatabaseConnection};
+
+    /// Root > Child > Grandchild and Other, with an addon in Root, Grandchild and Other.
+    async fn catalog_db() -> DatabaseConnection {
+        let db = Database::connect("sqlite::memory:").await.unwrap();
+        Migrator::up(&db, None).await.unwrap();
+        for sql in [
+            "insert or replace into category (id, title) values
+                (9001, 'Root'), (9002, 'Child'), (9003, 'Grandchild'), (9004, 'Other')",
+            "insert into category_parent (id, parent_id) values (9002, 9001), (9003, 9002)",
+            "insert into addon
+                (id, category_id, version, date, name, author_name, file_info_url, download_total)
+                values
+                (1, '9001', '1', '2026-01-03 00:00:00 UTC', 'Root Addon', '100%_Sure', '', '1234'),
+                (2, '9003', '1', '2026-01-02 00:00:00 UTC', 'Nested Addon', '100 Sure', '', ''),
+                (3, '9004', '1', '2026-01-01 00:00:00 UTC', 'Other Addon', 'Someone', '', null)",
+        ] {
+            db.execute_unprepared(sql).await.unwrap();
+        }
+        db
+    }
+
+    async fn query_ids(db: &DatabaseConnection, query: AddonQuery) -> Vec<i32> {
+        let page = p_query_addons(db, &query).await.unwrap();
+        assert_eq!(page.total as usize, page.results.len());
+        page.results.iter().map(|r| r.addon.id).collect()
+    }
+
+    #[tokio::test]
+    async fn test_query_addons_filters() {
+        let db = catalog_db().await;
+        let category = |id| AddonQuery {
+            category: Some(id),
+            ..Default::default()
+        };
+        assert_eq!(query_ids(&db, category(9001)).await, vec![1, 2]);
+        assert_eq!(query_ids(&db, category(9002)).await, vec![2]);
+        assert_eq!(query_ids(&db, category(9004)).await, vec![3]);
+
+        let author = |name: &str| AddonQuery {
+            author: Some(name.to_owned()),
+            ..Default::default()
+        };
+        assert_eq!(query_ids(&db, author("100%_")).await, vec![1]);
+        assert_eq!(query_ids(&db, author("sure")).await, vec![1, 2]);
+
+        // empty and missing counts are 0
+        let downloads = |min| AddonQuery {
+            min_downloads: Some(min),
+            ..Default::default()
+        };
+        assert_eq!(query_ids(&db, downloads(1)).await, vec![1]);
+        assert_eq!(query_ids(&db, downloads(0)).await, vec![1, 2, 3]);
+        let by_downloads = AddonQuery {
+            sort: AddonSort::TotalDownloads,
+            ..Default::default()
+        };
+        assert_eq!(query_ids(&db, by_downloads.clone()).await, vec![1, 2, 3]);
+        let fewest_first = AddonQuery {
+            reverse: true,
+            ..by_downloads
+        };
+        // ties stay newest first
+        assert_eq!(query_ids(&db, fewest_first).await, vec![2, 3, 1]);
+    }
+
+    #[test]
+    fn test_order_direction() {
+        assert!(matches!(order_direction(Order::Asc, false), Order::Asc));
+        assert!(matches!(order_direction(Order::Asc, true), Order::Desc));
+        assert!(matches!(order_direction(Order::Desc, false), Order::Desc));
+        assert!(matches!(order_direction(Order::Desc, true), Order::Asc));
+    }
+
+    #[test]
+    fn test_fts_query() {
+        assert_eq!(
+            fts_query("crafting  writ").as_deref(),
+            Some(r#""crafting"* "writ"*"#)
+        );
+        assert_eq!(
+            fts_query(r#"LibAddonMenu-2.0 "x - "#).as_deref(),
+            Some(r#""LibAddonMenu-2.0"* """x"*"#)
+        );
+        assert_eq!(fts_query("  - "), None);
+    }
+
+    #[test]
+    fn test_dir_distance() {
+        let wanted = "LibMediaProvider-1.0";
+        assert_eq!(dir_distance(wanted, "LibMediaProvider-1.0"), Some(0));
+        assert_eq!(dir_distance(wanted, "libmediaprovider-1.0"), Some(1));
+        assert_eq!(dir_distance(wanted, "LibMediaProvider"), Some(2));
+        assert_eq!(dir_distance(wanted, "LibMediaProvidr"), Some(3));
+        assert!(dir_distance("LibMedia", "LibMediaProvider").is_some());
+        assert_eq!(dir_distance("Lib", "LibMediaProvider"), None);
+        assert_eq!(dir_distance(wanted, "LibAddonMenu-2.0"), None);
+    }
+
+    #[test]
+    fn test_levenshtein() {
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("same", "same"), 0);
+    }
+
+    #[test]
+    fn test_parse_snippet() {
+        let snippet = parse_snippet("description", "…turns in \u{2}writs\u{3}\nfor you").unwrap();
+        assert_eq!(snippet.text, "…turns in writs for you");
+        assert_eq!(
+            snippet.parts(),
+            [("…turns in ", false), ("writs", true), (" for you", false)]
+        );
+        assert!(parse_snippet("dirs", "WritCreater").is_none());
+    }
+}
@@ -0,0 +1,260 @@
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, NaiveDateTime, Utc};
+use lazy_async_promise::ImmediateValuePromise;
+use snafu::{OptionExt, ResultExt};
+use tracing::log::{info, warn};
+use zip::ZipArchive;
+
+use super::AddonService;
+use super::backup::{BackupOptions, diff_addons, parse_backup};
+use super::fs_util::zip_read_backup_manifest;
+use super::result::{BackupDiff, BackupSnapshot, SnapshotTrigger};
+use crate::config::Config;
+use crate::error::{self, Result};
+
+const SNAPSHOT_PREFIX: &str = "backup-";
+/// Parses names with or without the millisecond part, which older snapshots lack.
+pub(super) const SNAPSHOT_TIME_FORMAT: &str = "%Y%m%d-%H%M%S%.f";
+
+impl AddonService {
+    /// Whether the backup interval has passed since the last snapshot.
+    pub fn backup_due(&self) -> bool {
+        let config = &self.config;
+        if !config.auto_backup || config.backup_interval_days == 0 {
+            return false;
+        }
+        config.backup_last.is_none_or(|last| {
+            Utc::now() - last >= chrono::Duration::days(config.backup_interval_days.into())
+        })
+    }
+
+    /// Starts the snapshot due before updating `updates` addons at once: the scheduled
+    /// one if the backup interval has passed, otherwise one if automatic backups are on
+    /// and more than one addon is updated. `None` if no snapshot is due. As with
+    /// `create_snapshot`, the caller stores the snapshot's `created` as `backup_last`.
+    pub fn backup_before_update(
+        &self,
+        updates: usize,
+    ) -> Option<ImmediateValuePromise<BackupSnapshot>> {
+        let trigger = if self.backup_due() {
+            SnapshotTrigger::Scheduled
+        } else if self.config.auto_backup && updates > 1 {
+            SnapshotTrigger::Update
+        } else {
+            return None;
+        };
+        Some(self.create_snapshot(trigger))
+    }
+
+    /// Writes the installed set and game files into the managed backup dir, then prunes
+    /// the oldest snapshots beyond `backup_keep`. Runs on a clone of the service, so the
+    /// caller stores the snapshot's `created` as `backup_last`.
+    pub fn create_snapshot(
+        &self,
+        trigger: SnapshotTrigger,
+    ) -> ImmediateValuePromise<BackupSnapshot> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let snapshot = service.p_create_snapshot(trigger).await?;
+            Ok(snapshot)
+        })
+    }
+
+    /// Snapshots in the managed backup dir, newest first.
+    pub fn list_snapshots(&self) -> ImmediateValuePromise<Vec<BackupSnapshot>> {
+        ImmediateValuePromise::new(async move {
+            let snapshots = fs_list_snapshots(&Config::default_backup_dir())?;
+            Ok(snapshots)
+        })
+    }
+
+    /// Compares a backup with the addons installed now.
+    pub fn diff_backup(&self, file: PathBuf) -> ImmediateValuePromise<BackupDiff> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let diff = service.p_diff_backup(&file).await?;
+            Ok(diff)
+        })
+    }
+
+    async fn p_create_snapshot(&self, trigger: SnapshotTrigger) -> Result<BackupSnapshot> {
+        let dir = Config::default_backup_dir();
+        fs::create_dir_all(&dir).context(error::BackupWriteSnafu { path: &dir })?;
+        let path = fs_reserve_snapshot(&dir, Utc::now(), trigger)?;
+        info!("Writing {} backup to {}", trigger.as_str(), path.display());
+        let options = BackupOptions {
+            saved_variables: true,
+            addon_settings: true,
+        };
+        if let Err(e) = self.p_backup(&path, options).await {
+            let _ = fs::remove_file(&path);
+            return Err(e);
+        }
+
+        let mut snapshots = fs_list_snapshots(&dir)?;
+        for old in snapshots.iter().skip(self.config.backup_keep.max(1)) {
+            info!("Pruning backup {}", old.path.display());
+            if let Err(e) = fs::remove_file(&old.path) {
+                warn!("Unable to prune backup {}: {e}", old.path.display());
+            }
+        }
+        let index = snapshots
+            .iter()
+            .position(|s| s.path == path)
+            .context(error::BackupSnapshotMissingSnafu { path: &path })?;
+        Ok(snapshots.swap_remove(index))
+    }
+
+    async fn p_diff_backup(&self, file: &Path) -> Result<BackupDiff> {
+        let reader = File::open(file).context(error::BackupReadSnafu { path: file })?;
+        let json = match ZipArchive::new(reader) {
+            Ok(mut zip) => zip_read_backup_manifest(&mut zip, file)?,
+            Err(_) => fs::read_to_string(file).context(error::BackupReadSnafu { path: file })?,
+        };
+        let data = parse_backup(&json)?;
+        let installed = self.p_backup_addons().await?;
+        Ok(diff_addons(&data.installed_addons, &installed))
+    }
+}
+
+/// Claims an unused snapshot name by creating the file, so a scheduled and an update
+/// snapshot taken together can't overwrite each other.
+fn fs_reserve_snapshot(
+    dir: &Path,
+    created: DateTime<Utc>,
+    trigger: SnapshotTrigger,
+) -> Result<PathBuf> {
+    fs_reserve_path(
+        created,
+        |created| dir.join(snapshot_name(created, trigger)),
+        |path| File::create_new(path).map(|_| ()),
+    )
+}
+
+/// Claims the path `name` gives for `created` with `create`, which fails if something
+/// is already there. Clashes move on a millisecond until a path is free.
+pub(super) fn fs_reserve_path(
+    mut created: DateTime<Utc>,
+    name: impl Fn(DateTime<Utc>) -> PathBuf,
+    create: impl Fn(&Path) -> std::io::Result<()>,
+) -> Result<PathBuf> {
+    loop {
+        let path = name(created);
+        match create(&path) {
+            Ok(()) => return Ok(path),
+            Err(e) if e.kind() == std::io::ErrorKind::AlreadyExists => {
+                created += chrono::Duration::milliseconds(1);
+            }
+            Err(e) => return Err(e).context(error::BackupWriteSnafu { path: &path }),
+        }
+    }
+}
+
+/// (2026-10-19 09:30:00.250 UTC, Update) -> `backup-20261019-093000.250-update.zip`
+fn snapshot_name(created: DateTime<Utc>, trigger: SnapshotTrigger) -> String {
+    format!(
+        "{SNAPSHOT_PREFIX}{}-{}.zip",
+        snapshot_time(created),
+        trigger.as_str()
+    )
+}
+
+/// 2026-10-19 09:30:00.250 UTC -> `20261019-093000.250`, parsed by `SNAPSHOT_TIME_FORMAT`
+pub(super) fn snapshot_time(created: DateTime<Utc>) -> String {
+    created.format("%Y%m%d-%H%M%S%.3f").to_string()
+}
+
+/// Reads the snapshots in `dir`, newest first. Files that aren't snapshots are ignored.
+fn fs_list_snapshots(dir: &Path) -> Result<Vec<BackupSnapshot>> {
+    if !dir.is_dir() {
+        return Ok(vec![]);
+    }
+    let mut snapshots = vec![];
+    for entry in fs::read_dir(dir).context(error::BackupReadSnafu { path: dir })? {
+        let path = entry.context(error::BackupReadSnafu { path: dir })?.path();
+        let Some((created, trigger)) = parse_snapshot_name(&path) else {
+            continue;
+        };
+        let summary = File::open(&path)
+            .context(error::BackupReadSnafu { path: &path })
+            .and_then(|reader| ZipArchive::new(reader).context(error::BackupArchiveSnafu))
+            .and_then(|mut zip| zip_read_backup_manifest(&mut zip, &path))
+            .and_then(|json| parse_backup(&json));
+        let data = match summary {
+            Ok(data) => data,
+            Err(e) => {
+                warn!("Skipping unreadable backup {}: {e}", path.display());
+                continue;
+            }
+        };
+        snapshots.push(BackupSnapshot {
+            size: path.metadata().map(|m| m.len()).unwrap_or_default(),
+            path,
+            created,
+            trigger,
+            addons: data.installed_addons.len(),
+        });
+    }
+    snapshots.sort_by_key(|s| std::cmp::Reverse(s.created));
+    Ok(snapshots)
+}
+
+/// `backup-20261019-093000-update.zip` -> (2026-10-19 09:30:00 UTC, Update)
+fn parse_snapshot_name(path: &Path) -> Option<(DateTime<Utc>, SnapshotTrigger)> {
+    let name = path
+        .file_name()?
+        .to_str()?
+        .strip_prefix(SNAPSHOT_PREFIX)?
+        .strip_suffix(".zip")?;
+    let (time, trigger) = name.rsplit_once('-')?;
+    let created = NaiveDateTime::parse_from_str(time, SNAPSHOT_TIME_FORMAT)
+        .ok()?
+        .and_utc();
+    Some((created, SnapshotTrigger::parse(trigger)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_snapshot_name() {
+        let (created, trigger) =
+            parse_snapshot_name(Path::new("/b/backup-20261019-093000-update.zip")).unwrap();
+        assert_eq!(
+            created.format("%Y-%m-%d %H:%M:%S").to_string(),
+            "2026-10-19 09:30:00"
+        );
+        assert_eq!(trigger, SnapshotTrigger::Update);
+        assert!(parse_snapshot_name(Path::new("backup-20261019-093000-other.zip")).is_none());
+        assert!(parse_snapshot_name(Path::new("notes.zip")).is_none());
+    }
+
+    #[test]
+    fn test_snapshot_name() {
+        let created = NaiveDateTime::parse_from_str("20261019-093000.250", SNAPSHOT_TIME_FORMAT)
+            .unwrap()
+            .and_utc();
+        let name = snapshot_name(created, SnapshotTrigger::Scheduled);
+        assert_eq!(name, "backup-20261019-093000.250-scheduled.zip");
+        assert_eq!(
+            parse_snapshot_name(Path::new(&name)),
+            Some((created, SnapshotTrigger::Scheduled))
+        );
+    }
+
+    #[test]
+    fn test_reserve_snapshot() {
+        let dir = tempfile::tempdir().unwrap();
+        let dir = dir.path();
+        let created = Utc::now();
+        let first = fs_reserve_snapshot(dir, created, SnapshotTrigger::Update).unwrap();
+        let second = fs_reserve_snapshot(dir, created, SnapshotTrigger::Update).unwrap();
+        assert_ne!(first, second);
+        let (first_created, _) = parse_snapshot_name(&first).unwrap();
+        let (second_created, _) = parse_snapshot_name(&second).unwrap();
+        assert!(second_created > first_created);
+    }
+}
@@ -0,0 +1,354 @@
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::PathBuf;
+
+use entity::addon as DbAddon;
+use entity::installed_addon::{self as InstalledAddon, InstallReason};
+use lazy_async_promise::ImmediateValuePromise;
+use sea_orm::EntityTrait;
+use snafu::ResultExt;
+use tracing::log::info;
+
+use super::result::{AddonRef, FailedStep, SyncPlan, SyncReport, SyncUpdate};
+use super::{AddonService, addon_dependents, dir_providers};
+use crate::config::AddonEntry;
+use crate::error::{self, Result};
+use crate::state::{DesiredState, EntryKey};
+
+impl AddonService {
+    /// Compares the installed set with the desired state in `file`, without changing
+    /// anything. Unlisted addons are only planned for removal if the file sets
+    /// `remove_unlisted` or `remove_unlisted` is passed.
+    pub fn get_sync_plan(
+        &self,
+        file: PathBuf,
+        remove_unlisted: bool,
+    ) -> ImmediateValuePromise<SyncPlan> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let toml = fs::read_to_string(&file).context(error::StateReadSnafu { path: &file })?;
+            let state = DesiredState::from_toml(&toml)?;
+            let remove_unlisted = remove_unlisted || state.remove_unlisted;
+            let plan = service.p_sync_plan(state, remove_unlisted).await?;
+            Ok(plan)
+        })
+    }
+
+    /// Installs and updates the listed addons, then removes the unlisted ones along
+    /// with the libraries that nothing listed requires anymore.
+    pub fn sync(&self, plan: SyncPlan) -> ImmediateValuePromise<SyncReport> {
+        let service = self.clone();
+        ImmediateValuePromise::new(async move {
+            let report = service.p_sync(plan).await?;
+            Ok(report)
+        })
+    }
+
+    async fn p_sync_plan(&self, state: DesiredState, remove_unlisted: bool) -> Result<SyncPlan> {
+        let names: Vec<String> = state
+            .addons
+            .iter()
+            .filter_map(|entry| match entry.key() {
+                EntryKey::Name(name) => Some(name.to_owned()),
+                _ => None,
+            })
+            .collect();
+        let providers = dir_providers(&self.db, &names).await?;
+        let catalog: Vec<DbAddon::Model> = DbAddon::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?;
+        let installed: HashMap<i32, InstalledAddon::Model> = InstalledAddon::Entity::find()
+            .all(&self.db)
+            .await
+            .context(error::DbGetSnafu)?
+            .into_iter()
+            .map(|i| (i.addon_id, i))
+            .collect();
+
+        let mut plan = SyncPlan::default();
+        let (resolved, unresolved) = resolve_entries(&state.addons, &catalog, &providers);
+        plan.unresolved = unresolved;
+        for (addon, dependency) in resolved {
+            plan.listed.push(addon.id);
+            if dependency {
+                plan.dependencies.push(addon.id);
+            }
+            let addon_ref = AddonRef {
+                id: addon.id,
+                name: addon.name.clone(),
+            };
+            match installed.get(&addon.id) {
+                None => plan.install.push(addon_ref),
+                Some(i) if i.version != addon.version => plan.update.push(SyncUpdate {
+                    addon: addon_ref,
+                    installed_version: i.version.clone(),
+                    available_version: addon.version.clone(),
+                }),
+                Some(_) => plan.unchanged += 1,
+            }
+        }
+
+        if remove_unlisted {
+            // libraries go once nothing needs them, after the explicit addons are removed
+            let mut unlisted: HashSet<i32> = installed
+                .values()
+                .filter(|i| i.reason == InstallReason::Explicit)
+                .map(|i| i.addon_id)
+                .filter(|id| !plan.listed.contains(id))
+                .collect();
+            let mut dependents: HashMap<i32, Vec<i32>> = HashMap::new();
+            for id in unlisted.iter() {
+                let ids = addon_dependents(&self.db, *id, true).await?;
+                dependents.insert(*id, ids.into_iter().map(|d| d.id).collect());
+            }
+            keep_required(&mut unlisted, &dependents);
+            plan.remove = catalog
+                .iter()
+                .filter(|a| unlisted.contains(&a.id))
+                .map(|a| AddonRef {
+                    id: a.id,
+                    name: a.name.clone(),
+                })
+                .collect();
+            plan.remove.sort_by_key(|a| a.name.to_lowercase());
+        }
+
+        info!(
+            "Sync plan: {} to install, {} to update, {} to remove, {} unchanged, {} unresolved",
+            plan.install.len(),
+            plan.update.len(),
+            plan.remove.len(),
+            plan.unchanged,
+            plan.unresolved.len()
+        );
+        Ok(plan)
+    }
+
+    /// One addon failing to resolve, install or remove is recorded and the sync goes on
+    /// with the others, the removals run even if installing failed.
+    async fn p_sync(&self, plan: SyncPlan) -> Result<SyncReport> {
+        let mut report = SyncReport::default();
+        let ids: Vec<i32> = plan
+            .install
+            .iter()
+            .chain(plan.update.iter().map(|u| &u.addon))
+            .map(|a| a.id)
+            .collect();
+        if !ids.is_empty() {
+            match self.p_resolve_install_plan(ids).await {
+                Ok(mut install) => {
+                    for step in install.steps.iter_mut() {
+                        if plan.dependencies.contains(&step.addon.id) {
+                            step.reason = InstallReason::Dependency;
+                        }
+                    }
+                    let steps: Vec<AddonRef> =
+                        install.steps.iter().map(|s| s.addon.clone()).collect();
+                    report.failed = std::mem::take(&mut install.failed);
+                    let failed = self.p_install_plan(install).await;
+                    report.installed = steps
+                        .into_iter()
+                        .filter(|a| !failed.iter().any(|f| f.addon_id == a.id))
+                        .collect();
+                    report.failed.extend(failed);
+                }
+                Err(e) => self.record_error("Error resolving addons to sync", e),
+            }
+        }
+
+        let removing = !plan.remove.is_empty();
+        for addon in plan.remove {
+            match self.p_remove(addon.id).await {
+                Ok(()) => report.removed.push(addon),
+                Err(e) => {
+                    self.record_error(format!("Error removing {} (#{})", addon.name, addon.id), &e);
+                    report.failed.push(FailedStep {
+                        addon_id: addon.id,
+                        name: Some(addon.name),
+                        required_by: None,
+                        error: e.to_string(),
+                    });
+                }
+            }
+        }
+        if removing {
+            let candidates = match self.p_autoremove_candidates().await {
+                Ok(candidates) => candidates,
+                Err(e) => {
+                    self.record_error("Error finding unused libraries", e);
+                    vec![]
+                }
+            };
+            for candidate in candidates {
+                if plan.listed.contains(&candidate.id) {
+                    continue;
+                }
+                match self.p_remove(candidate.id).await {
+                    Ok(()) => report.removed.push(candidate),
+                    Err(e) => {
+                        self.record_error(
+                            format!("Error removing {} (#{})", candidate.name, candidate.id),
+                            &e,
+                        );
+                        report.failed.push(FailedStep {
+                            addon_id: candidate.id,
+                            name: Some(candidate.name),
+                            required_by: None,
+                            error: e.to_string(),
+                        });
+                    }
+                }
+            }
+        }
+        info!(
+            "Synced: {} installed or updated, {} removed, {} failed",
+            report.installed.len(),
+            report.removed.len(),
+            report.failed.len()
+        );
+        Ok(report)
+    }
+}
+
+/// Matches each entry of the desired state with its catalog addon, by id or ESOUI URL,
+/// then by folder name, then by the name on ESOUI. An addon listed twice is kept once
+/// with the first entry's `dependency`. Entries matching nothing come back as labels.
+fn resolve_entries<'a>(
+    entries: &[AddonEntry],
+    catalog: &'a [DbAddon::Model],
+    providers: &HashMap<String, Vec<AddonRef>>,
+) -> (Vec<(&'a DbAddon::Model, bool)>, Vec<String>) {
+    let mut resolved: Vec<(&DbAddon::Model, bool)> = vec![];
+    let mut unresolved = vec![];
+    for entry in entries.iter() {
+        let addon = match entry.key() {
+            EntryKey::Id(id) => catalog.iter().find(|a| a.id == id),
+            EntryKey::Name(name) => providers
+                .get(name)
+                .and_then(|p| p.first())
+                .and_then(|p| catalog.iter().find(|a| a.id == p.id))
+                .or_else(|| catalog.iter().find(|a| a.name.eq_ignore_ascii_case(name))),
+            EntryKey::Invalid => None,
+        };
+        match addon {
+            None => unresolved.push(entry.label()),
+            Some(addon) if resolved.iter().any(|(a, _)| a.id == addon.id) => {}
+            Some(addon) => resolved.push((addon, entry.dependency)),
+        }
+    }
+    (resolved, unresolved)
+}
+
+/// Takes the addons out of `unlisted` that an addon staying installed requires, until
+/// only those nothing staying needs are left. `dependents` maps each unlisted addon to
+/// the installed addons requiring it.
+fn keep_required(unlisted: &mut HashSet<i32>, dependents: &HashMap<i32, Vec<i32>>) {
+    loop {
+        let kept: Vec<i32> = unlisted
+            .iter()
+            .copied()
+            .filter(|id| {
+                dependents
+                    .get(id)
+                    .is_some_and(|ids| ids.iter().any(|d| !unlisted.contains(d)))
+            })
+            .collect();
+        if kept.is_empty() {
+            break;
+        }
+        unlisted.retain(|id| !kept.contains(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn addon(id: i32, name: &str) -> DbAddon::Model {
+        DbAddon::Model {
+            id,
+            category_id: "1".to_string(),
+            version: "1.0".to_string(),
+            date: String::new(),
+            name: name.to_string(),
+            author_name: None,
+            file_info_url: None,
+            download_total: None,
+            download_monthly: None,
+            favorite_total: None,
+            md5: None,
+            file_name: None,
+            download: None,
+        }
+    }
+
+    fn entry(id: Option<i32>, url: Option<&str>, name: &str, dependency: bool) -> AddonEntry {
+        AddonEntry {
+            id,
+            name: name.to_string(),
+            url: url.map(String::from),
+            dependency,
+        }
+    }
+
+    #[test]
+    fn test_resolve_entries() {
+        let catalog = vec![
+            addon(1360, "Combat Metrics"),
+            addon(7, "LibAddonMenu"),
+            addon(57, "HarvestMap"),
+            addon(3000, "HarvestMap-Data"),
+        ];
+        let providers = HashMap::from([(
+            "HarvestMapData".to_string(),
+            vec![AddonRef {
+                id: 3000,
+                name: "HarvestMap-Data".to_string(),
+            }],
+        )]);
+        let entries = vec![
+            entry(Some(1360), None, "", false),
+            // the id wins over the URL
+            entry(
+                Some(7),
+                Some("https://www.esoui.com/downloads/info1360-CombatMetrics.html"),
+                "",
+                true,
+            ),
+            entry(
+                None,
+                Some("https://www.esoui.com/downloads/info57-HarvestMap.html"),
+                "",
+                false,
+            ),
+            // a folder name before the name on ESOUI
+            entry(None, None, "HarvestMapData", false),
+            entry(None, None, "libaddonmenu", false),
+            entry(Some(1360), None, "", true),
+            entry(Some(99), None, "", false),
+            entry(None, None, "Missing", false),
+        ];
+
+        let (resolved, unresolved) = resolve_entries(&entries, &catalog, &providers);
+
+        let resolved: Vec<(i32, bool)> = resolved.iter().map(|(a, d)| (a.id, *d)).collect();
+        assert_eq!(
+            resolved,
+            vec![(1360, false), (7, true), (57, false), (3000, false)]
+        );
+        assert_eq!(unresolved, vec!["99", "Missing"]);
+    }
+
+    #[test]
+    fn test_keep_required() {
+        // 10 stays installed and requires 1, which requires 2; 3 is required only by 4,
+        // which goes too
+        let mut unlisted = HashSet::from([1, 2, 3, 4]);
+        let dependents = HashMap::from([(1, vec![10]), (2, vec![1]), (3, vec![4])]);
+
+        keep_required(&mut unlisted, &dependents);
+
+        assert_eq!(unlisted, HashSet::from([3, 4]));
+    }
+}
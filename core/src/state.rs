@@ -0,0 +1,110 @@
+use serde_derive::Deserialize;
+use snafu::ResultExt;
+
+use crate::addons::get_addon_id;
+use crate::config::AddonEntry;
+use crate::error::{self, Result};
+
+/// The addons a machine should have, kept in a TOML file like a dotfiles repo:
+///
+/// ```toml
+/// # remove installed addons that aren't listed
+/// remove_unlisted = true
+///
+/// [[addon]]
+/// id = 1360
+///
+/// [[addon]]
+/// url = "https://www.esoui.com/downloads/info7-LibAddonMenu.html"
+/// dependency = true
+///
+/// [[addon]]
+/// name = "HarvestMap"
+/// ```
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct DesiredState {
+    #[serde(default)]
+    pub remove_unlisted: bool,
+    #[serde(default, rename = "addon")]
+    pub addons: Vec<AddonEntry>,
+}
+
+/// How an entry names its addon, in the order they're tried
+#[derive(Debug, PartialEq)]
+pub enum EntryKey<'a> {
+    Id(i32),
+    /// Addon folder name, or failing that the addon's name
+    Name(&'a str),
+    /// Neither an id, an ESOUI URL nor a name
+    Invalid,
+}
+
+impl DesiredState {
+    pub fn from_toml(toml: &str) -> Result<Self> {
+        toml::from_str(toml).context(error::StateParseSnafu)
+    }
+}
+
+impl AddonEntry {
+    pub fn key(&self) -> EntryKey<'_> {
+        if let Some(id) = self.id {
+            return EntryKey::Id(id);
+        }
+        if let Some(id) = self.url.as_deref().and_then(get_addon_id) {
+            return EntryKey::Id(id);
+        }
+        if !self.name.trim().is_empty() {
+            return EntryKey::Name(self.name.trim());
+        }
+        EntryKey::Invalid
+    }
+
+    /// How the entry reads in the state file, for reporting unresolved ones
+    pub fn label(&self) -> String {
+        match (self.id, &self.url) {
+            (Some(id), _) => id.to_string(),
+            (None, Some(url)) => url.clone(),
+            (None, None) if !self.name.is_empty() => self.name.clone(),
+            _ => "empty entry".to_owned(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_desired_state() {
+        let state = DesiredState::from_toml(
+            r#"
+            [[addon]]
+            id = 1360
+
+            [[addon]]
+            url = "https://www.esoui.com/downloads/info7-LibAddonMenu.html"
+            dependency = true
+
+            [[addon]]
+            name = " HarvestMap "
+
+            [[addon]]
+            url = "https://example.com/addon"
+            "#,
+        )
+        .unwrap();
+        assert!(!state.remove_unlisted);
+        let keys: Vec<EntryKey> = state.addons.iter().map(|a| a.key()).collect();
+        assert_eq!(
+            keys,
+            vec![
+                EntryKey::Id(1360),
+                EntryKey::Id(7),
+                EntryKey::Name("HarvestMap"),
+                EntryKey::Invalid
+            ]
+        );
+        assert!(state.addons[1].dependency);
+        assert_eq!(state.addons[3].label(), "https://example.com/addon");
+    }
+}
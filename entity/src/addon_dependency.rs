@@ -9,6 +9,17 @@ pub struct Model {
     pub addon_id: i32,
     #[sea_orm(primary_key, auto_increment = false)]
     pub dependency_dir: String,
+    pub kind: DependencyKind,
+}
+
+/// Manifest directive a dependency came from, `## DependsOn:` or `## OptionalDependsOn:`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum DependencyKind {
+    #[sea_orm(string_value = "required")]
+    Required,
+    #[sea_orm(string_value = "optional")]
+    Optional,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
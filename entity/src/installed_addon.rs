@@ -9,6 +9,17 @@ pub struct Model {
     pub addon_id: i32,
     pub version: String,
     pub date: String,
+    pub reason: InstallReason,
+}
+
+/// Why an addon was installed, libraries pulled in for another addon can be auto-removed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, EnumIter, DeriveActiveEnum)]
+#[sea_orm(rs_type = "String", db_type = "String(StringLen::None)")]
+pub enum InstallReason {
+    #[sea_orm(string_value = "explicit")]
+    Explicit,
+    #[sea_orm(string_value = "dependency")]
+    Dependency,
 }
 
 #[derive(Copy, Clone, Debug, EnumIter, DeriveRelation)]
@@ -7,6 +7,9 @@ mod m20230519_153409_manual_deps;
 mod m20240212_164739_update_0_category;
 mod m20240214_174800_add_game_version;
 mod m20240219_192429_add_addon_images;
+mod m20261018_101512_dependency_kind;
+mod m20261019_093027_install_reason;
+mod m20261020_141233_addon_search;
 
 pub struct Migrator;
 
@@ -21,6 +24,9 @@ impl MigratorTrait for Migrator {
             Box::new(m20240212_164739_update_0_category::Migration),
             Box::new(m20240214_174800_add_game_version::Migration),
             Box::new(m20240219_192429_add_addon_images::Migration),
+            Box::new(m20261018_101512_dependency_kind::Migration),
+            Box::new(m20261019_093027_install_reason::Migration),
+            Box::new(m20261020_141233_addon_search::Migration),
         ]
     }
 }
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // existing rows all came from `## DependsOn:`, so default them to required
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AddonDependency::Table)
+                    .add_column(
+                        ColumnDef::new(AddonDependency::Kind)
+                            .string()
+                            .not_null()
+                            .default("required"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(AddonDependency::Table)
+                    .drop_column(AddonDependency::Kind)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum AddonDependency {
+    Table,
+    Kind,
+}
@@ -0,0 +1,43 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // we can't know why existing addons were installed, so never treat them as removable
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InstalledAddon::Table)
+                    .add_column(
+                        ColumnDef::new(InstalledAddon::Reason)
+                            .string()
+                            .not_null()
+                            .default("explicit"),
+                    )
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .alter_table(
+                Table::alter()
+                    .table(InstalledAddon::Table)
+                    .drop_column(InstalledAddon::Reason)
+                    .to_owned(),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[derive(DeriveIden)]
+enum InstalledAddon {
+    Table,
+    Reason,
+}
@@ -0,0 +1,41 @@
+use sea_orm_migration::prelude::*;
+
+#[derive(DeriveMigrationName)]
+pub struct Migration;
+
+#[async_trait::async_trait]
+impl MigrationTrait for Migration {
+    async fn up(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        // full-text index over the catalog, the rowid is the addon id
+        let db = manager.get_connection();
+        db.execute_unprepared(
+            r#"create virtual table addon_search using fts5(
+                name, author, category, dirs, description, change_log,
+                tokenize = 'unicode61 remove_diacritics 2',
+                prefix = '2 3'
+            )"#,
+        )
+        .await?;
+        // descriptions are indexed as stored here, the next catalog refresh
+        // re-indexes them without bbcode
+        db.execute_unprepared(
+            r#"insert into addon_search (rowid, name, author, category, dirs, description, change_log)
+            select a.id, a.name, a.author_name, c.title,
+                (select group_concat(dir, ' ') from addon_dir where addon_id = a.id),
+                d.description, d.change_log
+            from addon a
+            left join category c on c.id = a.category_id
+            left join addon_detail d on d.id = a.id"#,
+        )
+        .await?;
+        Ok(())
+    }
+
+    async fn down(&self, manager: &SchemaManager) -> Result<(), DbErr> {
+        manager
+            .get_connection()
+            .execute_unprepared("drop table addon_search")
+            .await?;
+        Ok(())
+    }
+}
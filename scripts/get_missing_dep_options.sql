@@ -11,7 +11,8 @@ from installed_addon i
 	inner join addon_dependency adp on i.addon_id = adp.addon_id
 	inner join addon a on i.addon_id = a.id
 where
-	adp.dependency_dir not in (
+	adp.kind = 'required'
+	and adp.dependency_dir not in (
 		SELECT
 			DISTINCT ad.dir
 		FROM
@@ -4,23 +4,30 @@ use dotenv::dotenv;
 use eframe::egui::{self, RichText, Visuals, vec2};
 use eso_addons_core::config;
 use eso_addons_core::service::AddonService;
-use eso_addons_core::service::result::{AddonDepOption, AddonShowDetails, UpdateResult};
+use eso_addons_core::service::result::{
+    AddonDepOption, AddonShowDetails, BackupSnapshot, ChangePlan, InstallPlan, RemovalPlan,
+    SnapshotTrigger, UpdateResult,
+};
 use lazy_async_promise::{ImmediateValuePromise, ImmediateValueState};
 use std::collections::HashMap;
 use std::io;
 use std::sync::Arc;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tracing::log::info;
 use tracing_appender::rolling::{RollingFileAppender, Rotation};
 use tracing_subscriber::fmt::writer::MakeWriterExt;
 use views::author::Author;
+use views::changes::ChangesDialog;
 
 mod views;
 use views::addon_details::Details;
 use views::errors::Errors;
+use views::graph::Graph;
+use views::install_plan::InstallPlanDialog;
 use views::installed::Installed;
 use views::missing_deps::MissingDeps;
 use views::onboard::Onboard;
+use views::removal_plan::RemovalPlanDialog;
 use views::search::Search;
 use views::settings::Settings;
 use views::ui_helpers::{AddonResponse, AddonResponseType, PromisedValue, ViewOpt};
@@ -29,6 +36,8 @@ use views::{ResetView, View};
 const APP_NAME: &str = "ESO Addon Manager";
 pub const REPO: Option<&str> = option_env!("CARGO_PKG_REPOSITORY");
 pub const VERSION: &str = env!("CARGO_PKG_VERSION");
+/// How often to check whether a scheduled backup is due
+const BACKUP_CHECK_INTERVAL: Duration = Duration::from_secs(60 * 60);
 
 #[tokio::main]
 async fn main() -> Result<(), eframe::Error> {
@@ -91,18 +100,35 @@ struct EamApp {
     missing_dep: MissingDeps,
     author_view: Author,
     errors_view: Errors,
+    graph_view: Graph,
+    install_plan_dialog: InstallPlanDialog,
+    removal_plan_dialog: RemovalPlanDialog,
+    /// Confirms bulk updates, holding the addons to update
+    update_changes_dialog: ChangesDialog<Vec<i32>>,
     /// Addon Service with async network/DB
     service: AddonService,
     /// Addon management promises
     remove: PromisedValue<()>,
+    removal_plan: PromisedValue<RemovalPlan>,
     update_one: HashMap<i32, PromisedValue<()>>,
     install_one: HashMap<i32, PromisedValue<()>>,
+    /// Dependency resolution for requested installs, keyed by the requested addon
+    install_plans: HashMap<i32, PromisedValue<InstallPlan>>,
     installed_addons: PromisedValue<Vec<AddonShowDetails>>,
     update: PromisedValue<UpdateResult>,
     ttc_pricetable: PromisedValue<config::TtcConfigUpdate>,
     hm_data: Option<ImmediateValuePromise<config::HmConfigUpdate>>,
     missing_deps: PromisedValue<Vec<AddonDepOption>>,
+    optional_deps: PromisedValue<Vec<AddonDepOption>>,
     install_missing_deps: PromisedValue<()>,
+    /// Scheduled backups and the ones taken before bulk updates
+    auto_backup: PromisedValue<BackupSnapshot>,
+    last_backup_check: Option<Instant>,
+    /// Bulk update waiting to be confirmed
+    update_changes: PromisedValue<ChangePlan>,
+    planned_updates: Vec<i32>,
+    /// Bulk update waiting for its backup to finish
+    pending_updates: Vec<i32>,
     /// Only auto-nav to MissingDeps when newly discovered, not on every refresh.
     had_missing_deps: bool,
     /// Report once the GUI is up and exit; set by ESO_ADDONS_SMOKE_TEST.
@@ -150,15 +176,27 @@ impl EamApp {
             missing_dep: MissingDeps::new(),
             author_view: Author::default(),
             errors_view: Errors::default(),
+            graph_view: Graph::default(),
+            install_plan_dialog: InstallPlanDialog::default(),
+            removal_plan_dialog: RemovalPlanDialog::default(),
+            update_changes_dialog: ChangesDialog::default(),
             remove: PromisedValue::default(),
+            removal_plan: PromisedValue::default(),
             update_one: HashMap::new(),
             install_one: HashMap::new(),
+            install_plans: HashMap::new(),
             installed_addons: PromisedValue::default(),
             update: PromisedValue::default(),
             ttc_pricetable: PromisedValue::default(),
             hm_data: None,
             missing_deps: PromisedValue::default(),
+            optional_deps: PromisedValue::default(),
             install_missing_deps: PromisedValue::default(),
+            auto_backup: PromisedValue::default(),
+            last_backup_check: None,
+            update_changes: PromisedValue::default(),
+            planned_updates: vec![],
+            pending_updates: vec![],
             had_missing_deps: false,
             smoke_test: std::env::var_os("ESO_ADDONS_SMOKE_TEST").is_some(),
             smoke_reported: false,
@@ -254,12 +292,21 @@ impl EamApp {
                 if !self.had_missing_deps {
                     self.change_view(ViewOpt::MissingDeps);
                 }
-            } else if self.view == ViewOpt::MissingDeps {
+            } else if self.view == ViewOpt::MissingDeps && !self.missing_dep.has_optional_deps() {
                 self.close_view();
             }
             self.had_missing_deps = has_missing;
         }
 
+        // optional dependencies are only suggestions, never navigate to them
+        self.optional_deps
+            .poll_recording(&self.service, "Checking optional dependencies");
+        if self.optional_deps.is_ready() {
+            self.optional_deps.handle();
+            let deps = self.optional_deps.value.as_ref().unwrap().to_owned();
+            self.missing_dep.set_optional_deps(deps);
+        }
+
         // poll installing missing dependencies
         self.install_missing_deps
             .poll_recording(&self.service, "Installing missing dependencies");
@@ -268,6 +315,59 @@ impl EamApp {
             addons_changed = true;
         }
 
+        // removal plan poll, only ask when something depends on the addon
+        self.removal_plan
+            .poll_recording(&self.service, "Checking addon dependents");
+        if self.removal_plan.is_ready() {
+            self.removal_plan.handle();
+            if let Some(plan) = self.removal_plan.value.take() {
+                if plan.dependents.is_empty() {
+                    self.run_removal_plan(plan, false);
+                } else {
+                    self.removal_plan_dialog.open(plan);
+                }
+            }
+        }
+
+        // backups, a failed backup is recorded but doesn't hold back the update
+        if self
+            .last_backup_check
+            .is_none_or(|checked| checked.elapsed() >= BACKUP_CHECK_INTERVAL)
+        {
+            self.last_backup_check = Some(Instant::now());
+            if self.service.backup_due() && !self.auto_backup.is_polling() {
+                self.auto_backup
+                    .set(self.service.create_snapshot(SnapshotTrigger::Scheduled));
+            }
+        }
+        self.auto_backup
+            .poll_recording(&self.service, "Backing up addon data");
+        if self.auto_backup.is_ready() {
+            self.auto_backup.handle();
+            if let Some(snapshot) = self.auto_backup.value.as_ref() {
+                info!("Backed up to {}", snapshot.path.display());
+                self.service.config.backup_last = Some(snapshot.created);
+                self.service.save_config();
+            }
+        }
+        self.update_changes
+            .poll_recording(&self.service, "Planning updates");
+        if self.update_changes.is_ready() {
+            self.update_changes.handle();
+            if let Some(changes) = self.update_changes.value.take() {
+                let addon_ids = std::mem::take(&mut self.planned_updates);
+                let title = format!("Update {} Addons", addon_ids.len());
+                self.update_changes_dialog.open(title, addon_ids, changes);
+            }
+        } else if !self.update_changes.is_polling() {
+            self.planned_updates.clear();
+        }
+        if !self.pending_updates.is_empty() && !self.auto_backup.is_polling() {
+            for addon_id in std::mem::take(&mut self.pending_updates) {
+                self.update_addon(addon_id);
+            }
+        }
+
         // remove addon poll
         self.remove.poll_recording(&self.service, "Removing addon");
         if self.remove.is_ready() {
@@ -294,6 +394,35 @@ impl EamApp {
         addons_changed =
             addons_changed || (!updated_addons.is_empty() && self.update_one.is_empty());
 
+        // install plans poll, only ask for confirmation when more than the addon is installed
+        let mut resolved_plans = vec![];
+        for (addon_id, promise) in self.install_plans.iter_mut() {
+            promise.poll_recording(
+                &self.service,
+                &format!("Resolving dependencies of {addon_id}"),
+            );
+            if promise.is_ready() {
+                promise.handle();
+                resolved_plans.push((*addon_id, promise.value.take()));
+            } else if !promise.is_polling() {
+                resolved_plans.push((*addon_id, None));
+            }
+        }
+        for (addon_id, plan) in resolved_plans {
+            self.install_plans.remove(&addon_id);
+            match plan {
+                Some(plan)
+                    if plan.steps.len() > 1
+                        || !plan.unresolved.is_empty()
+                        || !plan.failed.is_empty() =>
+                {
+                    self.install_plan_dialog.open(plan);
+                }
+                Some(plan) => self.run_install_plan(plan),
+                None => {}
+            }
+        }
+
         // install addons poll
         let mut installed_addons = vec![];
         for (addon_id, promise) in self.install_one.iter_mut() {
@@ -350,14 +479,39 @@ impl EamApp {
         self.search.reset(&mut self.service);
         self.details.reset(&mut self.service);
         self.author_view.reset(&mut self.service);
+        self.graph_view.reset(&mut self.service);
     }
 
     fn remove_addon(&mut self, addon_id: i32) {
+        self.removal_plan
+            .set(self.service.get_removal_plan(addon_id));
+    }
+
+    fn run_removal_plan(&mut self, plan: RemovalPlan, cascade: bool) {
         let mut promise = PromisedValue::<()>::default();
-        promise.set(self.service.remove(addon_id));
+        promise.set(self.service.remove_plan(plan, cascade));
         self.remove = promise;
     }
 
+    /// Updates wait for the snapshot due before them, or the one already running.
+    fn update_addons(&mut self, addon_ids: Vec<i32>) {
+        if self.auto_backup.is_polling() {
+            self.pending_updates.extend(addon_ids);
+            return;
+        }
+        match self.service.backup_before_update(addon_ids.len()) {
+            Some(snapshot) => {
+                self.pending_updates.extend(addon_ids);
+                self.auto_backup.set(snapshot);
+            }
+            None => {
+                for addon_id in addon_ids {
+                    self.update_addon(addon_id);
+                }
+            }
+        }
+    }
+
     fn update_addon(&mut self, addon_id: i32) {
         let mut promise = PromisedValue::<()>::default();
         promise.set(self.service.install(addon_id, true));
@@ -365,8 +519,18 @@ impl EamApp {
     }
 
     fn install_addon(&mut self, addon_id: i32) {
+        let mut promise = PromisedValue::<InstallPlan>::default();
+        promise.set(self.service.resolve_install_plan(vec![addon_id]));
+        self.install_plans.insert(addon_id, promise);
+    }
+
+    fn run_install_plan(&mut self, plan: InstallPlan) {
+        // the requested addon is always the last step
+        let Some(addon_id) = plan.steps.last().map(|step| step.addon.id) else {
+            return;
+        };
         let mut promise = PromisedValue::<()>::default();
-        promise.set(self.service.install(addon_id, false));
+        promise.set(self.service.install_plan(plan));
         self.install_one.insert(addon_id, promise);
     }
 
@@ -398,6 +562,8 @@ impl EamApp {
     fn check_missing_deps(&mut self) {
         self.missing_deps
             .set(self.service.get_missing_dependency_options());
+        self.optional_deps
+            .set(self.service.get_optional_dependency_options());
     }
 
     // endregion
@@ -437,6 +603,16 @@ impl eframe::App for EamApp {
 
         self.poll();
 
+        if let Some(plan) = self.install_plan_dialog.show(ctx) {
+            self.run_install_plan(plan);
+        }
+        if let Some((plan, cascade)) = self.removal_plan_dialog.show(ctx) {
+            self.run_removal_plan(plan, cascade);
+        }
+        if let Some(addon_ids) = self.update_changes_dialog.show(ctx) {
+            self.update_addons(addon_ids);
+        }
+
         // if we are loading addons, show spinner and that's it
         if self.update.is_polling() || self.installed_addons.is_polling() {
             egui::CentralPanel::default().show(ui, |ui| {
@@ -482,6 +658,12 @@ impl eframe::App for EamApp {
                             ViewOpt::MissingDeps,
                             RichText::new("❗ Missing Dependencies").heading(),
                         );
+                    } else if self.missing_dep.has_optional_deps() {
+                        ui.selectable_value(
+                            &mut self.view,
+                            ViewOpt::MissingDeps,
+                            RichText::new("➕ Optional Dependencies").heading(),
+                        );
                     }
                     ui.selectable_value(
                         &mut self.view,
@@ -489,6 +671,11 @@ impl eframe::App for EamApp {
                         RichText::new("🔍 Find More").heading(),
                     );
                     // ui.selectable_value(&mut self.view, ViewOpt::Browse, "Browse");
+                    ui.selectable_value(
+                        &mut self.view,
+                        ViewOpt::Graph,
+                        RichText::new("🔗 Dependencies").heading(),
+                    );
                     ui.selectable_value(
                         &mut self.view,
                         ViewOpt::Settings,
@@ -529,7 +716,8 @@ impl eframe::App for EamApp {
                         });
                     }
                     let installing_count =
-                        self.install_one.values().filter(|x| x.is_polling()).count();
+                        self.install_one.values().filter(|x| x.is_polling()).count()
+                            + self.install_plans.len();
                     if installing_count > 0 {
                         ui.horizontal(|ui| {
                             ui.spinner();
@@ -565,6 +753,7 @@ impl eframe::App for EamApp {
                 ViewOpt::Author => self.author_view.ui(ctx, ui, &mut self.service),
                 ViewOpt::MissingDeps => self.missing_dep.ui(ctx, ui, &mut self.service),
                 ViewOpt::Errors => self.errors_view.ui(ctx, ui, &mut self.service),
+                ViewOpt::Graph => self.graph_view.ui(ctx, ui, &mut self.service),
                 ViewOpt::Root => {
                     self.view = ViewOpt::Installed;
                     AddonResponse::default()
@@ -598,8 +787,14 @@ impl eframe::App for EamApp {
                     self.update_addon(response.addon_id);
                 }
                 AddonResponseType::UpdateMultiple => {
-                    for addon_id in response.addon_ids {
-                        self.update_addon(addon_id);
+                    if response.addon_ids.len() > 1 {
+                        if !self.update_changes.is_polling() {
+                            self.planned_updates = response.addon_ids.clone();
+                            self.update_changes
+                                .set(self.service.plan_install(response.addon_ids, true));
+                        }
+                    } else {
+                        self.update_addons(response.addon_ids);
                     }
                 }
                 AddonResponseType::Install => {
@@ -9,7 +9,10 @@ use eframe::egui::{self, Image, Layout, RichText, ScrollArea, vec2};
 use egui::Button;
 use eso_addons_core::service::{
     AddonService,
-    result::{AddonDependencyView, AddonImageResult, AddonShowDetails, Resolution},
+    result::{
+        AddonDependencyView, AddonImageResult, AddonRef, AddonShowDetails, DepStatus, Resolution,
+        SettingsSnapshot,
+    },
 };
 
 #[derive(PartialEq, Default)]
@@ -21,6 +24,7 @@ enum DetailView {
     FileInfo,
     Dependencies,
     Dependents,
+    Settings,
 }
 
 #[derive(Default)]
@@ -47,6 +51,8 @@ pub struct Details {
     pending_addons_changed: bool,
     row_state: HashMap<String, DepRowState>,
     reset_scroll: bool,
+    settings_snapshots: PromisedValue<Vec<SettingsSnapshot>>,
+    settings_restore: PromisedValue<usize>,
 }
 
 impl Details {
@@ -75,6 +81,10 @@ impl Details {
                 self.pending_addons_changed = true;
             }
         }
+        self.settings_snapshots
+            .poll_recording(service, "Loading settings snapshots");
+        self.settings_restore
+            .poll_recording(service, "Restoring addon settings");
     }
 
     fn build_bb_views(&mut self) {
@@ -96,6 +106,9 @@ impl Details {
         self.images.set(service.get_addon_images(addon_id));
         self.dep_view
             .set(service.get_addon_dependency_view(addon_id));
+        self.settings_snapshots
+            .set(service.get_settings_snapshots(addon_id));
+        self.settings_restore = PromisedValue::default();
         self.view = DetailView::default();
         self.selected_image = String::default();
         self.bb_description = None;
@@ -272,12 +285,12 @@ impl View for Details {
                 let (deps_label, deps_enabled, dependents_label, dependents_enabled) =
                     match self.dep_view.value.as_ref() {
                         Some(v) => (
-                            if v.forward.is_empty() {
+                            if v.forward.is_empty() && v.optional.is_empty() {
                                 "No Dependencies"
                             } else {
                                 "Dependencies"
                             },
-                            !v.forward.is_empty(),
+                            !v.forward.is_empty() || !v.optional.is_empty(),
                             if v.dependents.is_empty() {
                                 "No Dependents"
                             } else {
@@ -305,6 +318,21 @@ impl View for Details {
                         )
                         .changed();
                 });
+                if addon.installed
+                    && self
+                        .settings_snapshots
+                        .value
+                        .as_ref()
+                        .is_some_and(|s| !s.is_empty())
+                {
+                    tab_changed |= ui
+                        .selectable_value(
+                            &mut self.view,
+                            DetailView::Settings,
+                            RichText::new("Saved Settings").heading(),
+                        )
+                        .changed();
+                }
                 ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
                     ui.checkbox(&mut self.show_raw_text, "Raw");
                 });
@@ -423,112 +451,30 @@ impl View for Details {
                         ui.separator();
                     }
                     for dep in &dep_view.forward {
-                        ui.horizontal_wrapped(|ui| {
-                            ui.strong(format!("{}:", dep.dep_dir));
-                            match &dep.resolution {
-                                Resolution::Installed(r) => {
-                                    if ui
-                                        .selectable_label(false, format!("{} (installed)", r.name))
-                                        .clicked()
-                                    {
-                                        action = Some(DepAction::Navigate(r.id));
-                                    }
-                                }
-                                Resolution::SatisfiedBy(r) => {
-                                    ui.label("satisfied by");
-                                    if ui.selectable_label(false, &r.name).clicked() {
-                                        action = Some(DepAction::Navigate(r.id));
-                                    }
-                                    if ui.button("revoke").clicked() {
-                                        action = Some(DepAction::Revoke(dep.dep_dir.clone()));
-                                    }
-                                }
-                                Resolution::Ignored => {
-                                    ui.label("ignored");
-                                    if ui.button("revoke").clicked() {
-                                        action = Some(DepAction::Revoke(dep.dep_dir.clone()));
-                                    }
-                                }
-                                Resolution::Unresolved { suggestions } => {
-                                    let row =
-                                        self.row_state.entry(dep.dep_dir.clone()).or_default();
-                                    if row.selected_suggestion.is_none() {
-                                        row.selected_suggestion = suggestions.first().map(|s| s.id);
-                                    }
-                                    if ui.button("Ignore").clicked() {
-                                        action = Some(DepAction::SetIgnored(dep.dep_dir.clone()));
-                                    }
-                                    ui.label("satisfied by:");
-                                    let sb_text = row
-                                        .selected_satisfied_by
-                                        .and_then(|id| {
-                                            dep_view
-                                                .installed_addons
-                                                .iter()
-                                                .find(|a| a.id == id)
-                                                .map(|a| a.name.as_str())
-                                        })
-                                        .unwrap_or("");
-                                    egui::ComboBox::from_id_salt(format!("sb_{}", dep.dep_dir))
-                                        .selected_text(sb_text)
-                                        .width(180.0)
-                                        .show_ui(ui, |ui| {
-                                            for a in &dep_view.installed_addons {
-                                                if ui
-                                                    .selectable_label(
-                                                        row.selected_satisfied_by == Some(a.id),
-                                                        &a.name,
-                                                    )
-                                                    .clicked()
-                                                {
-                                                    row.selected_satisfied_by = Some(a.id);
-                                                    action = Some(DepAction::SetSatisfiedBy(
-                                                        dep.dep_dir.clone(),
-                                                        a.id,
-                                                    ));
-                                                }
-                                            }
-                                        });
-                                    if !suggestions.is_empty() {
-                                        ui.label("install:");
-                                        let suggestion_text = row
-                                            .selected_suggestion
-                                            .and_then(|id| {
-                                                suggestions
-                                                    .iter()
-                                                    .find(|s| s.id == id)
-                                                    .map(|s| s.name.as_str())
-                                            })
-                                            .unwrap_or("");
-                                        egui::ComboBox::from_id_salt(format!("sg_{}", dep.dep_dir))
-                                            .selected_text(suggestion_text)
-                                            .width(180.0)
-                                            .show_ui(ui, |ui| {
-                                                for s in suggestions {
-                                                    if ui
-                                                        .selectable_label(
-                                                            row.selected_suggestion == Some(s.id),
-                                                            &s.name,
-                                                        )
-                                                        .clicked()
-                                                    {
-                                                        row.selected_suggestion = Some(s.id);
-                                                    }
-                                                }
-                                            });
-                                        if ui.button("Install").clicked()
-                                            && let Some(id) = row.selected_suggestion
-                                        {
-                                            action = Some(DepAction::InstallBatch(vec![(
-                                                dep.dep_dir.clone(),
-                                                id,
-                                            )]));
-                                        }
-                                    }
-                                }
-                            }
-                        });
+                        dep_row_ui(
+                            ui,
+                            dep,
+                            &dep_view.installed_addons,
+                            &mut self.row_state,
+                            &mut action,
+                        );
+                        ui.separator();
+                    }
+                    if !dep_view.optional.is_empty() {
+                        ui.add_space(5.0);
+                        ui.label(RichText::new("Optional").heading());
+                        ui.label("Not required to load, these addons enable extra features.");
                         ui.separator();
+                        for dep in &dep_view.optional {
+                            dep_row_ui(
+                                ui,
+                                dep,
+                                &dep_view.installed_addons,
+                                &mut self.row_state,
+                                &mut action,
+                            );
+                            ui.separator();
+                        }
                     }
                     if let Some(action) = action {
                         match action {
@@ -576,12 +522,163 @@ impl View for Details {
                         response.response_type = AddonResponseType::AddonName;
                     }
                 }
+                DetailView::Settings => {
+                    let Some(snapshots) = self.settings_snapshots.value.as_ref() else {
+                        ui.spinner();
+                        return;
+                    };
+                    ui.label("SavedVariables copied before this addon was updated. Close the game before restoring, it overwrites them on logout.");
+                    ui.add_space(5.0);
+                    if self.settings_restore.is_polling() {
+                        ui.spinner();
+                    } else if let Some(count) = self.settings_restore.value {
+                        ui.label(format!(
+                            "✔ Restored {count} file{}, the replaced ones were kept as .bak",
+                            if count == 1 { "" } else { "s" }
+                        ));
+                    }
+                    for snapshot in snapshots.iter() {
+                        ui.group(|ui| {
+                            ui.horizontal(|ui| {
+                                ui.label(
+                                    RichText::new(format!(
+                                        "Version {} - {}",
+                                        snapshot.version,
+                                        snapshot.created.format("%Y-%m-%d %H:%M UTC")
+                                    ))
+                                    .strong(),
+                                );
+                                ui.with_layout(
+                                    Layout::right_to_left(egui::Align::Center),
+                                    |ui| {
+                                        if ui
+                                            .add_enabled(
+                                                !self.settings_restore.is_polling(),
+                                                Button::new("Restore settings from before update"),
+                                            )
+                                            .clicked()
+                                        {
+                                            self.settings_restore.set(
+                                                service.restore_settings_snapshot(snapshot.clone()),
+                                            );
+                                        }
+                                    },
+                                );
+                            });
+                            ui.weak(snapshot.files.join(", "));
+                        });
+                    }
+                }
             });
         });
         response
     }
 }
 
+fn dep_row_ui(
+    ui: &mut egui::Ui,
+    dep: &DepStatus,
+    installed_addons: &[AddonRef],
+    row_state: &mut HashMap<String, DepRowState>,
+    action: &mut Option<DepAction>,
+) {
+    ui.horizontal_wrapped(|ui| {
+        ui.strong(format!("{}:", dep.dep_dir));
+        match &dep.resolution {
+            Resolution::Installed(r) => {
+                if ui
+                    .selectable_label(false, format!("{} (installed)", r.name))
+                    .clicked()
+                {
+                    *action = Some(DepAction::Navigate(r.id));
+                }
+            }
+            Resolution::SatisfiedBy(r) => {
+                ui.label("satisfied by");
+                if ui.selectable_label(false, &r.name).clicked() {
+                    *action = Some(DepAction::Navigate(r.id));
+                }
+                if ui.button("revoke").clicked() {
+                    *action = Some(DepAction::Revoke(dep.dep_dir.clone()));
+                }
+            }
+            Resolution::Ignored => {
+                ui.label("ignored");
+                if ui.button("revoke").clicked() {
+                    *action = Some(DepAction::Revoke(dep.dep_dir.clone()));
+                }
+            }
+            Resolution::Unresolved { suggestions } => {
+                let row = row_state.entry(dep.dep_dir.clone()).or_default();
+                if row.selected_suggestion.is_none() {
+                    row.selected_suggestion = suggestions.first().map(|s| s.id);
+                }
+                if ui.button("Ignore").clicked() {
+                    *action = Some(DepAction::SetIgnored(dep.dep_dir.clone()));
+                }
+                ui.label("satisfied by:");
+                let sb_text = row
+                    .selected_satisfied_by
+                    .and_then(|id| {
+                        installed_addons
+                            .iter()
+                            .find(|a| a.id == id)
+                            .map(|a| a.name.as_str())
+                    })
+                    .unwrap_or("");
+                egui::ComboBox::from_id_salt(format!("sb_{}", dep.dep_dir))
+                    .selected_text(sb_text)
+                    .width(180.0)
+                    .show_ui(ui, |ui| {
+                        for a in installed_addons {
+                            if ui
+                                .selectable_label(row.selected_satisfied_by == Some(a.id), &a.name)
+                                .clicked()
+                            {
+                                row.selected_satisfied_by = Some(a.id);
+                                *action =
+                                    Some(DepAction::SetSatisfiedBy(dep.dep_dir.clone(), a.id));
+                            }
+                        }
+                    });
+                if !suggestions.is_empty() {
+                    ui.label("install:");
+                    let suggestion_text = row
+                        .selected_suggestion
+                        .and_then(|id| {
+                            suggestions
+                                .iter()
+                                .find(|s| s.id == id)
+                                .map(|s| s.name.as_str())
+                        })
+                        .unwrap_or("");
+                    egui::ComboBox::from_id_salt(format!("sg_{}", dep.dep_dir))
+                        .selected_text(suggestion_text)
+                        .width(180.0)
+                        .show_ui(ui, |ui| {
+                            for s in suggestions {
+                                if ui
+                                    .selectable_label(
+                                        row.selected_suggestion == Some(s.id),
+                                        &s.name,
+                                    )
+                                    .clicked()
+                                {
+                                    row.selected_suggestion = Some(s.id);
+                                }
+                            }
+                        });
+                    if ui.button("Install").clicked()
+                        && let Some(id) = row.selected_suggestion
+                    {
+                        *action = Some(DepAction::InstallBatch(vec![(dep.dep_dir.clone(), id)]));
+                    }
+                }
+            }
+        }
+    });
+}
+
 /// Navigate to the in-app detail view when a bbcode link points at another
 /// addon on esoui, otherwise open it in the browser.
 fn handle_bb_link(ui: &egui::Ui, url: &str, response: &mut AddonResponse) {
@@ -637,6 +734,8 @@ impl ResetView for Details {
         self.details.set(service.get_addon_details(self.addon_id));
         self.dep_view
             .set(service.get_addon_dependency_view(self.addon_id));
+        self.settings_snapshots
+            .set(service.get_settings_snapshots(self.addon_id));
         self.row_state.clear();
         self.bb_description = None;
         self.bb_changelog = None;
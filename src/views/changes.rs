@@ -0,0 +1,111 @@
+use eframe::egui::{self, CollapsingHeader, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::ChangePlan;
+
+/// What a bulk action changes, asked before running it. `T` is what's needed to run it.
+pub struct ChangesDialog<T> {
+    pending: Option<(String, T, ChangePlan)>,
+}
+impl<T> Default for ChangesDialog<T> {
+    fn default() -> Self {
+        Self { pending: None }
+    }
+}
+impl<T> ChangesDialog<T> {
+    pub fn open(&mut self, title: impl Into<String>, action: T, changes: ChangePlan) {
+        self.pending = Some((title.into(), action, changes));
+    }
+
+    /// Returns the action once confirmed.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<T> {
+        let (title, _, changes) = self.pending.as_ref()?;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("changes").with(title.as_str())).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading(title);
+            ui.add_space(5.0);
+            if changes.is_empty() {
+                ui.label("Nothing to change.");
+            }
+            let (size, unknown) = changes.download_size();
+            if !changes.downloads.is_empty() {
+                let more = if unknown { " and more" } else { "" };
+                ui.label(format!(
+                    "Downloads {} files, {:.1} MB{more}",
+                    changes.downloads.len(),
+                    size as f64 / 1_000_000.0
+                ));
+            }
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                section_ui(
+                    ui,
+                    "Downloads",
+                    changes.downloads.iter().map(|d| match d.size {
+                        Some(size) => format!("{} ({:.1} MB)", d.label, size as f64 / 1_000_000.0),
+                        None => d.label.clone(),
+                    }),
+                );
+                section_ui(
+                    ui,
+                    "Written",
+                    changes
+                        .dirs_written
+                        .iter()
+                        .chain(changes.files_written.iter())
+                        .map(|p| p.display().to_string()),
+                );
+                section_ui(
+                    ui,
+                    "Deleted",
+                    changes.dirs_deleted.iter().map(|p| p.display().to_string()),
+                );
+                section_ui(
+                    ui,
+                    "Database",
+                    changes
+                        .db_changes
+                        .iter()
+                        .map(|c| format!("{} {} {}", c.action.as_str(), c.table, c.rows)),
+                );
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !changes.is_empty(),
+                        egui::Button::new(RichText::new("Continue").heading()),
+                    )
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            return self.pending.take().map(|(_, action, _)| action);
+        }
+        if cancelled || modal.should_close() {
+            self.pending = None;
+        }
+        None
+    }
+}
+
+fn section_ui(ui: &mut egui::Ui, heading: &str, lines: impl Iterator<Item = String>) {
+    let lines: Vec<String> = lines.collect();
+    if lines.is_empty() {
+        return;
+    }
+    CollapsingHeader::new(format!("{heading} ({})", lines.len()))
+        .id_salt(heading)
+        .show(ui, |ui| {
+            for line in lines.iter() {
+                ui.label(line);
+            }
+        });
+}
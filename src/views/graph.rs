@@ -0,0 +1,254 @@
+use std::collections::{HashMap, HashSet};
+
+use eframe::egui::{
+    self, Align2, Color32, FontId, Pos2, Rect, RichText, ScrollArea, Sense, Shape, Stroke,
+    StrokeKind, pos2, vec2,
+};
+use eso_addons_core::graph::{DependencyGraph, NodeKind};
+use eso_addons_core::service::AddonService;
+
+use super::ui_helpers::{AddonResponse, AddonResponseType, PromisedValue, truncate_len};
+use super::{ResetView, View};
+
+const NODE_SIZE: egui::Vec2 = vec2(180.0, 24.0);
+const COLUMN_GAP: f32 = 80.0;
+const ROW_GAP: f32 = 12.0;
+const MARGIN: f32 = 10.0;
+
+#[derive(Default)]
+pub struct Graph {
+    graph: PromisedValue<DependencyGraph>,
+    /// Top left corner of each node, relative to the graph area
+    positions: Vec<Pos2>,
+    cycle_nodes: HashSet<usize>,
+    leaves: HashSet<usize>,
+    selected: Option<usize>,
+    show_optional: bool,
+}
+impl Graph {
+    fn load(&mut self, service: &AddonService) {
+        self.selected = None;
+        self.graph.set(service.get_dependency_graph());
+    }
+
+    fn poll(&mut self, service: &AddonService) {
+        self.graph
+            .poll_recording(service, "Loading dependency graph");
+        if self.graph.is_ready() {
+            self.graph.handle();
+            let graph = self.graph.value.as_ref().unwrap();
+            self.cycle_nodes = graph.cycles().into_iter().flatten().collect();
+            self.leaves = graph.leaf_libraries().into_iter().collect();
+            self.positions = layout(graph);
+        }
+    }
+}
+
+/// Columns by dependency depth, addons on the left and the libraries they need on the right.
+fn layout(graph: &DependencyGraph) -> Vec<Pos2> {
+    // edges within a cycle would push its nodes apart forever
+    let mut component = vec![usize::MAX; graph.nodes.len()];
+    for (i, cycle) in graph.cycles().iter().enumerate() {
+        for &node in cycle {
+            component[node] = i;
+        }
+    }
+    let edges: Vec<(usize, usize)> = graph
+        .edges
+        .iter()
+        .filter(|e| component[e.from] == usize::MAX || component[e.from] != component[e.to])
+        .map(|e| (e.from, e.to))
+        .collect();
+
+    // longest path down to a node without dependencies
+    let mut depth = vec![0_usize; graph.nodes.len()];
+    for _ in 0..graph.nodes.len() {
+        let mut changed = false;
+        for &(from, to) in edges.iter() {
+            if depth[from] < depth[to] + 1 {
+                depth[from] = depth[to] + 1;
+                changed = true;
+            }
+        }
+        if !changed {
+            break;
+        }
+    }
+
+    let max_depth = depth.iter().copied().max().unwrap_or(0);
+    let mut columns: HashMap<usize, Vec<usize>> = HashMap::new();
+    for (node, d) in depth.iter().enumerate() {
+        columns.entry(max_depth - d).or_default().push(node);
+    }
+    let mut positions = vec![Pos2::ZERO; graph.nodes.len()];
+    for (column, nodes) in columns.iter_mut() {
+        nodes.sort_by_key(|&n| graph.nodes[n].label.to_lowercase());
+        for (row, &node) in nodes.iter().enumerate() {
+            positions[node] = pos2(
+                MARGIN + *column as f32 * (NODE_SIZE.x + COLUMN_GAP),
+                MARGIN + row as f32 * (NODE_SIZE.y + ROW_GAP),
+            );
+        }
+    }
+    positions
+}
+
+impl View for Graph {
+    fn ui(
+        &mut self,
+        _ctx: &egui::Context,
+        ui: &mut egui::Ui,
+        service: &mut AddonService,
+    ) -> AddonResponse {
+        let mut response = AddonResponse::default();
+        if self.graph.value.is_none() && !self.graph.is_polling() {
+            self.load(service);
+        }
+        self.poll(service);
+
+        egui::Panel::top("graph_top").show(ui, |ui| {
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                ui.heading("Dependencies");
+                if ui.button("↻ Refresh").clicked() {
+                    self.load(service);
+                }
+                ui.checkbox(&mut self.show_optional, "Show optional");
+            });
+            ui.horizontal_wrapped(|ui| {
+                ui.colored_label(Color32::ORANGE, "■ cycle");
+                ui.colored_label(Color32::RED, "■ no addon provides it");
+                ui.colored_label(Color32::GRAY, "■ library nothing requires");
+                ui.label("Click to highlight, double click to open the addon.");
+            });
+            ui.add_space(5.0);
+        });
+
+        let Some(graph) = self.graph.value.as_ref() else {
+            ui.spinner();
+            return response;
+        };
+        if graph.nodes.is_empty() {
+            ui.label("No installed addons.");
+            return response;
+        }
+
+        ScrollArea::both().show(ui, |ui| {
+            let size = self
+                .positions
+                .iter()
+                .fold(egui::Vec2::ZERO, |size, p| size.max(p.to_vec2()))
+                + NODE_SIZE
+                + vec2(MARGIN, MARGIN);
+            let (area, painter) = ui.allocate_painter(size, Sense::hover());
+            let origin = area.rect.min.to_vec2();
+            let rects: Vec<Rect> = self
+                .positions
+                .iter()
+                .map(|p| Rect::from_min_size(*p + origin, NODE_SIZE))
+                .collect();
+            let visuals = ui.visuals().clone();
+
+            for edge in graph.edges.iter() {
+                if edge.optional && !self.show_optional {
+                    continue;
+                }
+                let highlighted = self
+                    .selected
+                    .is_some_and(|s| s == edge.from || s == edge.to);
+                let color = if graph.nodes[edge.to].kind == NodeKind::Unresolved {
+                    Color32::RED
+                } else if self.cycle_nodes.contains(&edge.from)
+                    && self.cycle_nodes.contains(&edge.to)
+                {
+                    Color32::ORANGE
+                } else if highlighted {
+                    visuals.selection.stroke.color
+                } else {
+                    visuals.weak_text_color()
+                };
+                let stroke = Stroke::new(if highlighted { 2.0 } else { 1.0 }, color);
+                let start = rects[edge.from].right_center();
+                let end = rects[edge.to].left_center();
+                if edge.optional {
+                    painter.extend(Shape::dashed_line(&[start, end], stroke, 6.0, 4.0));
+                } else {
+                    painter.line_segment([start, end], stroke);
+                }
+                let direction = (end - start).normalized() * 8.0;
+                painter.arrow(end - direction, direction, stroke);
+            }
+
+            for (i, node) in graph.nodes.iter().enumerate() {
+                let rect = rects[i];
+                let node_response =
+                    ui.interact(rect, ui.id().with(("graph_node", i)), Sense::click());
+                let fill = if self.selected == Some(i) {
+                    visuals.selection.bg_fill
+                } else if self.leaves.contains(&i) {
+                    Color32::GRAY
+                } else {
+                    visuals.extreme_bg_color
+                };
+                let stroke_color = if node.kind == NodeKind::Unresolved {
+                    Color32::RED
+                } else if self.cycle_nodes.contains(&i) {
+                    Color32::ORANGE
+                } else {
+                    visuals.widgets.noninteractive.bg_stroke.color
+                };
+                painter.rect(
+                    rect,
+                    4.0,
+                    fill,
+                    Stroke::new(1.0, stroke_color),
+                    StrokeKind::Inside,
+                );
+                painter.text(
+                    rect.center(),
+                    Align2::CENTER_CENTER,
+                    truncate_len(&node.label, 24),
+                    FontId::proportional(13.0),
+                    visuals.text_color(),
+                );
+
+                if node_response.double_clicked()
+                    && let Some(addon_id) = node.addon_id
+                {
+                    response.addon_id = addon_id;
+                    response.response_type = AddonResponseType::AddonName;
+                } else if node_response.clicked() {
+                    self.selected = if self.selected == Some(i) {
+                        None
+                    } else {
+                        Some(i)
+                    };
+                }
+                node_response.on_hover_ui(|ui| {
+                    ui.label(RichText::new(&node.label).strong());
+                    let requires = graph.edges.iter().filter(|e| e.from == i).count();
+                    let required_by = graph
+                        .edges
+                        .iter()
+                        .filter(|e| e.to == i && !e.optional)
+                        .count();
+                    ui.label(format!("requires {requires}, required by {required_by}"));
+                    if node.kind == NodeKind::Unresolved {
+                        ui.label("No installed addon provides this directory");
+                    } else if self.leaves.contains(&i) {
+                        ui.label("Installed as a dependency, nothing requires it anymore");
+                    }
+                });
+            }
+        });
+
+        response
+    }
+}
+impl ResetView for Graph {
+    fn reset(&mut self, service: &mut AddonService) {
+        if self.graph.value.is_some() {
+            self.load(service);
+        }
+    }
+}
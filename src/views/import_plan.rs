@@ -0,0 +1,90 @@
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::ImportPlan;
+
+/// Preview of an imported addon list, nothing is installed until it's confirmed.
+#[derive(Default)]
+pub struct ImportPlanDialog {
+    plan: Option<ImportPlan>,
+}
+impl ImportPlanDialog {
+    pub fn open(&mut self, plan: ImportPlan) {
+        self.plan = Some(plan);
+    }
+
+    /// Returns the plan once confirmed.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<ImportPlan> {
+        let plan = self.plan.as_ref()?;
+        let to_install = plan.to_install().count();
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("import_plan")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading("Import Addons");
+            ui.add_space(5.0);
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                if to_install > 0 {
+                    ui.label(RichText::new(format!("To install ({to_install})")).strong());
+                    for imported in plan.to_install() {
+                        ui.horizontal(|ui| {
+                            ui.label(&imported.addon.name);
+                            if imported.source != imported.addon.id.to_string() {
+                                ui.weak(&imported.source);
+                            }
+                        });
+                    }
+                    ui.add_space(5.0);
+                }
+                let installed: Vec<&str> = plan
+                    .addons
+                    .iter()
+                    .filter(|a| a.installed)
+                    .map(|a| a.addon.name.as_str())
+                    .collect();
+                if !installed.is_empty() {
+                    ui.label(
+                        RichText::new(format!("Already installed ({})", installed.len())).strong(),
+                    );
+                    ui.label(installed.join(", "));
+                    ui.add_space(5.0);
+                }
+                if !plan.unresolved.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⚠ No addon found for these entries:",
+                    );
+                    for source in plan.unresolved.iter() {
+                        ui.label(source);
+                    }
+                    ui.add_space(5.0);
+                }
+            });
+            ui.label("Required libraries are installed along with them.");
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        to_install > 0,
+                        egui::Button::new(
+                            RichText::new(format!("Install ({to_install})")).heading(),
+                        ),
+                    )
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            return self.plan.take();
+        }
+        if cancelled || modal.should_close() {
+            self.plan = None;
+        }
+        None
+    }
+}
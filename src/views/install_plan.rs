@@ -0,0 +1,80 @@
+use std::collections::VecDeque;
+
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::InstallPlan;
+
+/// Confirmation for installs that pull in dependencies, shown over the current view.
+/// Plans resolved while one is open wait their turn.
+#[derive(Default)]
+pub struct InstallPlanDialog {
+    plans: VecDeque<InstallPlan>,
+}
+impl InstallPlanDialog {
+    pub fn open(&mut self, plan: InstallPlan) {
+        self.plans.push_back(plan);
+    }
+
+    /// Returns the front plan once the user confirms it.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<InstallPlan> {
+        let plan = self.plans.front()?;
+        let waiting = self.plans.len() - 1;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("install_plan")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading("Install");
+            if waiting > 0 {
+                ui.label(RichText::new(format!("{waiting} more waiting")).weak());
+            }
+            ui.add_space(5.0);
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for step in plan.steps.iter() {
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(RichText::new(&step.addon.name).strong());
+                        ui.label(RichText::new(&step.version).weak());
+                        if let Some(required_by) = &step.required_by {
+                            ui.label(format!("required by {required_by}"));
+                        }
+                    });
+                }
+            });
+            if !plan.failed.is_empty() {
+                ui.add_space(5.0);
+                ui.colored_label(ui.visuals().error_fg_color, "✗ Unable to install:");
+                for failed in plan.failed.iter() {
+                    ui.label(format!("{}: {}", failed.label(), failed.error));
+                }
+            }
+            if !plan.unresolved.is_empty() {
+                ui.add_space(5.0);
+                ui.colored_label(
+                    ui.visuals().warn_fg_color,
+                    "⚠ No addon found for these dependencies:",
+                );
+                for dep in plan.unresolved.iter() {
+                    ui.label(format!("{} (required by {})", dep.dir, dep.required_by));
+                }
+            }
+            ui.add_space(5.0);
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui.button(RichText::new("Install").heading()).clicked() {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            self.plans.pop_front()
+        } else {
+            if cancelled || modal.should_close() {
+                self.plans.pop_front();
+            }
+            None
+        }
+    }
+}
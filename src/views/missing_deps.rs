@@ -9,6 +9,7 @@ use super::ui_helpers::{AddonResponse, AddonResponseType};
 #[derive(Default)]
 pub struct MissingDeps {
     missing_deps: HashMap<String, MissingDepView>,
+    optional_deps: HashMap<String, MissingDepView>,
     addon_map: AddonMap,
     rev_addon_map: HashMap<String, i32>,
 }
@@ -35,12 +36,25 @@ impl MissingDeps {
     }
 
     pub fn set_deps(&mut self, deps: Vec<AddonDepOption>) {
-        self.missing_deps.clear();
+        self.missing_deps = Self::dep_views(&deps, false);
+    }
+
+    pub fn set_optional_deps(&mut self, deps: Vec<AddonDepOption>) {
+        self.optional_deps = Self::dep_views(&deps, true);
+    }
+
+    pub fn has_optional_deps(&self) -> bool {
+        !self.optional_deps.is_empty()
+    }
+
+    fn dep_views(deps: &[AddonDepOption], optional: bool) -> HashMap<String, MissingDepView> {
+        let mut views: HashMap<String, MissingDepView> = HashMap::new();
         for dep in deps.iter() {
-            let dep_view = match self.missing_deps.entry(dep.missing_dir.clone()) {
+            let dep_view = match views.entry(dep.missing_dir.clone()) {
                 Entry::Occupied(o) => o.into_mut(),
                 Entry::Vacant(v) => {
-                    let missing_dep = MissingDepView::new(dep.required_by.clone());
+                    let mut missing_dep = MissingDepView::new(dep.required_by.clone());
+                    missing_dep.optional = optional;
                     v.insert(missing_dep)
                 }
             };
@@ -50,13 +64,26 @@ impl MissingDeps {
                     .options
                     .insert(option_id, dep.option_name.as_ref().unwrap().clone());
             }
-            // default to first option selected
-            dep_view.satisfied_by = dep_view.options.keys().next().copied();
+            // default to first option selected, optional ones are opt-in only
+            if !optional {
+                dep_view.satisfied_by = dep_view.options.keys().next().copied();
+            }
         }
+        views
     }
     fn install_new(&mut self) -> AddonResponse {
         // Install selected missing dep addons or set to ignore
-        let vecs: Vec<MissingDepView> = self.missing_deps.values().cloned().collect();
+        // optional deps are only sent when the user picked something to do with them
+        let vecs: Vec<MissingDepView> = self
+            .missing_deps
+            .values()
+            .chain(
+                self.optional_deps
+                    .values()
+                    .filter(|x| x.ignore || x.satisfied_by.is_some()),
+            )
+            .cloned()
+            .collect();
         AddonResponse {
             missing_deps: vecs,
             response_type: AddonResponseType::InstallMissingDeps,
@@ -92,7 +119,12 @@ impl View for MissingDeps {
                     });
                 });
                             ui.add_space(5.0);
-                ui.label("Some installed addons have missing dependencies. Please select whether the missing dependency should be ignored, is already satisfied by an existing addon, or install one of the suggested addons.");
+                if !self.missing_deps.is_empty() {
+                    ui.label("Some installed addons have missing dependencies. Please select whether the missing dependency should be ignored, is already satisfied by an existing addon, or install one of the suggested addons.");
+                }
+                if !self.optional_deps.is_empty() {
+                    ui.label("Some installed addons offer extra features when optional addons are installed. These are never required, pick any you would like to add or dismiss them.");
+                }
                 ui.add_space(5.0);
             });
 
@@ -156,6 +188,43 @@ impl View for MissingDeps {
                     });
                     ui.separator();
                 }
+                if !self.optional_deps.is_empty() {
+                    ui.add_space(5.0);
+                    ui.label(RichText::new("Optional Dependencies").heading());
+                    ui.add_space(5.0);
+                }
+                for (missing_dir, dep_opt) in self.optional_deps.iter_mut() {
+                    ui.strong(missing_dir);
+                    ui.horizontal_wrapped(|ui| {
+                        ui.label(format!("Suggested By: {}", dep_opt.required_by));
+                    });
+                    ui.add_space(5.0);
+                    ui.horizontal(|ui| {
+                        ui.checkbox(&mut dep_opt.ignore, "Dismiss");
+                        ui.add_enabled_ui(!dep_opt.ignore && !dep_opt.options.is_empty(), |ui| {
+                            ui.label("Install:");
+                            egui::ComboBox::from_id_salt(format!("optional_by_{missing_dir}"))
+                                .selected_text(
+                                    dep_opt
+                                        .satisfied_by
+                                        .and_then(|id| dep_opt.options.get(&id))
+                                        .map_or("None", |x| x.as_str()),
+                                )
+                                .width(200.0)
+                                .show_ui(ui, |ui| {
+                                    ui.selectable_value(&mut dep_opt.satisfied_by, None, "None");
+                                    for (opt_id, opt_name) in dep_opt.options.iter() {
+                                        ui.selectable_value(
+                                            &mut dep_opt.satisfied_by,
+                                            Some(*opt_id),
+                                            opt_name,
+                                        );
+                                    }
+                                });
+                        });
+                    });
+                    ui.separator();
+                }
             });
         });
         response
@@ -5,12 +5,20 @@ use self::ui_helpers::AddonResponse;
 
 pub mod addon_details;
 pub mod author;
+pub mod changes;
 pub mod errors;
+pub mod graph;
+pub mod import_plan;
+pub mod install_plan;
 pub mod installed;
 pub mod missing_deps;
+pub mod modpack;
 pub mod onboard;
+pub mod removal_plan;
+pub mod restore_plan;
 pub mod search;
 pub mod settings;
+pub mod sync_plan;
 pub mod ui_helpers;
 
 pub trait View {
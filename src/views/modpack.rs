@@ -0,0 +1,135 @@
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::{ModpackEntryStatus, ModpackReport};
+
+/// How the installed set differs from a modpack, offering to install what's missing.
+#[derive(Default)]
+pub struct ModpackDialog {
+    report: Option<ModpackReport>,
+    optional: bool,
+}
+impl ModpackDialog {
+    pub fn open(&mut self, report: ModpackReport) {
+        self.report = Some(report);
+        self.optional = false;
+    }
+
+    /// Returns the report once confirmed, along with whether to install optional addons.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<(ModpackReport, bool)> {
+        let report = self.report.as_ref()?;
+        let optional = &mut self.optional;
+        let to_apply = report
+            .missing
+            .iter()
+            .filter(|m| *optional || !m.entry.optional)
+            .count()
+            + report
+                .older
+                .iter()
+                .filter(|o| o.available_satisfies)
+                .count();
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("modpack")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading(&report.pack.name);
+            if !report.pack.description.is_empty() {
+                ui.label(&report.pack.description);
+            }
+            ui.add_space(5.0);
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                ui.label(format!("{} addons installed as listed", report.matching));
+                ui.add_space(5.0);
+                entries_ui(ui, "Missing", &report.missing, |m| {
+                    if m.entry.optional {
+                        format!("{} (optional)", m.available_version)
+                    } else {
+                        m.available_version.clone()
+                    }
+                });
+                if report.missing.iter().any(|m| m.entry.optional) {
+                    ui.checkbox(optional, "Install optional addons too");
+                    ui.add_space(5.0);
+                }
+                entries_ui(ui, "Older than listed", &report.older, |o| {
+                    let installed = o.installed_version.clone().unwrap_or_default();
+                    if o.available_satisfies {
+                        format!("{installed} → {}", o.available_version)
+                    } else {
+                        format!("{installed}, ESOUI only has {}", o.available_version)
+                    }
+                });
+                entries_ui(ui, "Newer than listed", &report.newer, |n| {
+                    format!(
+                        "{} instead of {}",
+                        n.installed_version.clone().unwrap_or_default(),
+                        n.entry.version.clone().unwrap_or_default()
+                    )
+                });
+                if !report.extra.is_empty() {
+                    ui.label(
+                        RichText::new(format!("Not in the modpack ({})", report.extra.len()))
+                            .strong(),
+                    );
+                    let names: Vec<&str> = report.extra.iter().map(|a| a.name.as_str()).collect();
+                    ui.label(names.join(", "));
+                    ui.add_space(5.0);
+                }
+                if !report.unavailable.is_empty() {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠ Not found on ESOUI:");
+                    for entry in report.unavailable.iter() {
+                        ui.label(format!("{} (#{})", entry.name, entry.id));
+                    }
+                    ui.add_space(5.0);
+                }
+            });
+            if !report.has_drift() {
+                ui.label("✔ Everything required is installed as listed.");
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        to_apply > 0,
+                        egui::Button::new(
+                            RichText::new(format!("Install and update ({to_apply})")).heading(),
+                        ),
+                    )
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Close").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            return self.report.take().map(|report| (report, self.optional));
+        }
+        if cancelled || modal.should_close() {
+            self.report = None;
+        }
+        None
+    }
+}
+
+fn entries_ui(
+    ui: &mut egui::Ui,
+    heading: &str,
+    entries: &[ModpackEntryStatus],
+    detail: impl Fn(&ModpackEntryStatus) -> String,
+) {
+    if entries.is_empty() {
+        return;
+    }
+    ui.label(RichText::new(format!("{heading} ({})", entries.len())).strong());
+    for entry in entries.iter() {
+        ui.horizontal(|ui| {
+            ui.label(&entry.addon.name);
+            ui.weak(detail(entry));
+        });
+    }
+    ui.add_space(5.0);
+}
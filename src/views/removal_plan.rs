@@ -0,0 +1,65 @@
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::RemovalPlan;
+
+/// Confirmation for removing an addon that other installed addons still require.
+#[derive(Default)]
+pub struct RemovalPlanDialog {
+    plan: Option<RemovalPlan>,
+}
+impl RemovalPlanDialog {
+    pub fn open(&mut self, plan: RemovalPlan) {
+        self.plan = Some(plan);
+    }
+
+    /// Returns the plan once confirmed, along with whether to remove its dependents too.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<(RemovalPlan, bool)> {
+        let plan = self.plan.as_ref()?;
+        let mut choice = None;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("removal_plan")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading(format!("Remove {}", plan.addon.name));
+            ui.add_space(5.0);
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                "⚠ These installed addons require it and will stop working:",
+            );
+            ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                for dependent in plan.dependents.iter() {
+                    ui.label(RichText::new(&dependent.name).strong());
+                }
+            });
+            ui.add_space(5.0);
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::new("Remove Anyway").heading())
+                    .clicked()
+                {
+                    choice = Some(false);
+                }
+                if ui
+                    .button(
+                        RichText::new(format!("Remove With Dependents ({})", plan.cascade.len()))
+                            .heading(),
+                    )
+                    .clicked()
+                {
+                    choice = Some(true);
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if let Some(cascade) = choice {
+            return self.plan.take().map(|plan| (plan, cascade));
+        }
+        if cancelled || modal.should_close() {
+            self.plan = None;
+        }
+        None
+    }
+}
@@ -0,0 +1,109 @@
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::{RestoreEntry, RestorePlan};
+
+/// Preview of a backup restore, nothing is installed until it's confirmed.
+#[derive(Default)]
+pub struct RestorePlanDialog {
+    plan: Option<RestorePlan>,
+}
+impl RestorePlanDialog {
+    pub fn open(&mut self, plan: RestorePlan) {
+        self.plan = Some(plan);
+    }
+
+    /// Returns the plan once confirmed.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<RestorePlan> {
+        let plan = self.plan.as_mut()?;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("restore_plan")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading("Restore Backup");
+            ui.add_space(5.0);
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                entries_ui(ui, "To install", &plan.to_install, true);
+                entries_ui(ui, "Already present", &plan.present, false);
+                if plan.archive.is_some() {
+                    files_ui(ui, plan);
+                }
+                if !plan.unavailable.is_empty() {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        "⚠ No longer available on ESOUI:",
+                    );
+                    for entry in plan.unavailable.iter() {
+                        ui.label(&entry.addon.name);
+                    }
+                    ui.add_space(5.0);
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .button(RichText::new(format!("Restore ({})", plan.to_install.len())).heading())
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            return self.plan.take();
+        }
+        if cancelled || modal.should_close() {
+            self.plan = None;
+        }
+        None
+    }
+}
+
+fn entries_ui(ui: &mut egui::Ui, heading: &str, entries: &[RestoreEntry], versions: bool) {
+    if entries.is_empty() {
+        return;
+    }
+    ui.label(RichText::new(format!("{heading} ({})", entries.len())).strong());
+    for entry in entries.iter() {
+        ui.horizontal(|ui| {
+            ui.label(&entry.addon.name);
+            if versions && entry.version != entry.available_version {
+                ui.weak(format!("{} → {}", entry.version, entry.available_version));
+            }
+        });
+    }
+    ui.add_space(5.0);
+}
+
+fn files_ui(ui: &mut egui::Ui, plan: &mut RestorePlan) {
+    let conflicts = plan.files.iter().filter(|f| f.conflict).count();
+    ui.label(
+        RichText::new(format!(
+            "Game files ({} new, {conflicts} changed, {} unchanged)",
+            plan.files.len() - conflicts,
+            plan.unchanged_files
+        ))
+        .strong(),
+    );
+    if conflicts > 0 {
+        ui.label("Replaced files are kept next to the new ones with a .bak extension.");
+        ui.horizontal(|ui| {
+            if ui.small_button("Replace all").clicked() {
+                plan.files.iter_mut().for_each(|f| f.overwrite = true);
+            }
+            if ui.small_button("Keep all").clicked() {
+                plan.files.iter_mut().for_each(|f| f.overwrite = false);
+            }
+        });
+        for file in plan.files.iter_mut().filter(|f| f.conflict) {
+            ui.checkbox(
+                &mut file.overwrite,
+                format!("Replace {}", file.path.display()),
+            );
+        }
+    }
+    ui.add_space(5.0);
+}
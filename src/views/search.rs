@@ -4,44 +4,65 @@ use super::{
     ResetView, View,
     ui_helpers::{AddonResponse, AddonTable, PromisedValue, Sort},
 };
-use eframe::egui::{self, TextWrapMode};
+use chrono::NaiveDate;
+use eframe::egui::{self, Layout, RichText, TextWrapMode};
 use eso_addons_core::service::{
     AddonService,
-    result::{AddonShowDetails, CategoryResult},
+    result::{AddonPage, AddonShowDetails, CategoryResult, DirProvider, Snippet},
+    search::{AddonQuery, AddonSort, DirMatch, InstalledFilter},
 };
 use strum::IntoEnumIterator;
-use tracing::log::info;
+
+const PAGE_SIZE: u64 = 100;
+
+/// Text typed into the filter menu, turned into [`AddonQuery`] filters once valid.
+#[derive(Default, PartialEq)]
+struct FilterInputs {
+    game_version: String,
+    min_downloads: String,
+    min_favorites: String,
+    updated_since: String,
+    author: String,
+    provides_dir: String,
+}
 
 #[derive(Default)]
 pub struct Search {
-    results: PromisedValue<Vec<AddonShowDetails>>,
+    page: PromisedValue<AddonPage>,
     search: String,
     is_init: bool,
     get_categories: PromisedValue<Vec<CategoryResult>>,
     categories: HashMap<i32, CategoryResult>,
-    category_addons: PromisedValue<Vec<AddonShowDetails>>,
-    displayed_addons: Vec<AddonShowDetails>,
     selected_category: i32,
-    previous_category: i32,
-    sort: Sort,
-    prev_sort: Sort,
+    /// `None` for the best match, or most downloaded without search text
+    sort: Option<Sort>,
+    reverse: bool,
+    installed: InstalledFilter,
+    filters: FilterInputs,
+    offset: u64,
+    /// The query `page` is for
+    queried: Option<AddonQuery>,
+    snippets: HashMap<i32, Snippet>,
+    /// Looking up which addons ship a folder instead of searching addons
+    folder_mode: bool,
+    folder: String,
+    dir_match: DirMatch,
+    providers: PromisedValue<Vec<DirProvider>>,
+    /// The folder and matching `providers` are for
+    looked_up: Option<(String, DirMatch)>,
+    /// The folder each provider ships, shown like a search snippet
+    provider_dirs: HashMap<i32, Snippet>,
 }
 
 impl Search {
     pub fn new() -> Self {
-        Self {
-            sort: Sort::TotalDownloads,
-            prev_sort: Sort::Id,
-            ..Default::default()
-        }
+        Self::default()
     }
     fn handle_init(&mut self, service: &AddonService) {
         if !self.is_init {
             self.get_categories.set(service.get_categories());
             self.is_init = true;
             self.selected_category = 0;
-            self.previous_category = 0;
-            self.get_addons(service);
         }
     }
 
@@ -56,102 +77,246 @@ impl Search {
             }
         }
 
-        self.category_addons
-            .poll_recording(service, "Loading category addons");
-        if self.category_addons.is_ready() {
-            self.category_addons.handle();
-            self.sort_addons();
+        self.page.poll_recording(service, "Searching addons");
+        if self.page.is_ready() {
+            self.page.handle();
+            self.snippets = self
+                .page
+                .value
+                .iter()
+                .flat_map(|page| page.results.iter())
+                .filter_map(|r| Some((r.addon.id, r.snippet.clone()?)))
+                .collect();
         }
 
-        self.results.poll_recording(service, "Searching addons");
-        if self.results.is_ready() {
-            self.results.handle();
+        self.providers
+            .poll_recording(service, "Looking up folder providers");
+        if self.providers.is_ready() {
+            self.providers.handle();
+            self.provider_dirs = self
+                .providers
+                .value
+                .iter()
+                .flatten()
+                .map(|p| {
+                    let snippet = Snippet {
+                        field: "dirs".to_owned(),
+                        text: p.dir.clone(),
+                        highlights: vec![(0, p.dir.len())],
+                    };
+                    (p.addon.id, snippet)
+                })
+                .collect();
         }
     }
 
-    fn get_addons(&mut self, service: &AddonService) {
-        self.category_addons
-            .set(service.get_addons_by_category(self.selected_category));
+    /// Looks up the folder when it or the matching changed.
+    fn handle_lookup(&mut self, service: &AddonService) {
+        let lookup = (self.folder.trim().to_owned(), self.dir_match);
+        if self.looked_up.as_ref() != Some(&lookup) {
+            self.providers
+                .set(service.find_dir_providers(lookup.0.clone(), lookup.1));
+            self.looked_up = Some(lookup);
+        }
+    }
+
+    fn ui_folder_bar(&mut self, ui: &mut egui::Ui) {
+        let label = |mode: DirMatch| match mode {
+            DirMatch::Exact => "Exact",
+            DirMatch::IgnoreCase => "Ignore Case",
+            DirMatch::Fuzzy => "Fuzzy",
+        };
+        egui::ComboBox::from_id_salt("search_dir_match")
+            .selected_text(format!("Match: {}", label(self.dir_match).to_uppercase()))
+            .show_ui(ui, |ui| {
+                for mode in [DirMatch::Exact, DirMatch::IgnoreCase, DirMatch::Fuzzy] {
+                    ui.selectable_value(&mut self.dir_match, mode, label(mode));
+                }
+            });
+        ui.add(
+            egui::TextEdit::singleline(&mut self.folder)
+                .hint_text("Folder of a missing dependency, e.g. LibMediaProvider-1.0"),
+        );
+        if !self.folder.is_empty() && ui.button("🗙").clicked() {
+            self.folder.clear();
+        }
+    }
+
+    fn ui_providers(&self, ui: &mut egui::Ui) -> AddonResponse {
+        if self.folder.trim().is_empty() {
+            ui.centered_and_justified(|ui| {
+                ui.label(
+                    RichText::new("Type a folder name to find the addons shipping it").heading(),
+                );
+            });
+            return AddonResponse::default();
+        }
+        let Some(providers) = self.providers.value.as_ref() else {
+            ui.spinner();
+            return AddonResponse::default();
+        };
+        if providers.is_empty() && !self.providers.is_polling() {
+            ui.centered_and_justified(|ui| {
+                ui.label(RichText::new("No addon on ESOUI ships this folder").heading());
+            });
+            return AddonResponse::default();
+        }
+        let addons: Vec<&AddonShowDetails> = providers.iter().map(|p| &p.addon).collect();
+        AddonTable::new(&addons)
+            .installable(true)
+            .snippets(&self.provider_dirs)
+            .ui(ui)
     }
 
     fn get_cagetory_title(&self, category_id: i32) -> String {
         self.categories.get(&category_id).unwrap().title.to_owned()
     }
 
-    fn handle_sort(&mut self) {
-        if self.prev_sort != self.sort {
-            self.prev_sort = self.sort;
-            self.sort_addons();
-        }
-    }
-
-    fn sort_addons(&mut self) {
-        if self.category_addons.value.as_ref().is_some() {
-            self.displayed_addons = self.category_addons.value.as_ref().unwrap().to_vec();
-        }
-        info!("Sorting addons");
-        match self.sort {
-            Sort::Author => self.displayed_addons.sort_unstable_by(|a, b| {
-                a.author_name
-                    .to_lowercase()
-                    .cmp(&b.author_name.to_lowercase())
-            }),
-            Sort::Name => self
-                .displayed_addons
-                .sort_unstable_by_key(|a| a.name.to_lowercase()),
-            Sort::Updated => self
-                .displayed_addons
-                .sort_unstable_by(|a, b| a.date.cmp(&b.date)),
-            Sort::TotalDownloads => self.displayed_addons.sort_unstable_by(|a, b| {
-                b.download_total
-                    .as_ref()
-                    .unwrap_or(&"0".to_string())
-                    .parse::<i32>()
-                    .unwrap_or(0)
-                    .cmp(
-                        &a.download_total
-                            .as_ref()
-                            .unwrap_or(&"0".to_string())
-                            .parse::<i32>()
-                            .unwrap_or(0),
-                    )
-            }),
-            Sort::MonthlyDownloads => self.displayed_addons.sort_unstable_by(|a, b| {
-                b.download
-                    .as_ref()
-                    .unwrap_or(&"0".to_string())
-                    .parse::<i32>()
-                    .unwrap_or(0)
-                    .cmp(
-                        &a.download
-                            .as_ref()
-                            .unwrap_or(&"0".to_string())
-                            .parse::<i32>()
-                            .unwrap_or(0),
-                    )
-            }),
-            Sort::Favorites => self.displayed_addons.sort_unstable_by(|a, b| {
-                b.favorite_total
-                    .as_ref()
-                    .unwrap_or(&"0".to_string())
-                    .parse::<i32>()
-                    .unwrap_or(0)
-                    .cmp(
-                        &a.favorite_total
-                            .as_ref()
-                            .unwrap_or(&"0".to_string())
-                            .parse::<i32>()
-                            .unwrap_or(0),
-                    )
-            }),
-            Sort::Id => self.displayed_addons.sort_unstable_by_key(|a| a.id),
-        }
-
-        // secondary sort, put upgradeable at top
-        self.displayed_addons
-            .sort_unstable_by_key(|b| std::cmp::Reverse(b.is_upgradable()));
+    /// The query for the current search, filters and page.
+    fn query(&self) -> AddonQuery {
+        let text = self.search.trim().to_owned();
+        let sort = match self.sort {
+            Some(sort) => addon_sort(sort),
+            None if text.is_empty() => AddonSort::TotalDownloads,
+            None => AddonSort::Relevance,
+        };
+        let filters = &self.filters;
+        let non_empty = |s: &str| Some(s.trim().to_owned()).filter(|s| !s.is_empty());
+        AddonQuery {
+            text,
+            // 0 is the root category
+            category: Some(self.selected_category).filter(|id| *id != 0),
+            installed: self.installed,
+            game_version: non_empty(&filters.game_version),
+            min_downloads: filters.min_downloads.trim().parse().ok(),
+            min_favorites: filters.min_favorites.trim().parse().ok(),
+            updated_since: NaiveDate::parse_from_str(filters.updated_since.trim(), "%Y-%m-%d").ok(),
+            author: non_empty(&filters.author),
+            provides_dir: non_empty(&filters.provides_dir),
+            sort,
+            reverse: self.reverse,
+            offset: self.offset,
+            limit: Some(PAGE_SIZE),
+        }
+    }
+
+    /// Runs the query when the search, filters or page changed, back on the first
+    /// page unless only the page changed.
+    fn handle_query(&mut self, service: &AddonService) {
+        let mut query = self.query();
+        let Some(queried) = &self.queried else {
+            self.run_query(query, service);
+            return;
+        };
+        if query == *queried {
+            return;
+        }
+        if query.offset == queried.offset {
+            self.offset = 0;
+            query.offset = 0;
+        }
+        self.run_query(query, service);
+    }
+
+    fn run_query(&mut self, query: AddonQuery, service: &AddonService) {
+        self.page.set(service.query_addons(query.clone()));
+        self.queried = Some(query);
+    }
+
+    /// Chips for the filters in use, a click removes the filter.
+    fn ui_filter_chips(&mut self, ui: &mut egui::Ui) {
+        let query = self.query();
+        let mut chips: Vec<(String, &mut String)> = vec![];
+        let filters = &mut self.filters;
+        if let Some(version) = &query.game_version {
+            chips.push((format!("Game {version}"), &mut filters.game_version));
+        }
+        if let Some(min) = query.min_downloads {
+            chips.push((format!("≥ {min} downloads"), &mut filters.min_downloads));
+        }
+        if let Some(min) = query.min_favorites {
+            chips.push((format!("≥ {min} favorites"), &mut filters.min_favorites));
+        }
+        if let Some(since) = query.updated_since {
+            chips.push((format!("Updated since {since}"), &mut filters.updated_since));
+        }
+        if let Some(author) = &query.author {
+            chips.push((format!("Author: {author}"), &mut filters.author));
+        }
+        if let Some(dir) = &query.provides_dir {
+            chips.push((format!("Provides {dir}"), &mut filters.provides_dir));
+        }
+        for (label, input) in chips {
+            if ui
+                .button(format!("{label} 🗙"))
+                .on_hover_text("Remove filter")
+                .clicked()
+            {
+                input.clear();
+            }
+        }
+    }
+
+    fn ui_filter_menu(&mut self, ui: &mut egui::Ui) {
+        ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
+        let filters = &mut self.filters;
+        egui::Grid::new("search_filters")
+            .num_columns(2)
+            .show(ui, |ui| {
+                let mut row = |label: &str, value: &mut String, hint: &str| {
+                    ui.label(label);
+                    ui.add(egui::TextEdit::singleline(value).hint_text(hint));
+                    ui.end_row();
+                };
+                row("Game version", &mut filters.game_version, "10.3.5");
+                row("Min downloads", &mut filters.min_downloads, "1000");
+                row("Min favorites", &mut filters.min_favorites, "10");
+                row("Updated since", &mut filters.updated_since, "YYYY-MM-DD");
+                row("Author", &mut filters.author, "Name");
+                row(
+                    "Provides folder",
+                    &mut filters.provides_dir,
+                    "LibAddonMenu-2.0",
+                );
+            });
+    }
+
+    fn ui_pages(&mut self, ui: &mut egui::Ui) {
+        let Some(total) = self.page.value.as_ref().map(|page| page.total) else {
+            return;
+        };
+        if ui
+            .add_enabled(self.offset + PAGE_SIZE < total, egui::Button::new("▶"))
+            .clicked()
+        {
+            self.offset += PAGE_SIZE;
+        }
+        if ui
+            .add_enabled(self.offset > 0, egui::Button::new("◀"))
+            .clicked()
+        {
+            self.offset = self.offset.saturating_sub(PAGE_SIZE);
+        }
+        let first = if total == 0 { 0 } else { self.offset + 1 };
+        ui.label(format!(
+            "{first}-{} of {total}",
+            (self.offset + PAGE_SIZE).min(total)
+        ));
+    }
+}
+
+fn addon_sort(sort: Sort) -> AddonSort {
+    match sort {
+        Sort::Name => AddonSort::Name,
+        Sort::Updated => AddonSort::Updated,
+        Sort::Author => AddonSort::Author,
+        Sort::TotalDownloads => AddonSort::TotalDownloads,
+        Sort::MonthlyDownloads => AddonSort::MonthlyDownloads,
+        Sort::Favorites => AddonSort::Favorites,
+        Sort::Id => AddonSort::Id,
     }
 }
+
 impl View for Search {
     fn ui(
         &mut self,
@@ -169,9 +334,16 @@ impl View for Search {
         }
 
         egui::Panel::top("search_top").show(ui, |ui| {
-            self.handle_sort();
             ui.add_space(5.0);
             ui.horizontal(|ui| {
+                ui.selectable_value(&mut self.folder_mode, false, "🔎 Addons");
+                ui.selectable_value(&mut self.folder_mode, true, "📁 Folder")
+                    .on_hover_text("Find which addons ship a folder");
+                ui.separator();
+                if self.folder_mode {
+                    self.ui_folder_bar(ui);
+                    return;
+                }
                 egui::ComboBox::from_id_salt("search_category")
                     .selected_text(self.get_cagetory_title(self.selected_category))
                     .show_ui(ui, |ui| {
@@ -184,50 +356,77 @@ impl View for Search {
                             );
                         }
                     });
+                let sort = self.sort.map_or("Best Match".to_owned(), |s| s.to_string());
                 egui::ComboBox::from_id_salt("search_sort")
-                    .selected_text(format!("Sort By: {}", self.sort.to_string().to_uppercase()))
+                    .selected_text(format!("Sort By: {}", sort.to_uppercase()))
                     .show_ui(ui, |ui| {
                         ui.style_mut().wrap_mode = Some(TextWrapMode::Extend);
                         ui.set_min_width(60.0);
+                        ui.selectable_value(&mut self.sort, None, "Best Match");
                         for sort in Sort::iter() {
-                            ui.selectable_value(&mut self.sort, sort, sort.to_string());
+                            ui.selectable_value(&mut self.sort, Some(sort), sort.to_string());
                         }
                     });
+                ui.toggle_value(&mut self.reverse, "⬍")
+                    .on_hover_text("Reverse order");
                 ui.add(
                     egui::TextEdit::singleline(&mut self.search)
                         // .desired_width(120.0)
-                        .hint_text("Search ..."),
+                        .hint_text("Search names, authors, descriptions ..."),
                 );
                 if !self.search.is_empty() && ui.button("🗙").clicked() {
                     self.search.clear();
-                    if let Some(results) = &mut self.results.value {
-                        results.clear();
-                    }
                 }
             });
+            if self.folder_mode {
+                ui.add_space(5.0);
+                return;
+            }
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                for (filter, label) in [
+                    (InstalledFilter::Any, "All"),
+                    (InstalledFilter::Installed, "Installed"),
+                    (InstalledFilter::NotInstalled, "Not Installed"),
+                    (InstalledFilter::Outdated, "Outdated"),
+                ] {
+                    ui.selectable_value(&mut self.installed, filter, label);
+                }
+                ui.separator();
+                ui.menu_button("➕ Filter", |ui| self.ui_filter_menu(ui));
+                self.ui_filter_chips(ui);
+                ui.with_layout(Layout::right_to_left(egui::Align::Center), |ui| {
+                    self.ui_pages(ui);
+                });
+            });
             ui.add_space(5.0);
         });
 
-        if self.selected_category != self.previous_category {
-            self.get_addons(service);
-            self.previous_category = self.selected_category;
+        if self.folder_mode {
+            self.handle_lookup(service);
+            egui::CentralPanel::default().show(ui, |ui| {
+                response = self.ui_providers(ui);
+            });
+            return response;
         }
+        self.handle_query(service);
 
         egui::CentralPanel::default().show(ui, |ui| {
-            if self.category_addons.is_polling() {
+            let Some(page) = self.page.value.as_ref() else {
                 ui.spinner();
                 return;
+            };
+            if page.results.is_empty() && !self.page.is_polling() {
+                ui.centered_and_justified(|ui| {
+                    ui.label(RichText::new("No addons found").heading());
+                });
+                return;
             }
-            let addons: Vec<&AddonShowDetails> = self
-                .displayed_addons
-                .iter()
-                .filter(|x| {
-                    x.name
-                        .to_lowercase()
-                        .contains(self.search.to_lowercase().as_str())
-                })
-                .collect();
-            response = AddonTable::new(&addons).installable(true).ui(ui);
+            let addons: Vec<&AddonShowDetails> = page.results.iter().map(|r| &r.addon).collect();
+            response = AddonTable::new(&addons)
+                .installable(true)
+                .snippets(&self.snippets)
+                .ui(ui);
         });
         response
     }
@@ -235,7 +434,13 @@ impl View for Search {
 impl ResetView for Search {
     fn reset(&mut self, service: &mut AddonService) {
         if self.is_init {
-            self.get_addons(service);
+            // installs and updates change what the filters match
+            let query = self.query();
+            self.run_query(query, service);
+            if self.looked_up.is_some() {
+                self.looked_up = None;
+                self.handle_lookup(service);
+            }
         }
     }
 }
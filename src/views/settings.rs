@@ -4,11 +4,24 @@ use std::path::{Path, PathBuf};
 
 use eframe::egui::{self, Button, Color32, RichText, ScrollArea, Stroke, Visuals};
 use eso_addons_core::config;
+use eso_addons_core::export::ExportFormat;
+use eso_addons_core::modpack::PinMode;
 use eso_addons_core::service::AddonService;
+use eso_addons_core::service::backup::BackupOptions;
+use eso_addons_core::service::result::{
+    AddonRef, BackupDiff, BackupSnapshot, ChangePlan, ImportPlan, InstallPlan, ModpackReport,
+    RestorePlan, RestoreReport, SavedVariablesFile, ShadowedLibrary, SnapshotTrigger, SyncPlan,
+    SyncReport,
+};
 use lazy_async_promise::ImmediateValuePromise;
 use rfd::AsyncFileDialog;
 
 use crate::views::View;
+use crate::views::changes::ChangesDialog;
+use crate::views::import_plan::ImportPlanDialog;
+use crate::views::modpack::ModpackDialog;
+use crate::views::restore_plan::RestorePlanDialog;
+use crate::views::sync_plan::SyncPlanDialog;
 use crate::{REPO, VERSION};
 
 use super::ui_helpers::{AddonResponse, AddonResponseType, PromisedValue};
@@ -27,14 +40,74 @@ pub struct Settings {
     minion_dialog: PromisedValue<Option<String>>,
     minion_import: Option<PromisedValue<()>>,
 
+    import_text: String,
+    import_file_dialog: PromisedValue<Option<String>>,
+    import_plan: PromisedValue<ImportPlan>,
+    import_plan_dialog: ImportPlanDialog,
+    import_process: Option<PromisedValue<InstallPlan>>,
+    /// What the last import installed
+    import_result: Option<InstallPlan>,
+
+    export_format: ExportFormat,
+    export_dialog: PromisedValue<Option<String>>,
+    export: PromisedValue<String>,
+    /// File to write the export to, copied to the clipboard if `None`
+    export_path: Option<PathBuf>,
+    export_done: Option<String>,
+
+    modpack_name: String,
+    modpack_description: String,
+    modpack_pin: PinMode,
+    modpack_save_dialog: PromisedValue<Option<String>>,
+    modpack_export: PromisedValue<()>,
+    modpack_open_dialog: PromisedValue<Option<String>>,
+    /// Last modpack checked, to check it again after changes
+    modpack_path: Option<PathBuf>,
+    modpack_report: PromisedValue<ModpackReport>,
+    modpack_dialog: ModpackDialog,
+    modpack_apply: Option<PromisedValue<InstallPlan>>,
+    modpack_done: Option<String>,
+
+    state_remove_unlisted: bool,
+    state_open_dialog: PromisedValue<Option<String>>,
+    /// Last desired-state file opened, to apply it again after editing
+    state_path: Option<PathBuf>,
+    state_plan: PromisedValue<SyncPlan>,
+    state_plan_dialog: SyncPlanDialog,
+    state_sync: Option<PromisedValue<SyncReport>>,
+    state_report: Option<SyncReport>,
+
+    backup_options: BackupOptions,
     backup_dialog: PromisedValue<Option<String>>,
     backup_process: Option<PromisedValue<()>>,
 
     restore_dialog: PromisedValue<Option<String>>,
-    restore_process: Option<PromisedValue<()>>,
+    restore_plan: PromisedValue<RestorePlan>,
+    restore_plan_dialog: RestorePlanDialog,
+    restore_process: Option<PromisedValue<RestoreReport>>,
+    restore_report: Option<RestoreReport>,
+
+    snapshots: PromisedValue<Vec<BackupSnapshot>>,
+    snapshot_process: Option<PromisedValue<BackupSnapshot>>,
+    snapshot_diff: PromisedValue<BackupDiff>,
+    /// Snapshot the diff belongs to
+    diff_path: Option<PathBuf>,
 
     clear_cache: Option<PromisedValue<()>>,
 
+    autoremove_candidates: PromisedValue<Vec<AddonRef>>,
+    autoremove_changes: PromisedValue<ChangePlan>,
+    autoremove_dialog: ChangesDialog<()>,
+    autoremove_process: Option<PromisedValue<Vec<AddonRef>>>,
+
+    shadowed_libraries: PromisedValue<Vec<ShadowedLibrary>>,
+
+    saved_variables: PromisedValue<Vec<SavedVariablesFile>>,
+    saved_variables_selected: HashSet<String>,
+    saved_variables_clean: Option<PromisedValue<PathBuf>>,
+    /// Archive of the last cleanup
+    saved_variables_archive: Option<PathBuf>,
+
     installed_ids: HashSet<i32>,
 }
 impl Settings {
@@ -92,6 +165,137 @@ impl Settings {
             }
         }
 
+        // poll addon list file dialog
+        self.import_file_dialog
+            .poll_recording(service, "Reading addon list");
+        if self.import_file_dialog.is_ready() {
+            self.import_file_dialog.handle();
+            if let Some(Some(text)) = self.import_file_dialog.value.take() {
+                self.import_text = text;
+            }
+        }
+
+        self.import_plan
+            .poll_recording(service, "Matching addon list");
+        if self.import_plan.is_ready() {
+            self.import_plan.handle();
+            if let Some(plan) = self.import_plan.value.take() {
+                self.import_plan_dialog.open(plan);
+            }
+        }
+
+        if let Some(import_process) = self.import_process.as_mut() {
+            import_process.poll_recording(service, "Importing addon list");
+            if import_process.is_ready() {
+                self.import_result = import_process.value.take();
+                self.import_process = None;
+                response.response_type = AddonResponseType::AddonsChanged;
+            } else if !import_process.is_polling() {
+                self.import_process = None;
+            }
+        }
+
+        // poll export file dialog
+        self.export_dialog
+            .poll_recording(service, "Selecting export file");
+        if self.export_dialog.is_ready() {
+            self.export_dialog.handle();
+            if let Some(Some(path)) = self.export_dialog.value.take() {
+                self.export_path = Some(PathBuf::from(path));
+                self.export
+                    .set(service.export_installed(self.export_format));
+            }
+        }
+        self.export
+            .poll_recording(service, "Exporting installed addons");
+
+        // poll modpack dialogs
+        self.modpack_save_dialog
+            .poll_recording(service, "Selecting modpack file");
+        if self.modpack_save_dialog.is_ready() {
+            self.modpack_save_dialog.handle();
+            if let Some(Some(path)) = self.modpack_save_dialog.value.take() {
+                self.modpack_export.set(service.export_modpack(
+                    PathBuf::from(path),
+                    self.modpack_name.clone(),
+                    self.modpack_description.clone(),
+                    self.modpack_pin,
+                ));
+            }
+        }
+        self.modpack_export
+            .poll_recording(service, "Exporting modpack");
+        if self.modpack_export.is_ready() {
+            self.modpack_export.handle();
+            self.modpack_done = Some("✔ Modpack saved".to_owned());
+        }
+
+        self.modpack_open_dialog
+            .poll_recording(service, "Selecting modpack file");
+        if self.modpack_open_dialog.is_ready() {
+            self.modpack_open_dialog.handle();
+            if let Some(Some(path)) = self.modpack_open_dialog.value.take() {
+                let path = PathBuf::from(path);
+                self.modpack_report.set(service.check_modpack(path.clone()));
+                self.modpack_path = Some(path);
+            }
+        }
+        self.modpack_report
+            .poll_recording(service, "Checking modpack");
+        if self.modpack_report.is_ready() {
+            self.modpack_report.handle();
+            if let Some(report) = self.modpack_report.value.take() {
+                self.modpack_dialog.open(report);
+            }
+        }
+
+        if let Some(modpack_apply) = self.modpack_apply.as_mut() {
+            modpack_apply.poll_recording(service, "Applying modpack");
+            if modpack_apply.is_ready() {
+                let installed = modpack_apply.value.take().unwrap_or_default();
+                self.modpack_done = Some(format!(
+                    "✔ Installed or updated {} addons, including required libraries",
+                    installed.steps.len()
+                ));
+                self.modpack_apply = None;
+                response.response_type = AddonResponseType::AddonsChanged;
+            } else if !modpack_apply.is_polling() {
+                self.modpack_apply = None;
+            }
+        }
+
+        // poll desired state
+        self.state_open_dialog
+            .poll_recording(service, "Selecting desired-state file");
+        if self.state_open_dialog.is_ready() {
+            self.state_open_dialog.handle();
+            if let Some(Some(path)) = self.state_open_dialog.value.take() {
+                let path = PathBuf::from(path);
+                self.state_plan
+                    .set(service.get_sync_plan(path.clone(), self.state_remove_unlisted));
+                self.state_path = Some(path);
+            }
+        }
+        self.state_plan
+            .poll_recording(service, "Reading desired state");
+        if self.state_plan.is_ready() {
+            self.state_plan.handle();
+            if let Some(plan) = self.state_plan.value.take() {
+                self.state_plan_dialog.open(plan);
+            }
+        }
+
+        if let Some(state_sync) = self.state_sync.as_mut() {
+            state_sync.poll_recording(service, "Applying desired state");
+            if state_sync.is_ready() {
+                self.state_report = state_sync.value.take();
+                self.state_sync = None;
+                response.response_type = AddonResponseType::AddonsChanged;
+            } else if !state_sync.is_polling() {
+                self.state_sync = None;
+            }
+        }
+
         // poll backup file dialog
         self.backup_dialog
             .poll_recording(service, "Selecting backup file");
@@ -100,7 +304,7 @@ impl Settings {
             let value = self.backup_dialog.value.as_ref().unwrap();
             if let Some(path) = value {
                 let mut promise = PromisedValue::<()>::default();
-                promise.set(service.backup_data(PathBuf::from(path)));
+                promise.set(service.backup_data(PathBuf::from(path), self.backup_options));
                 self.backup_process = Some(promise);
             }
         }
@@ -119,20 +323,108 @@ impl Settings {
             self.restore_dialog.handle();
             let value = self.restore_dialog.value.as_ref().unwrap();
             if let Some(path) = value {
-                let mut promise = PromisedValue::<()>::default();
-                promise.set(service.restore_backup(PathBuf::from(path)));
-                self.restore_process = Some(promise);
+                self.restore_plan
+                    .set(service.get_restore_plan(PathBuf::from(path)));
+            }
+        }
+
+        self.restore_plan.poll_recording(service, "Reading backup");
+        if self.restore_plan.is_ready() {
+            self.restore_plan.handle();
+            if let Some(plan) = self.restore_plan.value.take() {
+                self.restore_plan_dialog.open(plan);
             }
         }
 
         if let Some(restore_process) = self.restore_process.as_mut() {
             restore_process.poll_recording(service, "Restoring addon data");
             if restore_process.is_ready() {
+                self.restore_report = restore_process.value.take();
                 self.restore_process = None;
                 response.response_type = AddonResponseType::AddonsChanged;
             }
         }
 
+        self.snapshots.poll_recording(service, "Listing backups");
+        if self.snapshots.is_ready() {
+            self.snapshots.handle();
+        }
+
+        if let Some(snapshot_process) = self.snapshot_process.as_mut() {
+            snapshot_process.poll_recording(service, "Backing up addon data");
+            if snapshot_process.is_ready() {
+                if let Some(snapshot) = snapshot_process.value.as_ref() {
+                    service.config.backup_last = Some(snapshot.created);
+                    service.save_config();
+                }
+                self.snapshot_process = None;
+                self.snapshots.set(service.list_snapshots());
+            }
+        }
+
+        self.snapshot_diff
+            .poll_recording(service, "Comparing backup");
+        if self.snapshot_diff.is_ready() {
+            self.snapshot_diff.handle();
+        }
+
+        self.autoremove_candidates
+            .poll_recording(service, "Finding unused libraries");
+        if self.autoremove_candidates.is_ready() {
+            self.autoremove_candidates.handle();
+        }
+
+        self.autoremove_changes
+            .poll_recording(service, "Planning library removal");
+        if self.autoremove_changes.is_ready() {
+            self.autoremove_changes.handle();
+            if let Some(changes) = self.autoremove_changes.value.take() {
+                self.autoremove_dialog
+                    .open("Remove Unused Libraries", (), changes);
+            }
+        }
+
+        if let Some(autoremove_process) = self.autoremove_process.as_mut() {
+            autoremove_process.poll_recording(service, "Removing unused libraries");
+            if autoremove_process.is_ready() {
+                self.autoremove_process = None;
+                self.autoremove_candidates = PromisedValue::default();
+                response.response_type = AddonResponseType::AddonsChanged;
+            }
+        }
+
+        self.shadowed_libraries
+            .poll_recording(service, "Scanning library copies");
+        if self.shadowed_libraries.is_ready() {
+            self.shadowed_libraries.handle();
+        }
+
+        self.saved_variables
+            .poll_recording(service, "Scanning SavedVariables");
+        if self.saved_variables.is_ready() {
+            self.saved_variables.handle();
+            // orphans are the usual cleanup, anything else is picked by hand
+            self.saved_variables_selected = self
+                .saved_variables
+                .value
+                .iter()
+                .flatten()
+                .filter(|f| f.is_orphan())
+                .map(|f| f.file.clone())
+                .collect();
+        }
+
+        if let Some(clean) = self.saved_variables_clean.as_mut() {
+            clean.poll_recording(service, "Cleaning up SavedVariables");
+            if clean.is_ready() {
+                self.saved_variables_archive = clean.value.take();
+                self.saved_variables_clean = None;
+                self.saved_variables.set(service.scan_saved_variables());
+            } else if !clean.is_polling() {
+                self.saved_variables_clean = None;
+            }
+        }
+
         if let Some(clear_cache) = self.clear_cache.as_mut() {
             clear_cache.poll_recording(service, "Clearing cache");
             if clear_cache.is_ready() {
@@ -152,6 +444,20 @@ impl View for Settings {
         service: &mut AddonService,
     ) -> AddonResponse {
         let mut response = self.poll(service);
+        if self.export.is_ready() {
+            self.export.handle();
+            let text = self.export.value.take().unwrap_or_default();
+            match self.export_path.take() {
+                Some(path) => match std::fs::write(&path, text) {
+                    Ok(()) => self.export_done = Some(format!("✔ Saved to {}", path.display())),
+                    Err(e) => service.record_error(format!("Writing {}", path.display()), e),
+                },
+                None => {
+                    ctx.copy_text(text);
+                    self.export_done = Some("✔ Copied to the clipboard".to_owned());
+                }
+            }
+        }
         let ttc_installed = self.installed_ids.contains(&TTC_ADDON_ID);
         let hm_installed = self.installed_ids.contains(&HM_DATA_ADDON_ID);
         ScrollArea::vertical().show(ui, |ui| {
@@ -340,14 +646,451 @@ impl View for Settings {
             ui.separator();
             ui.add_space(5.0);
 
+            ui.label(RichText::new("Import Addon List").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Paste ESOUI links, addon ids or addon folder names, one per line or comma separated. Nothing is installed before you confirm the matched addons.");
+            });
+            ui.add_space(5.0);
+            ui.add(
+                egui::TextEdit::multiline(&mut self.import_text)
+                    .hint_text("https://www.esoui.com/downloads/info7-LibAddonMenu.html")
+                    .desired_rows(4)
+                    .desired_width(f32::INFINITY),
+            );
+            ui.horizontal(|ui| {
+                let busy = self.import_plan.is_polling() || self.import_process.is_some();
+                if ui
+                    .add_enabled(!busy && !self.import_file_dialog.is_polling(), Button::new("🗁 Open file..."))
+                    .clicked()
+                {
+                    let promise = ImmediateValuePromise::new(async move {
+                        let dialog = AsyncFileDialog::new()
+                            .add_filter("text", &["txt", "csv"])
+                            .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
+                            .pick_file()
+                            .await;
+                        if let Some(path) = dialog {
+                            return Ok(Some(std::fs::read_to_string(path.path())?));
+                        }
+                        Ok(None::<String>)
+                    });
+                    self.import_file_dialog.set(promise);
+                }
+                if ui
+                    .add_enabled(!busy && !self.import_text.trim().is_empty(), Button::new("Import..."))
+                    .clicked()
+                {
+                    self.import_result = None;
+                    self.import_plan.set(service.get_import_plan(self.import_text.clone()));
+                }
+                if busy {
+                    ui.spinner();
+                }
+            });
+            if let Some(result) = self.import_result.as_ref() {
+                ui.label(format!("✔ Imported {} addons, including required libraries", result.steps.len()));
+                for failed in result.failed.iter() {
+                    ui.label(format!("✗ Unable to install {}: {}", failed.label(), failed.error));
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Export Installed Addons").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Share the installed addons, or take them to Minion. A Minion backup is restored from its backup folder as BU-addons.txt.");
+            });
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                egui::ComboBox::from_id_salt("export_format")
+                    .selected_text(self.export_format.label())
+                    .show_ui(ui, |ui| {
+                        for format in ExportFormat::ALL {
+                            ui.selectable_value(&mut self.export_format, format, format.label());
+                        }
+                    });
+                let busy = self.export.is_polling() || self.export_dialog.is_polling();
+                if ui.add_enabled(!busy, Button::new("Copy")).clicked() {
+                    self.export_done = None;
+                    self.export_path = None;
+                    self.export.set(service.export_installed(self.export_format));
+                }
+                if ui.add_enabled(!busy, Button::new("💾 Save...")).clicked() {
+                    self.export_done = None;
+                    let file_name = self.export_format.file_name();
+                    let promise = ImmediateValuePromise::new(async move {
+                        let dialog = AsyncFileDialog::new()
+                            .set_file_name(file_name)
+                            .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
+                            .save_file()
+                            .await;
+                        if let Some(path) = dialog {
+                            return Ok(Some(path.path().to_string_lossy().to_string()));
+                        }
+                        Ok(None::<String>)
+                    });
+                    self.export_dialog.set(promise);
+                }
+                if busy {
+                    ui.spinner();
+                }
+            });
+            if let Some(done) = self.export_done.as_ref() {
+                ui.label(done);
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Modpacks").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("A modpack lists addons everyone in a group should have, optionally with the versions they need. Save one from your installed addons, or check yours against one to see what's missing, extra or outdated.");
+            });
+            ui.add_space(5.0);
+            egui::Grid::new("modpack_export").num_columns(2).show(ui, |ui| {
+                ui.label("Name");
+                ui.text_edit_singleline(&mut self.modpack_name);
+                ui.end_row();
+                ui.label("Description");
+                ui.text_edit_singleline(&mut self.modpack_description);
+                ui.end_row();
+                ui.label("Pin");
+                egui::ComboBox::from_id_salt("modpack_pin")
+                    .selected_text(self.modpack_pin.label())
+                    .show_ui(ui, |ui| {
+                        for pin in PinMode::ALL {
+                            ui.selectable_value(&mut self.modpack_pin, pin, pin.label());
+                        }
+                    });
+                ui.end_row();
+            });
+            ui.horizontal(|ui| {
+                let busy = self.modpack_save_dialog.is_polling()
+                    || self.modpack_export.is_polling()
+                    || self.modpack_open_dialog.is_polling()
+                    || self.modpack_report.is_polling()
+                    || self.modpack_apply.is_some();
+                if ui
+                    .add_enabled(!busy && !self.modpack_name.trim().is_empty(), Button::new("💾 Save modpack..."))
+                    .clicked()
+                {
+                    self.modpack_done = None;
+                    let promise = ImmediateValuePromise::new(async move {
+                        let dialog = AsyncFileDialog::new()
+                            .add_filter("toml", &["toml"])
+                            .set_file_name("modpack.toml")
+                            .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
+                            .save_file()
+                            .await;
+                        if let Some(path) = dialog {
+                            return Ok(Some(path.path().to_string_lossy().to_string()));
+                        }
+                        Ok(None::<String>)
+                    });
+                    self.modpack_save_dialog.set(promise);
+                }
+                if ui.add_enabled(!busy, Button::new("🗁 Check modpack...")).clicked() {
+                    self.modpack_done = None;
+                    let promise = ImmediateValuePromise::new(async move {
+                        let dialog = AsyncFileDialog::new()
+                            .add_filter("toml", &["toml"])
+                            .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
+                            .pick_file()
+                            .await;
+                        if let Some(path) = dialog {
+                            return Ok(Some(path.path().to_string_lossy().to_string()));
+                        }
+                        Ok(None::<String>)
+                    });
+                    self.modpack_open_dialog.set(promise);
+                }
+                if let Some(path) = self.modpack_path.as_ref()
+                    && ui
+                        .add_enabled(!busy, Button::new("↻ Check again"))
+                        .on_hover_text(path.display().to_string())
+                        .clicked()
+                {
+                    self.modpack_done = None;
+                    self.modpack_report.set(service.check_modpack(path.clone()));
+                }
+                if busy {
+                    ui.spinner();
+                }
+            });
+            if let Some(done) = self.modpack_done.as_ref() {
+                ui.label(done);
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Desired State").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Keep the installed addons in line with a TOML file listing them by ESOUI id, link or name. Missing addons are installed and outdated ones updated, after you review the changes.");
+            });
+            ui.add_space(5.0);
+            ui.checkbox(&mut self.state_remove_unlisted, "Remove addons the file doesn't list");
+            ui.horizontal(|ui| {
+                let busy = self.state_open_dialog.is_polling()
+                    || self.state_plan.is_polling()
+                    || self.state_sync.is_some();
+                if ui.add_enabled(!busy, Button::new("🗁 Apply file...")).clicked() {
+                    self.state_report = None;
+                    let promise = ImmediateValuePromise::new(async move {
+                        let dialog = AsyncFileDialog::new()
+                            .add_filter("toml", &["toml"])
+                            .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
+                            .pick_file()
+                            .await;
+                        if let Some(path) = dialog {
+                            return Ok(Some(path.path().to_string_lossy().to_string()));
+                        }
+                        Ok(None::<String>)
+                    });
+                    self.state_open_dialog.set(promise);
+                }
+                if let Some(path) = self.state_path.as_ref()
+                    && ui
+                        .add_enabled(!busy, Button::new("↻ Apply again"))
+                        .on_hover_text(path.display().to_string())
+                        .clicked()
+                {
+                    self.state_report = None;
+                    self.state_plan
+                        .set(service.get_sync_plan(path.clone(), self.state_remove_unlisted));
+                }
+                if busy {
+                    ui.spinner();
+                }
+            });
+            if let Some(report) = self.state_report.as_ref() {
+                ui.label(format!(
+                    "✔ Installed or updated {} addons, removed {}",
+                    report.installed.len(),
+                    report.removed.len()
+                ));
+                for failed in report.failed.iter() {
+                    ui.label(format!("✗ {}: {}", failed.label(), failed.error));
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Unused Libraries").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Libraries installed as a dependency that no installed addon requires anymore.");
+            });
+            ui.add_space(5.0);
+            if self.autoremove_process.is_some() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.add_enabled(false, Button::new(RichText::new("Removing...").heading()));
+                });
+            } else if self.autoremove_candidates.is_polling() || self.autoremove_changes.is_polling() {
+                ui.add_enabled(false, Button::new(RichText::new("Searching...").heading()));
+            } else {
+                let candidates = self.autoremove_candidates.value.clone();
+                ui.horizontal(|ui| {
+                    if ui.button(RichText::new("Find Unused Libraries").heading()).clicked() {
+                        self.autoremove_candidates.set(service.get_autoremove_candidates());
+                    }
+                    if let Some(candidates) = candidates.as_ref().filter(|c| !c.is_empty())
+                        && ui.button(RichText::new(format!("Remove {}", candidates.len())).heading()).clicked()
+                    {
+                        self.autoremove_changes.set(service.plan_autoremove());
+                    }
+                });
+                if let Some(candidates) = candidates {
+                    ui.add_space(5.0);
+                    if candidates.is_empty() {
+                        ui.label("No unused libraries found.");
+                    }
+                    for addon in candidates.iter() {
+                        if ui.link(&addon.name).clicked() {
+                            response.addon_id = addon.id;
+                            response.response_type = AddonResponseType::AddonName;
+                        }
+                    }
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Library Copies").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Some addons bundle their own copy of a library. The game only loads the copy with the highest AddOnVersion, so an outdated copy can hide a newer one.");
+            });
+            ui.add_space(5.0);
+            if self.shadowed_libraries.is_polling() {
+                ui.add_enabled(false, Button::new(RichText::new("Scanning...").heading()));
+            } else {
+                if ui.button(RichText::new("Scan Library Copies").heading()).clicked() {
+                    self.shadowed_libraries.set(service.find_shadowed_libraries());
+                }
+                if let Some(shadowed) = self.shadowed_libraries.value.as_ref() {
+                    ui.add_space(5.0);
+                    if shadowed.is_empty() {
+                        ui.label("No outdated library copies found.");
+                    }
+                    for library in shadowed.iter() {
+                        shadowed_library_ui(ui, &mut response, library);
+                    }
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("SavedVariables").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label("Removed addons leave their SavedVariables behind, and some grow large enough to slow down logging out. Cleaned up files are archived to the backup folder first and can be restored from there.");
+            });
+            ui.add_space(5.0);
+            if self.saved_variables.is_polling() || self.saved_variables_clean.is_some() {
+                ui.add_enabled(false, Button::new(RichText::new("Scanning...").heading()));
+            } else {
+                if ui.button(RichText::new("Scan SavedVariables").heading()).clicked() {
+                    self.saved_variables_archive = None;
+                    self.saved_variables.set(service.scan_saved_variables());
+                }
+                if let Some(files) = self.saved_variables.value.as_ref() {
+                    ui.add_space(5.0);
+                    saved_variables_ui(ui, &mut response, files, &mut self.saved_variables_selected);
+                    let selected = self.saved_variables_selected.len();
+                    if ui
+                        .add_enabled(selected > 0, Button::new(format!("Archive and delete selected ({selected})")))
+                        .clicked()
+                    {
+                        let mut promise = PromisedValue::<PathBuf>::default();
+                        promise.set(service.clean_saved_variables(
+                            self.saved_variables_selected.iter().cloned().collect(),
+                        ));
+                        self.saved_variables_clean = Some(promise);
+                    }
+                }
+                if let Some(archive) = self.saved_variables_archive.as_ref() {
+                    ui.label(format!("✔ Archived to {}", archive.display()));
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
+            ui.label(RichText::new("Automatic Backups").heading());
+            ui.add_space(5.0);
+            ui.horizontal_wrapped(|ui| {
+                ui.label(format!("Installed addons, SavedVariables and AddOnSettings.txt are backed up to {}", config::Config::default_backup_dir().display()));
+            });
+            let mut backup_changed = false;
+            ui.horizontal(|ui| {
+                backup_changed |= ui
+                    .checkbox(
+                        &mut service.config.auto_backup,
+                        "Back up before updating several addons",
+                    )
+                    .changed();
+            });
+            ui.add_enabled_ui(service.config.auto_backup, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Also back up every");
+                    backup_changed |= ui
+                        .add(egui::DragValue::new(&mut service.config.backup_interval_days).range(0..=365))
+                        .changed();
+                    ui.label("days (0 to only back up before updates)");
+                });
+            });
+            ui.horizontal(|ui| {
+                ui.label("Keep the last");
+                backup_changed |= ui
+                    .add(egui::DragValue::new(&mut service.config.backup_keep).range(1..=100))
+                    .changed();
+                ui.label("backups");
+            });
+            if backup_changed {
+                service.save_config();
+            }
+            ui.add_space(5.0);
+            ui.horizontal(|ui| {
+                if self.snapshot_process.as_ref().is_some_and(|x| x.is_polling()) {
+                    ui.add_enabled(false, Button::new(RichText::new("Backing up...").heading()));
+                } else if ui.button(RichText::new("Back Up Now").heading()).clicked() {
+                    let mut promise = PromisedValue::<BackupSnapshot>::default();
+                    promise.set(service.create_snapshot(SnapshotTrigger::Manual));
+                    self.snapshot_process = Some(promise);
+                }
+                if self.snapshots.is_polling() {
+                    ui.spinner();
+                } else if ui.button(RichText::new("Show Backups").heading()).clicked() {
+                    self.snapshots.set(service.list_snapshots());
+                }
+            });
+            if let Some(snapshots) = self.snapshots.value.as_ref() {
+                ui.add_space(5.0);
+                if snapshots.is_empty() {
+                    ui.label("No backups yet.");
+                }
+                for snapshot in snapshots.iter() {
+                    ui.horizontal(|ui| {
+                        ui.label(format!(
+                            "{} ({}) - {} addons, {:.1} MB",
+                            snapshot.created.format("%Y-%m-%d %H:%M UTC"),
+                            snapshot.trigger.as_str(),
+                            snapshot.addons,
+                            snapshot.size as f64 / 1_000_000.0
+                        ));
+                        if ui.button("Diff").clicked() {
+                            self.diff_path = Some(snapshot.path.clone());
+                            self.snapshot_diff.set(service.diff_backup(snapshot.path.clone()));
+                        }
+                        if ui.button("Restore").clicked() {
+                            self.restore_plan.set(service.get_restore_plan(snapshot.path.clone()));
+                        }
+                    });
+                    if self.diff_path.as_ref() == Some(&snapshot.path) {
+                        if self.snapshot_diff.is_polling() {
+                            ui.spinner();
+                        } else if let Some(diff) = self.snapshot_diff.value.as_ref() {
+                            backup_diff_ui(ui, diff);
+                        }
+                    }
+                }
+            }
+
+            ui.add_space(5.0);
+            ui.separator();
+            ui.add_space(5.0);
+
             ui.label(RichText::new("Troubleshooting").heading());
             ui.add_space(5.0);
             ui.horizontal(|ui| {
                 if ui.button(RichText::new("Backup").heading()).clicked() {
                     // open backup file dialog
+                    let (filter, file_name) = if self.backup_options.is_archive() {
+                        ("zip", "eso-addons-backup.zip")
+                    } else {
+                        ("json", "eso-addons-backup.json")
+                    };
                     let promise = ImmediateValuePromise::new(async move {
                     let dialog = AsyncFileDialog::new()
-                        .add_filter("json", &["json"])
+                        .add_filter(filter, &[filter])
+                        .set_file_name(file_name)
                         .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
                         .save_file()
                         .await;
@@ -362,7 +1105,7 @@ impl View for Settings {
                     // open restore file dialog
                     let promise = ImmediateValuePromise::new(async move {
                         let dialog = AsyncFileDialog::new()
-                            .add_filter("json", &["json"])
+                            .add_filter("backup", &["json", "zip"])
                             .set_directory(env::home_dir().unwrap_or_else(|| PathBuf::from(".")))
                             .pick_file()
                             .await;
@@ -375,6 +1118,23 @@ impl View for Settings {
                 }
                 ui.hyperlink_to("Logs", eso_addons_core::config::Config::default_config_dir().to_string_lossy());
             });
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut self.backup_options.saved_variables, "Include SavedVariables");
+                ui.checkbox(&mut self.backup_options.addon_settings, "Include AddOnSettings.txt");
+            });
+            if self.restore_plan.is_polling() {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Reading backup...");
+                });
+            } else if self.restore_process.as_ref().is_some_and(|x| x.is_polling()) {
+                ui.horizontal(|ui| {
+                    ui.spinner();
+                    ui.label("Restoring addons...");
+                });
+            } else if let Some(report) = self.restore_report.as_ref() {
+                restore_report_ui(ui, report);
+            }
             ui.add_space(5.0);
             if self.clear_cache.as_ref().is_some_and(|x| x.is_polling()) {
                 ui.add_enabled(false, egui::Button::new(RichText::new("Clearing...").heading()));
@@ -421,10 +1181,192 @@ impl View for Settings {
             }
         });
 
+        if let Some((report, optional)) = self.modpack_dialog.show(ctx) {
+            let mut promise = PromisedValue::<InstallPlan>::default();
+            promise.set(service.apply_modpack(report, optional));
+            self.modpack_apply = Some(promise);
+        }
+
+        if self.autoremove_dialog.show(ctx).is_some() {
+            let mut promise = PromisedValue::<Vec<AddonRef>>::default();
+            promise.set(service.autoremove());
+            self.autoremove_process = Some(promise);
+        }
+
+        if let Some(plan) = self.state_plan_dialog.show(ctx) {
+            let mut promise = PromisedValue::<SyncReport>::default();
+            promise.set(service.sync(plan));
+            self.state_sync = Some(promise);
+        }
+
+        if let Some(plan) = self.import_plan_dialog.show(ctx) {
+            let mut promise = PromisedValue::<InstallPlan>::default();
+            promise.set(service.import_plan(plan));
+            self.import_process = Some(promise);
+        }
+
+        if let Some(plan) = self.restore_plan_dialog.show(ctx) {
+            self.restore_report = None;
+            let mut promise = PromisedValue::<RestoreReport>::default();
+            promise.set(service.restore_plan(plan));
+            self.restore_process = Some(promise);
+        }
+
         response
     }
 }
 
+fn backup_diff_ui(ui: &mut egui::Ui, diff: &BackupDiff) {
+    ui.indent("backup_diff", |ui| {
+        if diff.removed.is_empty() && diff.added.is_empty() && diff.changed.is_empty() {
+            ui.label("Same addons and versions as installed now.");
+        }
+        for addon in diff.removed.iter() {
+            ui.label(format!("- {} (no longer installed)", addon.name));
+        }
+        for addon in diff.added.iter() {
+            ui.label(format!("+ {} (installed since)", addon.name));
+        }
+        for change in diff.changed.iter() {
+            ui.label(format!(
+                "~ {} {} → {}",
+                change.addon.name, change.backup_version, change.installed_version
+            ));
+        }
+    });
+}
+
+fn restore_report_ui(ui: &mut egui::Ui, report: &RestoreReport) {
+    ui.label(format!(
+        "Restored {} addons, {} already present.",
+        report.installed.len(),
+        report.present.len()
+    ));
+    if report.files_restored > 0 || report.files_skipped > 0 {
+        ui.label(format!(
+            "Restored {} game files, kept {} existing ones.",
+            report.files_restored, report.files_skipped
+        ));
+    }
+    if !report.unavailable.is_empty() {
+        let names: Vec<&str> = report.unavailable.iter().map(|a| a.name.as_str()).collect();
+        ui.colored_label(
+            ui.visuals().warn_fg_color,
+            format!("No longer available: {}", names.join(", ")),
+        );
+    }
+    for (addon, error) in report.failed.iter() {
+        ui.colored_label(
+            ui.visuals().error_fg_color,
+            format!("Failed to install {}: {error}", addon.name),
+        );
+    }
+}
+
+fn shadowed_library_ui(ui: &mut egui::Ui, response: &mut AddonResponse, library: &ShadowedLibrary) {
+    let version = |v: Option<u32>| v.map(|v| v.to_string()).unwrap_or_else(|| "?".to_owned());
+    ui.group(|ui| {
+        ui.label(RichText::new(&library.loaded.title).strong());
+        ui.label(format!(
+            "Loaded: {} (AddOnVersion {})",
+            library.loaded.path.display(),
+            version(library.loaded.addon_version)
+        ));
+        for copy in library.stale.iter() {
+            ui.colored_label(
+                ui.visuals().warn_fg_color,
+                format!(
+                    "Outdated: {} (AddOnVersion {})",
+                    copy.path.display(),
+                    version(copy.addon_version)
+                ),
+            );
+        }
+        if let Some(standalone) = library.standalone.as_ref() {
+            let label = if library.standalone_installed {
+                format!("Update {}", standalone.name)
+            } else {
+                format!("Install {}", standalone.name)
+            };
+            if ui.button(label).clicked() {
+                response.addon_id = standalone.id;
+                response.response_type = if library.standalone_installed {
+                    AddonResponseType::Update
+                } else {
+                    AddonResponseType::Install
+                };
+            }
+        }
+    });
+}
+
+/// Orphaned files, then every file by size, each with a checkbox to clean it up.
+fn saved_variables_ui(
+    ui: &mut egui::Ui,
+    response: &mut AddonResponse,
+    files: &[SavedVariablesFile],
+    selected: &mut HashSet<String>,
+) {
+    let megabytes = |size: u64| format!("{:.1} MB", size as f64 / 1_000_000.0);
+    let total: u64 = files.iter().map(|f| f.size).sum();
+    let orphans: Vec<&SavedVariablesFile> = files.iter().filter(|f| f.is_orphan()).collect();
+    ui.label(format!(
+        "{} files, {} in total, {} orphaned ({})",
+        files.len(),
+        megabytes(total),
+        orphans.len(),
+        megabytes(orphans.iter().map(|f| f.size).sum())
+    ));
+    let mut file_row = |ui: &mut egui::Ui, file: &SavedVariablesFile| {
+        ui.horizontal(|ui| {
+            let mut checked = selected.contains(&file.file);
+            if ui
+                .checkbox(
+                    &mut checked,
+                    format!("{} ({})", file.file, megabytes(file.size)),
+                )
+                .changed()
+            {
+                if checked {
+                    selected.insert(file.file.clone());
+                } else {
+                    selected.remove(&file.file);
+                }
+            }
+            match (&file.addon, &file.owner) {
+                (Some(addon), _) => {
+                    if ui.link(&addon.name).clicked() {
+                        response.addon_id = addon.id;
+                        response.response_type = AddonResponseType::AddonName;
+                    }
+                }
+                (None, Some(owner)) => {
+                    ui.weak(owner);
+                }
+                (None, None) => {
+                    ui.colored_label(ui.visuals().warn_fg_color, "no addon");
+                }
+            }
+        });
+    };
+    if !orphans.is_empty() {
+        ui.label(RichText::new("Orphaned").strong());
+        for file in orphans {
+            file_row(ui, file);
+        }
+        ui.add_space(5.0);
+    }
+    ui.label(RichText::new("Largest").strong());
+    ScrollArea::vertical()
+        .id_salt("saved_variables")
+        .max_height(300.0)
+        .show(ui, |ui| {
+            for file in files.iter().filter(|f| !f.is_orphan()) {
+                file_row(ui, file);
+            }
+        });
+}
+
 /// Warn that an update is enabled without its addon installed, with a link to
 /// open the addon's detail view to install it.
 fn install_warning(ui: &mut egui::Ui, response: &mut AddonResponse, name: &str, addon_id: i32) {
@@ -0,0 +1,94 @@
+use eframe::egui::{self, Id, Modal, RichText, ScrollArea};
+use eso_addons_core::service::result::SyncPlan;
+
+/// What syncing with a desired-state file changes, nothing happens until it's confirmed.
+#[derive(Default)]
+pub struct SyncPlanDialog {
+    plan: Option<SyncPlan>,
+}
+impl SyncPlanDialog {
+    pub fn open(&mut self, plan: SyncPlan) {
+        self.plan = Some(plan);
+    }
+
+    /// Returns the plan once confirmed.
+    pub fn show(&mut self, ctx: &egui::Context) -> Option<SyncPlan> {
+        let plan = self.plan.as_ref()?;
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        let modal = Modal::new(Id::new("sync_plan")).show(ctx, |ui| {
+            ui.set_max_width(500.0);
+            ui.heading("Apply Desired State");
+            ui.add_space(5.0);
+            ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                ui.label(format!(
+                    "{} listed addons installed and up to date",
+                    plan.unchanged
+                ));
+                ui.add_space(5.0);
+                names_ui(
+                    ui,
+                    "To install",
+                    plan.install.iter().map(|a| a.name.clone()),
+                );
+                names_ui(
+                    ui,
+                    "To update",
+                    plan.update.iter().map(|u| {
+                        format!(
+                            "{} {} → {}",
+                            u.addon.name, u.installed_version, u.available_version
+                        )
+                    }),
+                );
+                names_ui(ui, "To remove", plan.remove.iter().map(|a| a.name.clone()));
+                if !plan.remove.is_empty() {
+                    ui.label("Libraries only these addons required are removed with them.");
+                    ui.add_space(5.0);
+                }
+                if !plan.unresolved.is_empty() {
+                    ui.colored_label(ui.visuals().warn_fg_color, "⚠ Not found on ESOUI:");
+                    for entry in plan.unresolved.iter() {
+                        ui.label(entry);
+                    }
+                    ui.add_space(5.0);
+                }
+            });
+            ui.separator();
+            ui.horizontal(|ui| {
+                if ui
+                    .add_enabled(
+                        !plan.is_empty(),
+                        egui::Button::new(RichText::new("Apply").heading()),
+                    )
+                    .clicked()
+                {
+                    confirmed = true;
+                }
+                if ui.button(RichText::new("Cancel").heading()).clicked() {
+                    cancelled = true;
+                }
+            });
+        });
+
+        if confirmed {
+            return self.plan.take();
+        }
+        if cancelled || modal.should_close() {
+            self.plan = None;
+        }
+        None
+    }
+}
+
+fn names_ui(ui: &mut egui::Ui, heading: &str, names: impl ExactSizeIterator<Item = String>) {
+    if names.len() == 0 {
+        return;
+    }
+    ui.label(RichText::new(format!("{heading} ({})", names.len())).strong());
+    for name in names {
+        ui.label(name);
+    }
+    ui.add_space(5.0);
+}
@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::fmt;
 
 use eframe::{
@@ -9,7 +10,7 @@ use eframe::{
     epaint::Color32,
 };
 use eso_addons_core::service::AddonService;
-use eso_addons_core::service::result::{AddonShowDetails, MissingDepView};
+use eso_addons_core::service::result::{AddonShowDetails, MissingDepView, Snippet};
 use lazy_async_promise::{ImmediateValuePromise, ImmediateValueState};
 use strum_macros::EnumIter;
 
@@ -52,6 +53,7 @@ pub enum ViewOpt {
     Settings,
     Details,
     Errors,
+    Graph,
     Quit,
 }
 
@@ -152,22 +154,30 @@ impl Default for AddonResponse {
 pub struct AddonTable<'a> {
     addons: &'a Vec<&'a AddonShowDetails>,
     allow_install: bool,
+    snippets: Option<&'a HashMap<i32, Snippet>>,
 }
 impl<'a> AddonTable<'a> {
     pub fn new(addons: &'a Vec<&'a AddonShowDetails>) -> Self {
         Self {
             addons,
             allow_install: false,
+            snippets: None,
         }
     }
     pub fn installable(mut self, value: bool) -> Self {
         self.allow_install = value;
         self
     }
+    /// Shows where a search matched below the addon's name.
+    pub fn snippets(mut self, snippets: &'a HashMap<i32, Snippet>) -> Self {
+        self.snippets = Some(snippets);
+        self
+    }
     pub fn ui(&self, ui: &mut egui::Ui) -> AddonResponse {
         let Self {
             addons,
             allow_install,
+            snippets,
         } = *self;
         // let has_updateable = any(addons.iter(), |x| x.is_upgradable());
         let num_rows = addons.len();
@@ -260,7 +270,19 @@ impl<'a> AddonTable<'a> {
                             ..Default::default()
                         };
                         job.append(&addon.name, 0.0, format);
-                        if ui.selectable_label(false, job).clicked() {
+                        let snippet = snippets.and_then(|snippets| snippets.get(&addon.id));
+                        let clicked = match snippet {
+                            Some(snippet) => {
+                                ui.vertical(|ui| {
+                                    let clicked = ui.selectable_label(false, job).clicked();
+                                    ui.label(snippet_job(ui, snippet));
+                                    clicked
+                                })
+                                .inner
+                            }
+                            None => ui.selectable_label(false, job).clicked(),
+                        };
+                        if clicked {
                             response.addon_id = addon.id;
                             response.response_type = AddonResponseType::AddonName;
                         }
@@ -287,6 +309,51 @@ impl<'a> AddonTable<'a> {
     }
 }
 
+/// A search snippet on one line, with the matched words highlighted.
+fn snippet_job(ui: &egui::Ui, snippet: &Snippet) -> LayoutJob {
+    let font_id = TextStyle::Small.resolve(ui.style());
+    let mut job = LayoutJob {
+        wrap: TextWrapping {
+            max_rows: 1,
+            break_anywhere: true,
+            ..Default::default()
+        },
+        ..Default::default()
+    };
+    let label = match snippet.field.as_str() {
+        "dirs" => "Folders: ",
+        "change_log" => "Change Log: ",
+        _ => "",
+    };
+    job.append(
+        label,
+        0.0,
+        TextFormat {
+            font_id: font_id.clone(),
+            color: ui.visuals().weak_text_color(),
+            ..Default::default()
+        },
+    );
+    for (text, matched) in snippet.parts() {
+        let format = if matched {
+            TextFormat {
+                font_id: font_id.clone(),
+                color: ui.visuals().strong_text_color(),
+                background: ui.visuals().selection.bg_fill,
+                ..Default::default()
+            }
+        } else {
+            TextFormat {
+                font_id: font_id.clone(),
+                color: ui.visuals().weak_text_color(),
+                ..Default::default()
+            }
+        };
+        job.append(text, 0.0, format);
+    }
+    job
+}
+
 pub fn ui_show_star(ui: &mut egui::Ui) {
     ui.label(RichText::new("★").color(Color32::YELLOW))
         .on_hover_text("Popular! (More than 5000 downloads)");
@@ -0,0 +1,538 @@
+use std::io;
+use std::time::Duration;
+
+use eso_addons_core::config::{HmConfigUpdate, TtcConfigUpdate};
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{
+    AddonDepOption, AddonShowDetails, BackupSnapshot, InstallPlan, RemovalPlan, UpdateResult,
+};
+use lazy_async_promise::ImmediateValuePromise;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Clear, List, Paragraph, Tabs, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use tracing::info;
+
+use crate::promised::PromisedValue;
+use crate::views::details::Details;
+use crate::views::installed::Installed;
+use crate::views::missing_deps::MissingDeps;
+use crate::views::search::Search;
+use crate::views::settings::Settings;
+use crate::views::{Action, View};
+
+/// How long to wait for a key before polling the promises again
+const FRAME: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Copy, PartialEq)]
+enum Pane {
+    Installed,
+    Search,
+    MissingDeps,
+    Settings,
+}
+
+impl Pane {
+    const ALL: [Pane; 4] = [
+        Pane::Installed,
+        Pane::Search,
+        Pane::MissingDeps,
+        Pane::Settings,
+    ];
+}
+
+pub struct App {
+    service: AddonService,
+    pane: Pane,
+    /// Shown over the pane it was opened from
+    details_open: bool,
+    errors_open: bool,
+    quit: bool,
+    /// Views
+    installed: Installed,
+    search: Search,
+    details: Details,
+    missing_deps: MissingDeps,
+    settings: Settings,
+    /// Service promises
+    installed_addons: PromisedValue<Vec<AddonShowDetails>>,
+    missing_dep_options: PromisedValue<Vec<AddonDepOption>>,
+    optional_dep_options: PromisedValue<Vec<AddonDepOption>>,
+    catalog: PromisedValue<UpdateResult>,
+    ttc_pricetable: PromisedValue<TtcConfigUpdate>,
+    hm_data: PromisedValue<HmConfigUpdate>,
+    /// Plans being worked out, each one runs once it is ready
+    install_plans: Vec<PromisedValue<InstallPlan>>,
+    removal_plans: Vec<PromisedValue<RemovalPlan>>,
+    auto_backup: PromisedValue<BackupSnapshot>,
+    /// Updates waiting for the backup taken before them
+    pending_updates: Vec<i32>,
+    /// Installs, updates and removals, with what they're doing
+    jobs: Vec<(String, PromisedValue<()>)>,
+    status: String,
+}
+
+impl App {
+    pub fn new(service: AddonService) -> Self {
+        let mut app = Self {
+            service,
+            pane: Pane::Installed,
+            details_open: false,
+            errors_open: false,
+            quit: false,
+            installed: Installed::default(),
+            search: Search::default(),
+            details: Details::default(),
+            missing_deps: MissingDeps::default(),
+            settings: Settings::default(),
+            installed_addons: PromisedValue::default(),
+            missing_dep_options: PromisedValue::default(),
+            optional_dep_options: PromisedValue::default(),
+            catalog: PromisedValue::default(),
+            ttc_pricetable: PromisedValue::default(),
+            hm_data: PromisedValue::default(),
+            install_plans: vec![],
+            removal_plans: vec![],
+            auto_backup: PromisedValue::default(),
+            pending_updates: vec![],
+            jobs: vec![],
+            status: String::new(),
+        };
+        if app.service.config.update_on_launch {
+            app.check_update();
+        } else {
+            app.get_installed_addons();
+        }
+        app
+    }
+
+    pub fn run(mut self, terminal: &mut DefaultTerminal) -> io::Result<()> {
+        while !self.quit {
+            self.poll();
+            terminal.draw(|frame| self.draw(frame))?;
+            if event::poll(FRAME)?
+                && let Event::Key(key) = event::read()?
+                && key.kind == KeyEventKind::Press
+            {
+                self.handle_key(key);
+            }
+        }
+        Ok(())
+    }
+
+    /// Refresh the catalog, then the PriceTable and HarvestMap data when enabled,
+    /// like the GUI does on launch
+    fn check_update(&mut self) {
+        info!("Checking for updates");
+        self.status = "Checking for updates...".to_owned();
+        self.catalog.set(self.service.update(false));
+        if self.service.config.update_ttc_pricetable {
+            self.ttc_pricetable
+                .set(self.service.update_ttc_pricetable());
+        }
+        if self.service.config.update_hm_data {
+            self.hm_data.set(self.service.update_hm_data());
+        }
+    }
+
+    fn get_installed_addons(&mut self) {
+        self.installed_addons
+            .set(self.service.get_installed_addons());
+        self.missing_dep_options
+            .set(self.service.get_missing_dependency_options());
+        self.optional_dep_options
+            .set(self.service.get_optional_dependency_options());
+    }
+
+    fn poll(&mut self) {
+        self.catalog
+            .poll_recording(&self.service, "Updating addons");
+        if self.catalog.is_ready() {
+            self.catalog.handle();
+            self.status = "Addon catalog refreshed".to_owned();
+            self.get_installed_addons();
+        }
+        self.ttc_pricetable
+            .poll_recording(&self.service, "Updating TTC PriceTable");
+        if self.ttc_pricetable.is_ready() {
+            self.ttc_pricetable.handle();
+            if let Some(update) = self.ttc_pricetable.value.take() {
+                self.service.config.apply_ttc_update(update);
+                self.service.save_config();
+            }
+        }
+        self.hm_data
+            .poll_recording(&self.service, "Updating HarvestMap data");
+        if self.hm_data.is_ready() {
+            self.hm_data.handle();
+            if let Some(update) = self.hm_data.value.take() {
+                self.service.config.apply_hm_update(update);
+                self.service.save_config();
+            }
+        }
+
+        self.installed_addons
+            .poll_recording(&self.service, "Loading installed addons");
+        if self.installed_addons.is_ready() {
+            self.installed_addons.handle();
+            if let Some(addons) = self.installed_addons.value.take() {
+                self.installed.set_addons(addons);
+            }
+        }
+        self.missing_dep_options
+            .poll_recording(&self.service, "Checking missing dependencies");
+        if self.missing_dep_options.is_ready() {
+            self.missing_dep_options.handle();
+            if let Some(options) = self.missing_dep_options.value.take() {
+                self.missing_deps.set_deps(options);
+            }
+        }
+        self.optional_dep_options
+            .poll_recording(&self.service, "Checking optional dependencies");
+        if self.optional_dep_options.is_ready() {
+            self.optional_dep_options.handle();
+            if let Some(options) = self.optional_dep_options.value.take() {
+                self.missing_deps.set_optional_deps(options);
+            }
+        }
+
+        let mut install_plans = vec![];
+        for promise in self.install_plans.iter_mut() {
+            promise.poll_recording(&self.service, "Resolving dependencies");
+            if promise.is_ready() {
+                promise.handle();
+                install_plans.extend(promise.value.take());
+            }
+        }
+        self.install_plans.retain(|promise| promise.is_polling());
+        for plan in install_plans {
+            self.run_install_plan(plan);
+        }
+        let mut removal_plans = vec![];
+        for promise in self.removal_plans.iter_mut() {
+            promise.poll_recording(&self.service, "Checking dependents");
+            if promise.is_ready() {
+                promise.handle();
+                removal_plans.extend(promise.value.take());
+            }
+        }
+        self.removal_plans.retain(|promise| promise.is_polling());
+        for plan in removal_plans {
+            self.run_removal_plan(plan);
+        }
+        // a failed backup is recorded but doesn't hold back the update
+        self.auto_backup
+            .poll_recording(&self.service, "Backing up addon data");
+        if self.auto_backup.is_ready() {
+            self.auto_backup.handle();
+            if let Some(snapshot) = self.auto_backup.value.as_ref() {
+                info!("Backed up to {}", snapshot.path.display());
+                self.service.config.backup_last = Some(snapshot.created);
+                self.service.save_config();
+            }
+            for addon_id in std::mem::take(&mut self.pending_updates) {
+                self.update_addon(addon_id);
+            }
+        }
+
+        let mut finished = vec![];
+        for (label, job) in self.jobs.iter_mut() {
+            job.poll_recording(&self.service, label);
+            if job.is_ready() {
+                finished.push(label.clone());
+            }
+        }
+        if !finished.is_empty() {
+            self.jobs.retain(|(_, job)| !job.is_ready());
+            self.status = format!("Done: {}", finished.join(", "));
+            self.get_installed_addons();
+            self.search.refresh(&self.service);
+            if self.details_open {
+                self.details.refresh(&self.service);
+            }
+        }
+
+        match (self.details_open, self.pane) {
+            (true, _) => self.details.poll(&mut self.service),
+            (false, Pane::Search) => self.search.poll(&mut self.service),
+            _ => {}
+        }
+    }
+
+    fn run_install_plan(&mut self, plan: InstallPlan) {
+        let Some(addon) = plan.steps.last().map(|step| step.addon.clone()) else {
+            return;
+        };
+        let mut label = format!("Installing {}", addon.name);
+        if plan.steps.len() > 1 {
+            label.push_str(&format!(" with {} dependencies", plan.steps.len() - 1));
+        }
+        for dep in plan.unresolved.iter() {
+            self.service.record_error(
+                format!("Installing {}", addon.name),
+                format!(
+                    "No addon found for {} (required by {})",
+                    dep.dir, dep.required_by
+                ),
+            );
+        }
+        self.start_job(label, self.service.install_plan(plan));
+    }
+
+    /// Addons other installed addons depend on are kept, removing them is left to
+    /// the GUI or `eso-addons remove --cascade`.
+    fn run_removal_plan(&mut self, plan: RemovalPlan) {
+        if !plan.dependents.is_empty() {
+            let names: Vec<&str> = plan.dependents.iter().map(|a| a.name.as_str()).collect();
+            self.status = format!(
+                "{} is required by {}, not removed",
+                plan.addon.name,
+                names.join(", ")
+            );
+            return;
+        }
+        let label = format!("Removing {}", plan.addon.name);
+        self.start_job(label, self.service.remove_plan(plan, false));
+    }
+
+    /// Updates wait for the snapshot due before them, or the one already running.
+    fn update_addons(&mut self, addon_ids: Vec<i32>) {
+        if self.auto_backup.is_polling() {
+            self.pending_updates.extend(addon_ids);
+            return;
+        }
+        match self.service.backup_before_update(addon_ids.len()) {
+            Some(snapshot) => {
+                self.pending_updates.extend(addon_ids);
+                self.status = "Backing up before updating...".to_owned();
+                self.auto_backup.set(snapshot);
+            }
+            None => {
+                for addon_id in addon_ids {
+                    self.update_addon(addon_id);
+                }
+            }
+        }
+    }
+
+    fn update_addon(&mut self, addon_id: i32) {
+        let label = match self.installed.addon_name(addon_id) {
+            Some(name) => format!("Updating {name}"),
+            None => format!("Updating addon {addon_id}"),
+        };
+        self.start_job(label, self.service.install(addon_id, true));
+    }
+
+    fn start_job(&mut self, label: String, promise: ImmediateValuePromise<()>) {
+        let mut job = PromisedValue::default();
+        job.set(promise);
+        self.status = format!("{label}...");
+        self.jobs.push((label, job));
+    }
+
+    fn handle_action(&mut self, action: Action) {
+        match action {
+            Action::None => {}
+            Action::ShowDetails(addon_id) => {
+                self.details.open(addon_id, &self.service);
+                self.details_open = true;
+            }
+            Action::Back => self.details_open = false,
+            Action::Install(addon_id) => {
+                self.status = "Resolving dependencies...".to_owned();
+                let mut promise = PromisedValue::default();
+                promise.set(self.service.resolve_install_plan(vec![addon_id]));
+                self.install_plans.push(promise);
+            }
+            Action::Update(addon_ids) if addon_ids.is_empty() => {
+                self.status = "Everything up to date".to_owned();
+            }
+            Action::Update(addon_ids) => self.update_addons(addon_ids),
+            Action::Remove(addon_id) => {
+                let mut promise = PromisedValue::default();
+                promise.set(self.service.get_removal_plan(addon_id));
+                self.removal_plans.push(promise);
+            }
+            Action::RefreshCatalog => {
+                if !self.catalog.is_polling() {
+                    self.check_update();
+                }
+            }
+            Action::InstallDeps(picks) => {
+                let label = format!("Installing {} dependencies", picks.len());
+                self.start_job(label, self.service.install_dep_suggestions(picks));
+            }
+            Action::IgnoreDep(dir) => {
+                let label = format!("Ignoring {dir}");
+                self.start_job(label, self.service.set_dep_ignored(dir));
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) {
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.quit = true;
+            return;
+        }
+        if self.errors_open {
+            match key.code {
+                KeyCode::Char('c') => self.service.clear_errors(),
+                _ => self.errors_open = false,
+            }
+            return;
+        }
+        let view: &mut dyn View = if self.details_open {
+            &mut self.details
+        } else {
+            match self.pane {
+                Pane::Installed => &mut self.installed,
+                Pane::Search => &mut self.search,
+                Pane::MissingDeps => &mut self.missing_deps,
+                Pane::Settings => &mut self.settings,
+            }
+        };
+        // tab works while typing too, text fields don't take it
+        let editing = view.is_editing();
+        let i = Pane::ALL.iter().position(|p| *p == self.pane).unwrap_or(0);
+        let pane = match key.code {
+            KeyCode::Tab => Some(Pane::ALL[(i + 1) % Pane::ALL.len()]),
+            KeyCode::BackTab => Some(Pane::ALL[(i + Pane::ALL.len() - 1) % Pane::ALL.len()]),
+            _ if editing => None,
+            KeyCode::Char('q') => {
+                self.quit = true;
+                return;
+            }
+            KeyCode::Char('e') => {
+                self.errors_open = true;
+                return;
+            }
+            KeyCode::Char(c @ '1'..='4') => Some(Pane::ALL[c as usize - '1' as usize]),
+            _ => None,
+        };
+        if let Some(pane) = pane {
+            self.pane = pane;
+            self.details_open = false;
+            return;
+        }
+        let action = view.handle_key(key, &mut self.service);
+        self.handle_action(action);
+    }
+
+    fn draw(&mut self, frame: &mut Frame) {
+        let [tabs_area, body_area, status_area, help_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(frame.area());
+
+        let missing = self.missing_deps.missing_count();
+        let titles = Pane::ALL.iter().enumerate().map(|(i, pane)| {
+            let title = match pane {
+                Pane::Installed => match self.installed.upgradable_count() {
+                    0 => "Installed".to_owned(),
+                    updates => format!("Installed ({updates})"),
+                },
+                Pane::Search => "Search".to_owned(),
+                Pane::MissingDeps if missing > 0 => format!("Missing Deps ({missing})"),
+                Pane::MissingDeps => "Missing Deps".to_owned(),
+                Pane::Settings => "Settings".to_owned(),
+            };
+            format!("{} {title}", i + 1)
+        });
+        let selected = Pane::ALL.iter().position(|p| *p == self.pane);
+        let tabs = Tabs::new(titles)
+            .select(selected)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, tabs_area);
+
+        let view: &mut dyn View = if self.details_open {
+            &mut self.details
+        } else {
+            match self.pane {
+                Pane::Installed => &mut self.installed,
+                Pane::Search => &mut self.search,
+                Pane::MissingDeps => &mut self.missing_deps,
+                Pane::Settings => &mut self.settings,
+            }
+        };
+        view.draw(frame, body_area, &self.service);
+        let help = format!("{}  tab pane  e errors  q quit", view.help());
+
+        let errors = self.service.errors();
+        let mut status = vec![];
+        if self.is_working() {
+            status.push(Span::styled("⟳ ", Style::default().fg(Color::Cyan)));
+        }
+        status.push(Span::raw(self.status.clone()));
+        if let Some(error) = errors.last() {
+            status.push(Span::styled(
+                format!("  ✗ {} errors, last: {}", errors.len(), error.context),
+                Style::default().fg(Color::Red),
+            ));
+        }
+        frame.render_widget(Paragraph::new(Line::from(status)), status_area);
+        frame.render_widget(
+            Paragraph::new(help).style(Style::default().fg(Color::DarkGray)),
+            help_area,
+        );
+
+        if self.errors_open {
+            let area = popup_area(frame.area());
+            let items = errors.iter().rev().map(|error| {
+                format!(
+                    "{} {}: {}",
+                    error.timestamp.format("%H:%M:%S"),
+                    error.context,
+                    error.message
+                )
+            });
+            let list = List::new(items).block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .title(" Errors - c clear, any key close "),
+            );
+            frame.render_widget(Clear, area);
+            if errors.is_empty() {
+                frame.render_widget(
+                    Paragraph::new("No errors")
+                        .wrap(Wrap { trim: true })
+                        .block(Block::default().borders(Borders::ALL).title(" Errors ")),
+                    area,
+                );
+            } else {
+                frame.render_widget(list, area);
+            }
+        }
+    }
+
+    fn is_working(&self) -> bool {
+        !self.jobs.is_empty()
+            || self.catalog.is_polling()
+            || !self.install_plans.is_empty()
+            || !self.removal_plans.is_empty()
+            || self.auto_backup.is_polling()
+            || self.ttc_pricetable.is_polling()
+            || self.hm_data.is_polling()
+    }
+}
+
+/// The middle of `area`, for popups
+fn popup_area(area: Rect) -> Rect {
+    let [_, area, _] = Layout::vertical([
+        Constraint::Percentage(15),
+        Constraint::Percentage(70),
+        Constraint::Percentage(15),
+    ])
+    .areas(area);
+    let [_, area, _] = Layout::horizontal([
+        Constraint::Percentage(10),
+        Constraint::Percentage(80),
+        Constraint::Percentage(10),
+    ])
+    .areas(area);
+    area
+}
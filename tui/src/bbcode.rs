@@ -0,0 +1,262 @@
+//! Render ESOUI bbcode as terminal text.
+//!
+//! Covers the tags `bbcode-egui` renders. Links and images can't be shown or
+//! clicked, so their URLs are printed next to them; spoilers are shown expanded.
+//! Unrecognized tags render as their raw bracketed form.
+
+use bbcode::{Element, Node};
+use ratatui::layout::Alignment;
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+
+/// Renders `input` into lines for a wrapping `Paragraph`.
+pub fn to_text(input: &str) -> Text<'static> {
+    let input = input.replace("\r\n", "\n");
+    let doc = bbcode::parse(&input);
+    let mut writer = Writer::default();
+    writer.nodes(&doc.children, Style::default());
+    writer.flush();
+    Text::from(writer.lines)
+}
+
+#[derive(Default)]
+struct Writer {
+    lines: Vec<Line<'static>>,
+    current: Vec<Span<'static>>,
+    /// Prefixes of the enclosing quotes, indents and list items
+    prefix: Vec<&'static str>,
+    /// Replaces the innermost prefix on the next line, for list markers
+    marker: Option<String>,
+    alignment: Option<Alignment>,
+}
+
+impl Writer {
+    fn nodes(&mut self, nodes: &[Node<'_>], style: Style) {
+        for node in nodes {
+            match node {
+                Node::Text(text) => self.text(text, style),
+                Node::Element(e) => self.element(e, style),
+            }
+        }
+    }
+
+    fn element(&mut self, e: &Element<'_>, style: Style) {
+        match e.tag.to_ascii_lowercase().as_str() {
+            "b" => self.nodes(&e.children, style.add_modifier(Modifier::BOLD)),
+            "i" => self.nodes(&e.children, style.add_modifier(Modifier::ITALIC)),
+            "u" => self.nodes(&e.children, style.add_modifier(Modifier::UNDERLINED)),
+            "s" | "strike" => self.nodes(&e.children, style.add_modifier(Modifier::CROSSED_OUT)),
+            "color" => {
+                let style = match e.attr.and_then(parse_color) {
+                    Some(color) => style.fg(color),
+                    None => style,
+                };
+                self.nodes(&e.children, style);
+            }
+            "size" | "font" => self.nodes(&e.children, style),
+            "url" | "email" => {
+                let label = collect_text(&e.children);
+                let url = e.attr_value().unwrap_or(&label).to_owned();
+                self.nodes(&e.children, style.add_modifier(Modifier::UNDERLINED));
+                if url != label {
+                    self.push(format!(" <{url}>"), style.add_modifier(Modifier::DIM));
+                }
+            }
+            "img" => self.push(
+                format!("[image {}]", collect_text(&e.children).trim()),
+                style.add_modifier(Modifier::DIM),
+            ),
+            "youtube" => self.push(
+                format!(
+                    "[video https://youtu.be/{}]",
+                    collect_text(&e.children).trim()
+                ),
+                style.add_modifier(Modifier::DIM),
+            ),
+            "list" | "ul" | "ol" => {
+                self.flush();
+                let ordered =
+                    e.is("ol") || matches!(e.attr_value(), Some(v) if !v.is_empty() && v != "*");
+                let items = e.children.iter().filter_map(|child| match child {
+                    Node::Element(item) if item.tag == "*" || item.is("li") => Some(item),
+                    _ => None,
+                });
+                for (i, item) in items.enumerate() {
+                    self.marker = Some(if ordered {
+                        format!("{:<4}", format!("{}.", i + 1))
+                    } else {
+                        "  • ".to_owned()
+                    });
+                    self.block("    ", &item.children, style);
+                }
+            }
+            "indent" => self.block("    ", &e.children, style),
+            "quote" => self.block("│ ", &e.children, style.add_modifier(Modifier::ITALIC)),
+            "spoiler" | "blur" => {
+                self.flush();
+                self.push("Spoiler:".to_owned(), style.add_modifier(Modifier::DIM));
+                self.block("  ", &e.children, style);
+            }
+            "code" | "highlight" | "pre" => {
+                self.flush();
+                let code = collect_text(&e.children);
+                self.prefix.push("    ");
+                for line in code.trim_matches('\n').lines() {
+                    self.push(line.to_owned(), style.fg(Color::Cyan));
+                    self.flush();
+                }
+                self.prefix.pop();
+            }
+            "center" | "left" | "right" => {
+                self.flush();
+                let outer = self
+                    .alignment
+                    .replace(match e.tag.to_ascii_lowercase().as_str() {
+                        "center" => Alignment::Center,
+                        "right" => Alignment::Right,
+                        _ => Alignment::Left,
+                    });
+                self.nodes(&e.children, style);
+                self.flush();
+                self.alignment = outer;
+            }
+            _ => {
+                self.push(e.raw_open.to_owned(), style);
+                self.nodes(&e.children, style);
+            }
+        }
+    }
+
+    /// Renders `children` on lines of their own, each starting with `prefix`.
+    fn block(&mut self, prefix: &'static str, children: &[Node<'_>], style: Style) {
+        self.flush();
+        self.prefix.push(prefix);
+        self.nodes(children, style);
+        self.flush();
+        self.prefix.pop();
+    }
+
+    fn text(&mut self, text: &str, style: Style) {
+        for (i, part) in text.split('\n').enumerate() {
+            if i > 0 {
+                self.new_line();
+            }
+            if !part.is_empty() {
+                self.push(part.to_owned(), style);
+            }
+        }
+    }
+
+    fn push(&mut self, text: String, style: Style) {
+        self.current.push(Span::styled(text, style));
+    }
+
+    /// Ends the current line, if it has anything on it.
+    fn flush(&mut self) {
+        if !self.current.is_empty() {
+            self.new_line();
+        }
+    }
+
+    fn new_line(&mut self) {
+        let mut spans = vec![];
+        let prefix = match self.marker.take() {
+            Some(marker) => {
+                let outer = &self.prefix[..self.prefix.len().saturating_sub(1)];
+                outer.concat() + &marker
+            }
+            None => self.prefix.concat(),
+        };
+        if !prefix.is_empty() {
+            spans.push(Span::raw(prefix));
+        }
+        spans.append(&mut self.current);
+        let mut line = Line::from(spans);
+        if let Some(alignment) = self.alignment {
+            line = line.alignment(alignment);
+        }
+        self.lines.push(line);
+    }
+}
+
+fn collect_text(nodes: &[Node<'_>]) -> String {
+    let mut text = String::new();
+    for node in nodes {
+        match node {
+            Node::Text(s) => text.push_str(s),
+            Node::Element(e) => text.push_str(&collect_text(&e.children)),
+        }
+    }
+    text
+}
+
+/// `#rgb`, `#rrggbb` or a color name.
+fn parse_color(raw: &str) -> Option<Color> {
+    let s = bbcode::unquote(raw).trim();
+    let hex = s.strip_prefix('#').unwrap_or(s);
+    if hex.len() == 3 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 1], 16).ok().map(|c| c * 17);
+        return Some(Color::Rgb(channel(0)?, channel(1)?, channel(2)?));
+    }
+    if hex.len() == 6 && hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return format!("#{hex}").parse().ok();
+    }
+    s.to_ascii_lowercase().parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn plain(text: &Text<'_>) -> Vec<String> {
+        text.lines
+            .iter()
+            .map(|line| line.spans.iter().map(|s| s.content.as_ref()).collect())
+            .collect()
+    }
+
+    #[test]
+    fn styles_inline_tags() {
+        let text = to_text("a [b]bold[/b] [color=#ff0000]red[/color]");
+        let line = &text.lines[0];
+        assert_eq!(line.spans[1].content, "bold");
+        assert!(line.spans[1].style.add_modifier.contains(Modifier::BOLD));
+        assert_eq!(line.spans[3].style.fg, Some(Color::Rgb(255, 0, 0)));
+    }
+
+    #[test]
+    fn lists_get_markers_and_indented_lines() {
+        let text = to_text("Features:\n[list][*]one\ncontinued[*]two[/list]");
+        assert_eq!(
+            plain(&text),
+            ["Features:", "  • one", "    continued", "  • two"]
+        );
+        let text = to_text("[list=1][*]first[*]second[/list]");
+        assert_eq!(plain(&text), ["1.  first", "2.  second"]);
+    }
+
+    #[test]
+    fn quotes_nest_prefixes() {
+        let text = to_text("[quote]outer[quote]inner[/quote][/quote]");
+        assert_eq!(plain(&text), ["│ outer", "│ │ inner"]);
+    }
+
+    #[test]
+    fn links_print_their_url() {
+        let text = to_text("[url=https://www.esoui.com]ESOUI[/url] [url]https://x.y[/url]");
+        assert_eq!(plain(&text), ["ESOUI <https://www.esoui.com> https://x.y"]);
+    }
+
+    #[test]
+    fn unknown_tags_stay_visible() {
+        let text = to_text("[foo]bar[/foo]");
+        assert_eq!(plain(&text), ["[foo]bar"]);
+    }
+
+    #[test]
+    fn parses_colors() {
+        assert_eq!(parse_color("#0f0"), Some(Color::Rgb(0, 255, 0)));
+        assert_eq!(parse_color("\"Red\""), Some(Color::Red));
+        assert_eq!(parse_color("notacolor"), None);
+    }
+}
@@ -0,0 +1,38 @@
+use dotenv::dotenv;
+use eso_addons_core::config::Config;
+use eso_addons_core::service::AddonService;
+use tracing_appender::rolling::{RollingFileAppender, Rotation};
+
+use crate::app::App;
+
+mod app;
+mod bbcode;
+mod promised;
+mod views;
+
+#[tokio::main]
+async fn main() -> std::io::Result<()> {
+    dotenv().ok();
+
+    // the terminal is taken, log to the GUI's log file only
+    let file_appender = RollingFileAppender::builder()
+        .rotation(Rotation::WEEKLY)
+        .max_log_files(5)
+        .filename_prefix("eso-addons.log")
+        .build(Config::default_config_dir())
+        .expect("failed to initialize rolling file appender");
+    let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
+    tracing_subscriber::fmt()
+        .with_max_level(tracing::Level::INFO)
+        .with_ansi(false)
+        .with_writer(non_blocking)
+        .init();
+
+    // before taking over the terminal, so a failing start prints normally
+    let service = AddonService::new().await;
+
+    let mut terminal = ratatui::init();
+    let result = App::new(service).run(&mut terminal);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,50 @@
+use eso_addons_core::service::AddonService;
+use lazy_async_promise::{ImmediateValuePromise, ImmediateValueState};
+
+/// A service promise polled once per frame, like the GUI's `PromisedValue`.
+#[derive(Default)]
+pub struct PromisedValue<T: Send + Clone + Default + 'static> {
+    promise: Option<ImmediateValuePromise<T>>,
+    /// `None` until it succeeds, and after a failure
+    pub value: Option<T>,
+    done: bool,
+    handled: bool,
+}
+
+impl<T: Send + Clone + Default> PromisedValue<T> {
+    /// Polls the promise, recording a failure under `context`.
+    pub fn poll_recording(&mut self, service: &AddonService, context: &str) {
+        let Some(promise) = self.promise.as_mut() else {
+            return;
+        };
+        match promise.poll_state() {
+            ImmediateValueState::Success(value) => {
+                self.value = Some(value.clone());
+                self.promise = None;
+                self.done = true;
+            }
+            ImmediateValueState::Error(e) => {
+                service.record_error(context, &**e);
+                self.promise = None;
+                self.done = true;
+            }
+            _ => {}
+        }
+    }
+    pub fn set(&mut self, value_promise: ImmediateValuePromise<T>) {
+        self.promise = Some(value_promise);
+        self.value = None;
+        self.done = false;
+        self.handled = false;
+    }
+    pub fn is_polling(&self) -> bool {
+        self.promise.is_some()
+    }
+    /// Finished, successfully or not, and not handled yet
+    pub fn is_ready(&self) -> bool {
+        self.done && !self.handled
+    }
+    pub fn handle(&mut self) {
+        self.handled = true;
+    }
+}
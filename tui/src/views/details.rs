@@ -0,0 +1,282 @@
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{
+    AddonDependencyView, AddonShowDetails, DepStatus, Resolution,
+};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span, Text};
+use ratatui::widgets::{Block, Borders, Paragraph, Tabs, Wrap};
+
+use super::{Action, View};
+use crate::bbcode;
+use crate::promised::PromisedValue;
+
+#[derive(Clone, Copy, PartialEq, Default)]
+enum DetailView {
+    #[default]
+    Description,
+    ChangeLog,
+    Dependencies,
+}
+
+impl DetailView {
+    const ALL: [DetailView; 3] = [
+        DetailView::Description,
+        DetailView::ChangeLog,
+        DetailView::Dependencies,
+    ];
+
+    fn title(self) -> &'static str {
+        match self {
+            DetailView::Description => "Description",
+            DetailView::ChangeLog => "Change Log",
+            DetailView::Dependencies => "Dependencies",
+        }
+    }
+}
+
+/// One addon with its description, change log and dependencies.
+#[derive(Default)]
+pub struct Details {
+    addon_id: i32,
+    details: PromisedValue<Option<AddonShowDetails>>,
+    dep_view: PromisedValue<AddonDependencyView>,
+    view: DetailView,
+    description: Text<'static>,
+    change_log: Text<'static>,
+    scroll: u16,
+}
+
+impl Details {
+    /// Loads `addon_id`, fetching its details from ESOUI if they aren't cached.
+    pub fn open(&mut self, addon_id: i32, service: &AddonService) {
+        self.addon_id = addon_id;
+        self.view = DetailView::default();
+        self.scroll = 0;
+        self.description = Text::default();
+        self.change_log = Text::default();
+        self.details.set(service.get_addon_details(addon_id));
+        self.dep_view
+            .set(service.get_addon_dependency_view(addon_id));
+    }
+
+    /// Reloads after the addon was installed, updated or removed.
+    pub fn refresh(&mut self, service: &AddonService) {
+        if self.addon_id != 0 {
+            self.details.set(service.get_addon_details(self.addon_id));
+            self.dep_view
+                .set(service.get_addon_dependency_view(self.addon_id));
+        }
+    }
+
+    fn addon(&self) -> Option<&AddonShowDetails> {
+        self.details.value.as_ref().and_then(|d| d.as_ref())
+    }
+
+    fn header(addon: &AddonShowDetails) -> Text<'static> {
+        let bold = Style::default().add_modifier(Modifier::BOLD);
+        let mut name = vec![Span::styled(addon.name.clone(), bold)];
+        if addon.is_upgradable() {
+            name.push(Span::styled(
+                "  (update available)",
+                Style::default().fg(Color::Yellow),
+            ));
+        } else if addon.installed {
+            name.push(Span::styled(
+                "  (installed)",
+                Style::default().fg(Color::Green),
+            ));
+        }
+        let mut version = addon.version.clone();
+        if let Some(installed) = addon
+            .installed_version
+            .as_ref()
+            .filter(|_| addon.is_upgradable())
+        {
+            version = format!("{installed} → {version}");
+        }
+        let mut lines = vec![
+            Line::from(name),
+            Line::from(format!(
+                "by {} · {} · {} · {}",
+                addon.author_name, addon.category, version, addon.date
+            )),
+        ];
+        let mut stats = vec![];
+        if let Some(downloads) = &addon.download_total {
+            stats.push(format!("{downloads} downloads"));
+        }
+        if let Some(favorites) = &addon.favorite_total {
+            stats.push(format!("{favorites} favorites"));
+        }
+        if let Some(compat) = &addon.game_compat_name {
+            stats.push(format!("for {compat}"));
+        }
+        stats.push(addon.file_info_url.clone());
+        lines.push(Line::from(stats.join(" · ")).style(Style::default().fg(Color::DarkGray)));
+        Text::from(lines)
+    }
+
+    fn dependencies(&self) -> Text<'static> {
+        let Some(view) = self.dep_view.value.as_ref() else {
+            return Text::from("Loading...");
+        };
+        let mut lines = vec![];
+        if view.forward.is_empty() && view.optional.is_empty() {
+            lines.push(Line::from("No dependencies"));
+        }
+        for dep in view.forward.iter() {
+            lines.push(dep_line(dep, false));
+        }
+        for dep in view.optional.iter() {
+            lines.push(dep_line(dep, true));
+        }
+        if !view.dependents.is_empty() {
+            lines.push(Line::default());
+            lines.push(Line::styled(
+                "Required by",
+                Style::default().add_modifier(Modifier::BOLD),
+            ));
+            for addon in view.dependents.iter() {
+                lines.push(Line::from(format!("  {} ({})", addon.name, addon.id)));
+            }
+        }
+        Text::from(lines)
+    }
+}
+
+fn dep_line(dep: &DepStatus, optional: bool) -> Line<'static> {
+    let optional = if optional { " (optional)" } else { "" };
+    let (mark, color, note) = match &dep.resolution {
+        Resolution::Installed(addon) => ("✔", Color::Green, addon.name.clone()),
+        Resolution::SatisfiedBy(addon) => {
+            ("✔", Color::Green, format!("satisfied by {}", addon.name))
+        }
+        Resolution::Ignored => ("-", Color::DarkGray, "ignored".to_owned()),
+        Resolution::Unresolved { suggestions } if suggestions.is_empty() => {
+            ("✗", Color::Red, "missing".to_owned())
+        }
+        Resolution::Unresolved { suggestions } => {
+            let names: Vec<&str> = suggestions.iter().map(|a| a.name.as_str()).collect();
+            (
+                "✗",
+                Color::Red,
+                format!("missing, try {}", names.join(", ")),
+            )
+        }
+    };
+    Line::from(vec![
+        Span::styled(format!("{mark} "), Style::default().fg(color)),
+        Span::raw(format!("{}{optional} ", dep.dep_dir)),
+        Span::styled(format!("({note})"), Style::default().fg(Color::DarkGray)),
+    ])
+}
+
+impl View for Details {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, _service: &AddonService) {
+        let Some(addon) = self.addon() else {
+            let message = if self.details.is_polling() {
+                "Loading..."
+            } else {
+                "No details found for this addon"
+            };
+            frame.render_widget(
+                Paragraph::new(message).block(Block::default().borders(Borders::ALL)),
+                area,
+            );
+            return;
+        };
+        let [header_area, tabs_area, body_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(area);
+        frame.render_widget(Paragraph::new(Self::header(addon)), header_area);
+
+        let selected = DetailView::ALL.iter().position(|v| *v == self.view);
+        let tabs = Tabs::new(DetailView::ALL.iter().map(|v| v.title()))
+            .select(selected)
+            .highlight_style(Style::default().add_modifier(Modifier::REVERSED));
+        frame.render_widget(tabs, tabs_area);
+
+        let body = match self.view {
+            DetailView::Description => self.description.clone(),
+            DetailView::ChangeLog => self.change_log.clone(),
+            DetailView::Dependencies => self.dependencies(),
+        };
+        let paragraph = Paragraph::new(body)
+            .wrap(Wrap { trim: false })
+            .scroll((self.scroll, 0))
+            .block(Block::default().borders(Borders::TOP));
+        frame.render_widget(paragraph, body_area);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, _service: &mut AddonService) -> Action {
+        match key.code {
+            KeyCode::Esc | KeyCode::Backspace => return Action::Back,
+            KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
+                let i = DetailView::ALL
+                    .iter()
+                    .position(|v| *v == self.view)
+                    .unwrap_or(0);
+                let len = DetailView::ALL.len();
+                let i = if matches!(key.code, KeyCode::Left | KeyCode::Char('h')) {
+                    (i + len - 1) % len
+                } else {
+                    (i + 1) % len
+                };
+                self.view = DetailView::ALL[i];
+                self.scroll = 0;
+            }
+            KeyCode::Up | KeyCode::Char('k') => self.scroll = self.scroll.saturating_sub(1),
+            KeyCode::Down | KeyCode::Char('j') => self.scroll = self.scroll.saturating_add(1),
+            KeyCode::PageUp => self.scroll = self.scroll.saturating_sub(10),
+            KeyCode::PageDown => self.scroll = self.scroll.saturating_add(10),
+            KeyCode::Home => self.scroll = 0,
+            KeyCode::Char('i') => {
+                if let Some(addon) = self.addon().filter(|a| !a.installed) {
+                    return Action::Install(addon.id);
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(addon) = self.addon().filter(|a| a.is_upgradable()) {
+                    return Action::Update(vec![addon.id]);
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(addon) = self.addon().filter(|a| a.installed) {
+                    return Action::Remove(addon.id);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn poll(&mut self, service: &mut AddonService) {
+        self.details
+            .poll_recording(service, "Loading addon details");
+        if self.details.is_ready() {
+            self.details.handle();
+            let (description, change_log) = self
+                .addon()
+                .map(|a| (a.description.clone(), a.change_log.clone()))
+                .unwrap_or_default();
+            let render = |text: Option<&String>| match text {
+                Some(text) if !text.trim().is_empty() => bbcode::to_text(text),
+                _ => Text::from("None"),
+            };
+            self.description = render(description.as_ref());
+            self.change_log = render(change_log.as_ref());
+        }
+        self.dep_view
+            .poll_recording(service, "Loading addon dependencies");
+    }
+
+    fn help(&self) -> &'static str {
+        "esc back  ←/→ tab  ↑/↓ scroll  i install  u update  x remove"
+    }
+}
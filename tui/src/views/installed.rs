@@ -0,0 +1,159 @@
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::AddonShowDetails;
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::Rect;
+use ratatui::text::Line;
+use ratatui::widgets::TableState;
+
+use super::{Action, View, addon_table, clamp_selection, edit_text, move_selection};
+
+const LIBRARY_CATEGORY: &str = "Libraries";
+
+/// Installed addons, updates first and libraries after the addons using them.
+#[derive(Default)]
+pub struct Installed {
+    addons: Vec<AddonShowDetails>,
+    filter: String,
+    editing: bool,
+    hide_libraries: bool,
+    state: TableState,
+}
+
+impl Installed {
+    pub fn set_addons(&mut self, mut addons: Vec<AddonShowDetails>) {
+        addons.sort_by_cached_key(|a| {
+            (
+                !a.is_upgradable(),
+                a.category == LIBRARY_CATEGORY,
+                a.name.to_lowercase(),
+            )
+        });
+        self.addons = addons;
+        let len = self.displayed().len();
+        clamp_selection(&mut self.state, len);
+    }
+
+    pub fn addon_name(&self, addon_id: i32) -> Option<&str> {
+        self.addons
+            .iter()
+            .find(|a| a.id == addon_id)
+            .map(|a| a.name.as_str())
+    }
+
+    pub fn upgradable_count(&self) -> usize {
+        self.addons.iter().filter(|a| a.is_upgradable()).count()
+    }
+
+    fn displayed(&self) -> Vec<&AddonShowDetails> {
+        filter_addons(&self.addons, &self.filter, self.hide_libraries)
+    }
+
+    fn selected(&self) -> Option<&AddonShowDetails> {
+        self.state
+            .selected()
+            .and_then(|i| self.displayed().get(i).copied())
+    }
+}
+
+fn filter_addons<'a>(
+    addons: &'a [AddonShowDetails],
+    filter: &str,
+    hide_libraries: bool,
+) -> Vec<&'a AddonShowDetails> {
+    let filter = filter.to_lowercase();
+    addons
+        .iter()
+        .filter(|a| a.name.to_lowercase().contains(&filter))
+        // while filtering, libraries are searched too
+        .filter(|a| !hide_libraries || !filter.is_empty() || a.category != LIBRARY_CATEGORY)
+        .collect()
+}
+
+impl View for Installed {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, _service: &AddonService) {
+        let addons = filter_addons(&self.addons, &self.filter, self.hide_libraries);
+        let mut title = format!(" Installed - {} addons", addons.len());
+        let updates = self.upgradable_count();
+        if updates > 0 {
+            title.push_str(&format!(", {updates} updates"));
+        }
+        if self.editing || !self.filter.is_empty() {
+            title.push_str(&format!(" - filter: {}", self.filter));
+            if self.editing {
+                title.push('▏');
+            }
+        }
+        title.push(' ');
+        let table = addon_table(&addons, Line::from(title));
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, _service: &mut AddonService) -> Action {
+        if self.editing {
+            match key.code {
+                KeyCode::Enter => self.editing = false,
+                KeyCode::Esc => {
+                    self.editing = false;
+                    self.filter.clear();
+                }
+                code => {
+                    edit_text(&mut self.filter, code);
+                }
+            }
+            let len = self.displayed().len();
+            clamp_selection(&mut self.state, len);
+            return Action::None;
+        }
+        let len = self.displayed().len();
+        if move_selection(&mut self.state, len, key.code) {
+            return Action::None;
+        }
+        match key.code {
+            KeyCode::Char('/') => self.editing = true,
+            KeyCode::Char('l') => {
+                self.hide_libraries = !self.hide_libraries;
+                let len = self.displayed().len();
+                clamp_selection(&mut self.state, len);
+            }
+            KeyCode::Char('r') => return Action::RefreshCatalog,
+            KeyCode::Char('U') => {
+                return Action::Update(
+                    self.addons
+                        .iter()
+                        .filter(|a| a.is_upgradable())
+                        .map(|a| a.id)
+                        .collect(),
+                );
+            }
+            KeyCode::Enter => {
+                if let Some(addon) = self.selected() {
+                    return Action::ShowDetails(addon.id);
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(addon) = self.selected().filter(|a| a.is_upgradable()) {
+                    return Action::Update(vec![addon.id]);
+                }
+            }
+            KeyCode::Char('x') => {
+                if let Some(addon) = self.selected() {
+                    return Action::Remove(addon.id);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    fn help(&self) -> &'static str {
+        if self.editing {
+            return "enter done  esc clear";
+        }
+        "enter details  u update  U update all  x remove  r refresh  / filter  l libraries"
+    }
+}
@@ -0,0 +1,201 @@
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonDepOption, AddonRef};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Row, Table, TableState};
+
+use super::{Action, View, clamp_selection, move_selection};
+
+/// A dir installed addons require that nothing installed provides.
+struct MissingDep {
+    missing_dir: String,
+    required_by: String,
+    /// Catalog addons providing the dir, best first
+    candidates: Vec<AddonRef>,
+    /// Index into `candidates` of the one to install
+    picked: usize,
+}
+
+/// Missing dependencies with the addons that could provide them. Optional ones
+/// are listed on their own, like in the GUI they're opt-in only.
+#[derive(Default)]
+pub struct MissingDeps {
+    missing: Vec<MissingDep>,
+    optional: Vec<MissingDep>,
+    show_optional: bool,
+    state: TableState,
+}
+
+impl MissingDeps {
+    pub fn set_deps(&mut self, options: Vec<AddonDepOption>) {
+        self.missing = group(options);
+        let len = self.deps().len();
+        clamp_selection(&mut self.state, len);
+    }
+
+    pub fn set_optional_deps(&mut self, options: Vec<AddonDepOption>) {
+        self.optional = group(options);
+        let len = self.deps().len();
+        clamp_selection(&mut self.state, len);
+    }
+
+    pub fn missing_count(&self) -> usize {
+        self.missing.len()
+    }
+
+    fn deps(&self) -> &[MissingDep] {
+        if self.show_optional {
+            &self.optional
+        } else {
+            &self.missing
+        }
+    }
+
+    fn selected(&self) -> Option<&MissingDep> {
+        self.state.selected().and_then(|i| self.deps().get(i))
+    }
+}
+
+/// Groups the service's rows, one per dir and candidate, keeping its ranking.
+fn group(options: Vec<AddonDepOption>) -> Vec<MissingDep> {
+    let mut deps: Vec<MissingDep> = vec![];
+    for option in options {
+        if deps
+            .last()
+            .is_none_or(|dep| dep.missing_dir != option.missing_dir)
+        {
+            deps.push(MissingDep {
+                missing_dir: option.missing_dir.clone(),
+                required_by: option.required_by.clone(),
+                candidates: vec![],
+                picked: 0,
+            });
+        }
+        if let (Some(id), Some(name), Some(dep)) =
+            (option.option_id, option.option_name, deps.last_mut())
+            && !dep.candidates.iter().any(|a| a.id == id)
+        {
+            dep.candidates.push(AddonRef { id, name });
+        }
+    }
+    deps
+}
+
+impl View for MissingDeps {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, _service: &AddonService) {
+        let rows = self.deps().iter().map(|dep| {
+            let candidate = match dep.candidates.get(dep.picked) {
+                Some(addon) if dep.candidates.len() > 1 => Cell::from(format!(
+                    "◂ {} ({}/{}) ▸",
+                    addon.name,
+                    dep.picked + 1,
+                    dep.candidates.len()
+                )),
+                Some(addon) => Cell::from(addon.name.clone()),
+                None => Cell::from("no addon on ESOUI provides it")
+                    .style(Style::default().fg(Color::Red)),
+            };
+            Row::new(vec![
+                Cell::from(dep.missing_dir.clone()),
+                Cell::from(dep.required_by.clone()),
+                candidate,
+            ])
+        });
+        let title = if self.show_optional {
+            format!(" Optional Dependencies - {} ", self.optional.len())
+        } else {
+            format!(" Missing Dependencies - {} ", self.missing.len())
+        };
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(1),
+                Constraint::Fill(2),
+                Constraint::Fill(2),
+            ],
+        )
+        .header(
+            Row::new(["Dir", "Required by", "Install"])
+                .style(Style::default().add_modifier(Modifier::BOLD)),
+        )
+        .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+        .block(
+            Block::default()
+                .borders(Borders::ALL)
+                .title(Line::from(title)),
+        );
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, _service: &mut AddonService) -> Action {
+        let len = self.deps().len();
+        if move_selection(&mut self.state, len, key.code) {
+            return Action::None;
+        }
+        match key.code {
+            KeyCode::Left | KeyCode::Right | KeyCode::Char('h') | KeyCode::Char('l') => {
+                let forward = matches!(key.code, KeyCode::Right | KeyCode::Char('l'));
+                let Some(i) = self.state.selected() else {
+                    return Action::None;
+                };
+                let deps = if self.show_optional {
+                    &mut self.optional
+                } else {
+                    &mut self.missing
+                };
+                if let Some(dep) = deps.get_mut(i).filter(|d| !d.candidates.is_empty()) {
+                    let len = dep.candidates.len();
+                    dep.picked = if forward {
+                        (dep.picked + 1) % len
+                    } else {
+                        (dep.picked + len - 1) % len
+                    };
+                }
+            }
+            KeyCode::Char('o') => {
+                self.show_optional = !self.show_optional;
+                self.state.select(None);
+                let len = self.deps().len();
+                clamp_selection(&mut self.state, len);
+            }
+            KeyCode::Enter | KeyCode::Char('i') => {
+                if let Some(dep) = self.selected()
+                    && let Some(addon) = dep.candidates.get(dep.picked)
+                {
+                    return Action::InstallDeps(vec![(dep.missing_dir.clone(), addon.id)]);
+                }
+            }
+            // optional dependencies are never all installed at once
+            KeyCode::Char('I') if !self.show_optional => {
+                let picks: Vec<(String, i32)> = self
+                    .missing
+                    .iter()
+                    .filter_map(|dep| {
+                        let addon = dep.candidates.get(dep.picked)?;
+                        Some((dep.missing_dir.clone(), addon.id))
+                    })
+                    .collect();
+                if !picks.is_empty() {
+                    return Action::InstallDeps(picks);
+                }
+            }
+            KeyCode::Char('n') => {
+                if let Some(dep) = self.selected() {
+                    return Action::IgnoreDep(dep.missing_dir.clone());
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn help(&self) -> &'static str {
+        if self.show_optional {
+            return "enter install  ←/→ pick addon  n ignore  o required";
+        }
+        "enter install  I install all  ←/→ pick addon  n ignore  o optional"
+    }
+}
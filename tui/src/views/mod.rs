@@ -0,0 +1,149 @@
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::AddonShowDetails;
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::Line;
+use ratatui::widgets::{Block, Borders, Cell, Row, Table, TableState};
+
+pub mod details;
+pub mod installed;
+pub mod missing_deps;
+pub mod search;
+pub mod settings;
+
+/// What a view asks the app to do in response to a key, like the GUI's
+/// `AddonResponse`.
+#[derive(Debug, PartialEq, Default)]
+pub enum Action {
+    #[default]
+    None,
+    ShowDetails(i32),
+    /// Close the details pane
+    Back,
+    /// Install with the dependencies it requires
+    Install(i32),
+    Update(Vec<i32>),
+    Remove(i32),
+    RefreshCatalog,
+    /// Install the picked addon for each missing dir
+    InstallDeps(Vec<(String, i32)>),
+    IgnoreDep(String),
+}
+
+pub trait View {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, service: &AddonService);
+    fn handle_key(&mut self, key: KeyEvent, service: &mut AddonService) -> Action;
+    /// Polls the view's own promises, once per frame
+    fn poll(&mut self, _service: &mut AddonService) {}
+    /// Typing into a text field, the app's own keys are off
+    fn is_editing(&self) -> bool {
+        false
+    }
+    /// The view's keys, for the footer
+    fn help(&self) -> &'static str;
+}
+
+/// Addons with their version, an update shown as `installed → latest`.
+pub fn addon_table<'a>(addons: &[&'a AddonShowDetails], title: Line<'a>) -> Table<'a> {
+    let rows = addons.iter().map(|addon| {
+        let version = match &addon.installed_version {
+            Some(installed) if addon.is_upgradable() => {
+                Cell::from(format!("{installed} → {}", addon.version))
+                    .style(Style::default().fg(Color::Yellow))
+            }
+            Some(installed) => Cell::from(installed.as_str()),
+            None => Cell::from(addon.version.as_str()),
+        };
+        let name = if addon.installed {
+            Cell::from(format!("✔ {}", addon.name)).style(Style::default().fg(Color::Green))
+        } else {
+            Cell::from(format!("  {}", addon.name))
+        };
+        Row::new(vec![
+            name,
+            Cell::from(addon.author_name.as_str()),
+            Cell::from(addon.category.as_str()),
+            version,
+        ])
+    });
+    Table::new(
+        rows,
+        [
+            Constraint::Fill(3),
+            Constraint::Fill(1),
+            Constraint::Fill(1),
+            Constraint::Fill(2),
+        ],
+    )
+    .header(
+        Row::new(["  Name", "Author", "Category", "Version"])
+            .style(Style::default().add_modifier(Modifier::BOLD)),
+    )
+    .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+    .block(Block::default().borders(Borders::ALL).title(title))
+}
+
+/// Moves the selected row of a `len` row table by up/down, page and home/end keys,
+/// `false` for other keys.
+pub fn move_selection(state: &mut TableState, len: usize, key: KeyCode) -> bool {
+    if len == 0 {
+        state.select(None);
+        return matches!(
+            key,
+            KeyCode::Up | KeyCode::Down | KeyCode::Char('k') | KeyCode::Char('j')
+        );
+    }
+    let selected = state.selected().unwrap_or(0).min(len - 1);
+    let next = match key {
+        KeyCode::Up | KeyCode::Char('k') => selected.saturating_sub(1),
+        KeyCode::Down | KeyCode::Char('j') => (selected + 1).min(len - 1),
+        KeyCode::PageUp => selected.saturating_sub(10),
+        KeyCode::PageDown => (selected + 10).min(len - 1),
+        KeyCode::Home => 0,
+        KeyCode::End => len - 1,
+        _ => return false,
+    };
+    state.select(Some(next));
+    true
+}
+
+/// Keeps the selection on a row after the rows changed.
+pub fn clamp_selection(state: &mut TableState, len: usize) {
+    state.select(match (state.selected(), len) {
+        (_, 0) => None,
+        (Some(i), len) => Some(i.min(len - 1)),
+        (None, _) => Some(0),
+    });
+}
+
+/// Edits `text` with a typed key, `false` for keys that aren't editing.
+pub fn edit_text(text: &mut String, key: KeyCode) -> bool {
+    match key {
+        KeyCode::Char(c) => text.push(c),
+        KeyCode::Backspace => {
+            text.pop();
+        }
+        _ => return false,
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selection_stays_in_bounds() {
+        let mut state = TableState::default();
+        move_selection(&mut state, 3, KeyCode::Up);
+        assert_eq!(state.selected(), Some(0));
+        move_selection(&mut state, 3, KeyCode::PageDown);
+        assert_eq!(state.selected(), Some(2));
+        clamp_selection(&mut state, 1);
+        assert_eq!(state.selected(), Some(0));
+        clamp_selection(&mut state, 0);
+        assert_eq!(state.selected(), None);
+    }
+}
@@ -0,0 +1,169 @@
+use eso_addons_core::service::AddonService;
+use eso_addons_core::service::result::{AddonShowDetails, SearchResult};
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Borders, Paragraph, TableState};
+
+use super::{Action, View, addon_table, clamp_selection, edit_text, move_selection};
+use crate::promised::PromisedValue;
+
+/// Full-text search of the catalog, showing where the selected addon matched.
+pub struct Search {
+    query: String,
+    editing: bool,
+    results: PromisedValue<Vec<SearchResult>>,
+    state: TableState,
+}
+
+impl Default for Search {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            // typing starts right away when the pane opens
+            editing: true,
+            results: PromisedValue::default(),
+            state: TableState::default(),
+        }
+    }
+}
+
+impl Search {
+    /// Runs the search again, after installs change what's installed.
+    pub fn refresh(&mut self, service: &AddonService) {
+        if !self.query.is_empty() {
+            self.results.set(service.search(self.query.clone()));
+        }
+    }
+
+    fn results(&self) -> &[SearchResult] {
+        self.results.value.as_deref().unwrap_or_default()
+    }
+
+    fn selected(&self) -> Option<&AddonShowDetails> {
+        self.state
+            .selected()
+            .and_then(|i| self.results().get(i))
+            .map(|r| &r.addon)
+    }
+
+    /// Where the selected addon matched, when it wasn't its name, author or category.
+    fn snippet_line(&self) -> Option<Line<'static>> {
+        let i = self.state.selected()?;
+        let snippet = self.results().get(i)?.snippet.as_ref()?;
+        let dim = Style::default().fg(Color::DarkGray);
+        let matched = Style::default()
+            .fg(Color::Yellow)
+            .add_modifier(Modifier::BOLD);
+        let field = match snippet.field.as_str() {
+            "dirs" => "Folders",
+            "change_log" => "Change Log",
+            _ => "Description",
+        };
+        let mut spans = vec![Span::styled(format!(" {field}: "), dim)];
+        for (text, is_match) in snippet.parts() {
+            spans.push(Span::styled(
+                text.to_owned(),
+                if is_match { matched } else { dim },
+            ));
+        }
+        Some(Line::from(spans))
+    }
+}
+
+impl View for Search {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, _service: &AddonService) {
+        let snippet = self.snippet_line();
+        let [input_area, results_area, snippet_area] = Layout::vertical([
+            Constraint::Length(3),
+            Constraint::Fill(1),
+            Constraint::Length(snippet.is_some() as u16),
+        ])
+        .areas(area);
+        let cursor = if self.editing { "▏" } else { "" };
+        let input = Paragraph::new(format!("{}{cursor}", self.query))
+            .block(Block::default().borders(Borders::ALL).title(" Search "));
+        frame.render_widget(input, input_area);
+
+        let title = if self.results.is_polling() {
+            " Searching... ".to_owned()
+        } else {
+            format!(" {} results ", self.results().len())
+        };
+        let addons: Vec<&AddonShowDetails> = self
+            .results
+            .value
+            .iter()
+            .flatten()
+            .map(|r| &r.addon)
+            .collect();
+        let table = addon_table(&addons, Line::from(title));
+        frame.render_stateful_widget(table, results_area, &mut self.state);
+        if let Some(snippet) = snippet {
+            frame.render_widget(Paragraph::new(snippet), snippet_area);
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, service: &mut AddonService) -> Action {
+        if self.editing {
+            match key.code {
+                KeyCode::Enter if !self.query.is_empty() => {
+                    self.editing = false;
+                    self.state.select(Some(0));
+                    self.results.set(service.search(self.query.clone()));
+                }
+                KeyCode::Esc | KeyCode::Down => self.editing = false,
+                code => {
+                    edit_text(&mut self.query, code);
+                }
+            }
+            return Action::None;
+        }
+        let len = self.results().len();
+        if move_selection(&mut self.state, len, key.code) {
+            return Action::None;
+        }
+        match key.code {
+            KeyCode::Char('/') => self.editing = true,
+            KeyCode::Enter => {
+                if let Some(addon) = self.selected() {
+                    return Action::ShowDetails(addon.id);
+                }
+            }
+            KeyCode::Char('i') => {
+                if let Some(addon) = self.selected().filter(|a| !a.installed) {
+                    return Action::Install(addon.id);
+                }
+            }
+            KeyCode::Char('u') => {
+                if let Some(addon) = self.selected().filter(|a| a.is_upgradable()) {
+                    return Action::Update(vec![addon.id]);
+                }
+            }
+            _ => {}
+        }
+        Action::None
+    }
+
+    fn poll(&mut self, service: &mut AddonService) {
+        self.results.poll_recording(service, "Searching addons");
+        if self.results.is_ready() {
+            self.results.handle();
+            let len = self.results().len();
+            clamp_selection(&mut self.state, len);
+        }
+    }
+
+    fn is_editing(&self) -> bool {
+        self.editing
+    }
+
+    fn help(&self) -> &'static str {
+        if self.editing {
+            return "enter search  esc/↓ results";
+        }
+        "enter details  i install  u update  / search"
+    }
+}
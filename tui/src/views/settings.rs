@@ -0,0 +1,178 @@
+use std::path::PathBuf;
+
+use eso_addons_core::config::{Config, TTCRegion};
+use eso_addons_core::service::AddonService;
+use ratatui::Frame;
+use ratatui::crossterm::event::{KeyCode, KeyEvent};
+use ratatui::layout::{Constraint, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::widgets::{Block, Borders, Cell, Row, Table, TableState};
+
+use super::{Action, View, edit_text, move_selection};
+
+#[derive(Clone, Copy)]
+enum Setting {
+    AddonDir,
+    UpdateOnLaunch,
+    TtcRegion,
+    UpdateTtc,
+    UpdateHm,
+    AutoBackup,
+    BackupInterval,
+    BackupKeep,
+}
+
+impl Setting {
+    const ALL: [Setting; 8] = [
+        Setting::AddonDir,
+        Setting::UpdateOnLaunch,
+        Setting::TtcRegion,
+        Setting::UpdateTtc,
+        Setting::UpdateHm,
+        Setting::AutoBackup,
+        Setting::BackupInterval,
+        Setting::BackupKeep,
+    ];
+
+    fn label(self) -> &'static str {
+        match self {
+            Setting::AddonDir => "AddOns folder",
+            Setting::UpdateOnLaunch => "Check for addon updates on launch",
+            Setting::TtcRegion => "TTC Region",
+            Setting::UpdateTtc => "Update TTC PriceTable",
+            Setting::UpdateHm => "Update HarvestMap data",
+            Setting::AutoBackup => "Automatic backups",
+            Setting::BackupInterval => "Back up every (days, 0 only before bulk updates)",
+            Setting::BackupKeep => "Backups kept",
+        }
+    }
+
+    fn value(self, config: &Config) -> String {
+        let check = |on: bool| if on { "[x]" } else { "[ ]" }.to_owned();
+        match self {
+            Setting::AddonDir => config.addon_dir.display().to_string(),
+            Setting::UpdateOnLaunch => check(config.update_on_launch),
+            Setting::TtcRegion => format!("{:?}", config.ttc_region),
+            Setting::UpdateTtc => check(config.update_ttc_pricetable),
+            Setting::UpdateHm => check(config.update_hm_data),
+            Setting::AutoBackup => check(config.auto_backup),
+            Setting::BackupInterval => config.backup_interval_days.to_string(),
+            Setting::BackupKeep => config.backup_keep.to_string(),
+        }
+    }
+
+    /// Toggles or steps the setting, `false` if the key doesn't change it.
+    fn change(self, config: &mut Config, key: KeyCode) -> bool {
+        let toggle = matches!(key, KeyCode::Enter | KeyCode::Char(' '));
+        let step: i64 = match key {
+            KeyCode::Right | KeyCode::Char('l') | KeyCode::Char('+') => 1,
+            KeyCode::Left | KeyCode::Char('h') | KeyCode::Char('-') => -1,
+            _ => 0,
+        };
+        match self {
+            Setting::AddonDir => return false,
+            Setting::UpdateOnLaunch if toggle => config.update_on_launch = !config.update_on_launch,
+            Setting::UpdateTtc if toggle => {
+                config.update_ttc_pricetable = !config.update_ttc_pricetable
+            }
+            Setting::UpdateHm if toggle => config.update_hm_data = !config.update_hm_data,
+            Setting::AutoBackup if toggle => config.auto_backup = !config.auto_backup,
+            Setting::TtcRegion if toggle || step != 0 => {
+                let regions = [TTCRegion::NA, TTCRegion::EU, TTCRegion::ALL];
+                let i = regions
+                    .iter()
+                    .position(|r| *r == config.ttc_region)
+                    .unwrap_or(0) as i64;
+                config.ttc_region =
+                    regions[(i + step.max(toggle as i64)).rem_euclid(3) as usize].clone();
+            }
+            Setting::BackupInterval if step != 0 => {
+                config.backup_interval_days = config
+                    .backup_interval_days
+                    .saturating_add_signed(step as i32)
+            }
+            Setting::BackupKeep if step != 0 => {
+                config.backup_keep = config
+                    .backup_keep
+                    .saturating_add_signed(step as isize)
+                    .max(1)
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+/// Settings of the GUI's Settings view that make sense without a display,
+/// saved as soon as they change.
+#[derive(Default)]
+pub struct Settings {
+    state: TableState,
+    /// The AddOns folder being typed, `None` when not editing it
+    addon_dir: Option<String>,
+}
+
+impl Settings {
+    fn selected(&self) -> Setting {
+        Setting::ALL[self.state.selected().unwrap_or(0)]
+    }
+}
+
+impl View for Settings {
+    fn draw(&mut self, frame: &mut Frame, area: Rect, service: &AddonService) {
+        if self.state.selected().is_none() {
+            self.state.select(Some(0));
+        }
+        let rows = Setting::ALL.iter().map(|setting| {
+            let value = match (setting, self.addon_dir.as_ref()) {
+                (Setting::AddonDir, Some(typed)) => {
+                    Cell::from(format!("{typed}▏")).style(Style::default().fg(Color::Yellow))
+                }
+                _ => Cell::from(setting.value(&service.config)),
+            };
+            Row::new(vec![Cell::from(setting.label()), value])
+        });
+        let table = Table::new(rows, [Constraint::Fill(1), Constraint::Fill(1)])
+            .row_highlight_style(Style::default().add_modifier(Modifier::REVERSED))
+            .block(Block::default().borders(Borders::ALL).title(" Settings "));
+        frame.render_stateful_widget(table, area, &mut self.state);
+    }
+
+    fn handle_key(&mut self, key: KeyEvent, service: &mut AddonService) -> Action {
+        if let Some(addon_dir) = self.addon_dir.as_mut() {
+            match key.code {
+                KeyCode::Enter => {
+                    service.config.addon_dir = PathBuf::from(addon_dir.trim());
+                    service.save_config();
+                    self.addon_dir = None;
+                }
+                KeyCode::Esc => self.addon_dir = None,
+                code => {
+                    edit_text(addon_dir, code);
+                }
+            }
+            return Action::None;
+        }
+        if move_selection(&mut self.state, Setting::ALL.len(), key.code) {
+            return Action::None;
+        }
+        let setting = self.selected();
+        if matches!(setting, Setting::AddonDir) && key.code == KeyCode::Enter {
+            self.addon_dir = Some(service.config.addon_dir.display().to_string());
+        } else if setting.change(&mut service.config, key.code) {
+            service.save_config();
+        }
+        Action::None
+    }
+
+    fn is_editing(&self) -> bool {
+        self.addon_dir.is_some()
+    }
+
+    fn help(&self) -> &'static str {
+        if self.addon_dir.is_some() {
+            return "enter save  esc cancel";
+        }
+        "enter/space toggle  ←/→ change"
+    }
+}